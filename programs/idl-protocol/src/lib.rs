@@ -1,9 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 declare_id!("BSn7neicVV2kEzgaZmd6tZEBm4tdgzBRyELov65Lq7dt");
 
 // ==================== CONSTANTS ====================
+// Economic parameters here are ProtocolConfig defaults; instructions read the live values from config.
 
 pub const MAX_LOCK_DURATION: i64 = 126144000; // 4 years in seconds
 pub const MIN_LOCK_DURATION: i64 = 604800; // 1 week minimum
@@ -41,10 +43,21 @@ pub const MAX_STAKE_BONUS_BPS: u64 = 5000; // 50% max
 pub const MARKET_STATUS_ACTIVE: u8 = 0;
 pub const MARKET_STATUS_RESOLVED: u8 = 1;
 pub const MARKET_STATUS_CANCELLED: u8 = 2;
+pub const MARKET_STATUS_DISPUTED: u8 = 3; // Resolution under veIDL dispute vote
+
+// Market types
+pub const MARKET_TYPE_BINARY: u8 = 0;
+pub const MARKET_TYPE_CATEGORICAL: u8 = 1;
+pub const MARKET_TYPE_SCALAR: u8 = 2;
+pub const MARKET_TYPE_COMPARISON: u8 = 3; // YES = protocol A beats B; ties cancel and refund
+pub const MAX_MARKET_OUTCOMES: usize = 8; // Max outcomes in a categorical market
 
 // Authority timelock
 pub const AUTHORITY_TIMELOCK: i64 = 172800; // 48 hours
 
+// Event schema version, carried in every event. Bumped on breaking field changes.
+pub const EVENT_VERSION: u8 = 1;
+
 // Minimum target value to prevent trivial markets
 pub const MIN_TARGET_VALUE: u64 = 1;
 
@@ -77,6 +90,7 @@ pub const MIN_STAKE_AMOUNT: u64 = 100_000_000; // 0.1 tokens minimum stake (prev
 pub const MIN_ORACLE_CONSENSUS: u8 = 2; // Minimum 2 oracles must agree
 pub const MAX_ORACLES_PER_MARKET: u8 = 5; // Maximum oracles per market
 pub const ORACLE_CONSENSUS_THRESHOLD: u8 = 67; // 67% must agree on outcome
+pub const ORACLE_REVEAL_DEADLINE: i64 = 86400; // Finalize with missing reveals 24h after resolution time
 
 // ═══════════════════════════════════════════════════════════════════════════════
 // NOVEL ATTACK FIXES - Protecting against newly discovered vulnerabilities
@@ -92,6 +106,10 @@ pub const BATCH_REVEAL_DELAY: i64 = 600; // All reveals hidden for 10 min after
 // FIX: DISPUTE_GRIEF - Require bond to dispute
 pub const DISPUTE_BOND_AMOUNT: u64 = 5_000_000_000; // 5 tokens to dispute (slashed if frivolous)
 pub const DISPUTE_SLASH_IF_INVALID: u64 = 100; // 100% slash if dispute rejected
+pub const DISPUTE_VOTE_PERIOD: i64 = 259200; // 3 days of veIDL voting per dispute
+pub const DISPUTE_CHOICE_UPHOLD: u8 = 0;
+pub const DISPUTE_CHOICE_OVERTURN: u8 = 1; // Flip to the opposite outcome (two-outcome markets only)
+pub const DISPUTE_CHOICE_CANCEL: u8 = 2;
 
 // FIX: MARKET_SPAM - Rate limit market creation
 pub const MARKET_CREATION_COOLDOWN: i64 = 3600; // 1 hour between market creations per user
@@ -142,6 +160,7 @@ pub const VIP_TIER_2_STAKE: u64 = 1_000_000_000_000; // 1k tokens = Silver VIP
 pub const VIP_TIER_3_STAKE: u64 = 10_000_000_000_000; // 10k tokens = Gold VIP
 pub const VIP_TIER_4_STAKE: u64 = 100_000_000_000_000; // 100k tokens = Platinum VIP
 pub const VIP_FEE_DISCOUNT_BPS: u64 = 50; // 0.5% fee discount per tier
+pub const PROTOCOL_FEE_FLOOR_BPS: u64 = 100; // Discounts never take a fee below 1%
 
 // AUTO-COMPOUND - Reinvest rewards automatically
 pub const AUTO_COMPOUND_BONUS_BPS: u64 = 200; // 2% bonus for auto-compounding
@@ -149,6 +168,7 @@ pub const AUTO_COMPOUND_BONUS_BPS: u64 = 200; // 2% bonus for auto-compounding
 // SEASON REWARDS - Time-limited bonus periods
 pub const SEASON_DURATION: i64 = 2592000; // 30 days per season
 pub const SEASON_BONUS_BPS: u64 = 2500; // 25% bonus during active season
+pub const LEADERBOARD_MAX_RANKS: usize = 10; // Top 10 predictors share the prize pool
 
 // EARLY BIRD - Bonus for early bettors
 pub const EARLY_BIRD_WINDOW: i64 = 3600; // First hour after market creation
@@ -196,10 +216,18 @@ pub const LIMIT_ORDER_FEE_BPS: u64 = 50; // 0.5% fee for limit orders
 // STOP LOSS - Auto-exit
 pub const STOP_LOSS_CHECK_INTERVAL: i64 = 300; // Check every 5 minutes
 pub const STOP_LOSS_MIN_THRESHOLD: u64 = 1000; // Min 10% loss to trigger
+pub const STOP_LOSS_KEEPER_REWARD_BPS: u64 = 50; // 0.5% of payout to the triggering keeper
 
 // HEDGING - Auto-inverse markets
 pub const HEDGE_MARKET_FEE_BPS: u64 = 100; // 1% fee for hedge markets
 
+// GOVERNANCE - veIDL proposals
+pub const PROPOSAL_MIN_VOTING_POWER: u64 = 10_000_000_000; // 10 veIDL to open a proposal
+pub const PROPOSAL_VOTING_PERIOD: i64 = 432000; // 5 days of voting
+pub const PROPOSAL_QUORUM_BPS: u64 = 1000; // 10% of veIDL supply must vote
+pub const PROPOSAL_PASS_THRESHOLD_BPS: u64 = 5000; // More than 50% of votes in favour
+pub const PROPOSAL_EXECUTION_DELAY: i64 = 172800; // 48 hours between vote end and execution
+
 #[program]
 pub mod idl_protocol {
     use super::*;
@@ -240,11 +268,24 @@ pub mod idl_protocol {
         state.vault = ctx.accounts.vault.key();
         state.vault_bump = ctx.bumps.vault;
 
-        msg!("Vault initialized - call init_burn_vault next");
+        msg!("Vault initialized - call init_config next");
+        Ok(())
+    }
+
+    /// Step 3: Initialize protocol config with the default parameters
+    pub fn init_config(ctx: Context<InitConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.params = ConfigParams::default();
+        config.params.validate()?;
+        config.pending_params = None;
+        config.update_time = None;
+        config.bump = ctx.bumps.config;
+
+        msg!("Protocol config initialized - call init_burn_vault next");
         Ok(())
     }
 
-    /// Step 3: Initialize burn vault and activate protocol
+    /// Step 4: Initialize burn vault and activate protocol
     pub fn init_burn_vault(ctx: Context<InitBurnVault>) -> Result<()> {
         let state = &mut ctx.accounts.state;
         require!(state.vault != Pubkey::default(), IdlError::VaultNotInitialized);
@@ -253,16 +294,32 @@ pub mod idl_protocol {
         state.burn_vault_bump = ctx.bumps.burn_vault;
         state.paused = false; // Now ready
 
+        emit!(ProtocolInitialized {
+            version: EVENT_VERSION,
+            authority: state.authority,
+            idl_mint: state.idl_mint,
+            vault: state.vault,
+            burn_vault: state.burn_vault,
+            tvl_cap: state.tvl_cap,
+        });
         msg!("IDL Protocol initialized. Vault: {}, TVL Cap: {}", state.vault, state.tvl_cap);
         Ok(())
     }
 
     /// SECURITY FIX: Stake IDL tokens with actual SPL token transfer
     pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(amount > 0, IdlError::InvalidAmount);
         // ATTACK FIX: Minimum stake to prevent Sybil attacks with dust amounts
-        require!(amount >= MIN_STAKE_AMOUNT, IdlError::StakeTooSmall);
+        require!(amount >= cfg.min_stake_amount, IdlError::StakeTooSmall);
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        // STAKE_FRONT_RUN FIX: Large deposits must go through commit_stake + reveal_stake
+        require!(amount <= cfg.large_stake_threshold, IdlError::LargeStakeRequiresCommitReveal);
+        // TVL_CAP_RACE FIX: New capacity is allocated through the raise queue
+        require!(
+            !ctx.accounts.state.tvl_raise_active(Clock::get()?.unix_timestamp),
+            IdlError::TvlRaiseQueueActive
+        );
 
         // TIER 3: Check TVL cap
         let new_total = ctx.accounts.state.total_staked.saturating_add(amount);
@@ -278,37 +335,90 @@ pub mod idl_protocol {
         token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
 
         let state = &mut ctx.accounts.state;
-        let staker = &mut ctx.accounts.staker_account;
+        credit_stake(
+            state,
+            &mut ctx.accounts.staker_account,
+            ctx.accounts.user.key(),
+            ctx.bumps.staker_account,
+            amount,
+            Clock::get()?.unix_timestamp
+        )?;
 
-        // Initialize staker if new
-        if staker.owner == Pubkey::default() {
-            staker.owner = ctx.accounts.user.key();
-            staker.bump = ctx.bumps.staker_account;
-            staker.reward_per_token_paid = state.reward_per_token_stored;
-        } else {
-            // SECURITY FIX: Update pending rewards before changing stake
-            let earned = calculate_earned(staker, state);
-            staker.pending_rewards = staker.pending_rewards
-                .checked_add(earned)
-                .ok_or(IdlError::MathOverflow)?;
-            staker.reward_per_token_paid = state.reward_per_token_stored;
-        }
+        msg!("Staked {} tokens. Total staked: {}", amount, state.total_staked);
+        Ok(())
+    }
 
-        // SECURITY FIX: Use checked arithmetic
-        staker.staked_amount = staker.staked_amount
-            .checked_add(amount)
-            .ok_or(IdlError::MathOverflow)?;
-        staker.last_stake_timestamp = Clock::get()?.unix_timestamp;
-        state.total_staked = state.total_staked
-            .checked_add(amount)
-            .ok_or(IdlError::MathOverflow)?;
+    /// STAKE_FRONT_RUN FIX: Commit to a stake amount without revealing it
+    /// commitment = hash(amount, salt); reveal_stake after STAKE_COMMIT_WINDOW
+    pub fn commit_stake(ctx: Context<CommitStake>, commitment: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
-        msg!("Staked {} tokens. Total staked: {}", amount, state.total_staked);
+        let stake_commitment = &mut ctx.accounts.stake_commitment;
+        let clock = Clock::get()?;
+
+        // A stale commitment that was never revealed can be overwritten
+        require!(
+            stake_commitment.owner == Pubkey::default()
+                || clock.unix_timestamp > stake_commitment.commit_time + cfg.commitment_expiry,
+            IdlError::StakeCommitmentPending
+        );
+
+        stake_commitment.owner = ctx.accounts.user.key();
+        stake_commitment.commitment = commitment;
+        stake_commitment.commit_time = clock.unix_timestamp;
+        stake_commitment.bump = ctx.bumps.stake_commitment;
+
+        emit!(StakeCommitted {
+            version: EVENT_VERSION,
+            user: stake_commitment.owner,
+            reveal_after: stake_commitment.commit_time + cfg.stake_commit_window,
+        });
+        msg!("Stake committed, reveal after {} seconds", cfg.stake_commit_window);
+        Ok(())
+    }
+
+    /// STAKE_FRONT_RUN FIX: Reveal and deposit a committed stake (any size)
+    pub fn reveal_stake(ctx: Context<RevealStake>, amount: u64, salt: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(amount >= cfg.min_stake_amount, IdlError::StakeTooSmall);
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+
+        let clock = Clock::get()?;
+        ctx.accounts.stake_commitment.check_reveal(cfg, amount, &salt, clock.unix_timestamp)?;
+        require!(
+            !ctx.accounts.state.tvl_raise_active(clock.unix_timestamp),
+            IdlError::TvlRaiseQueueActive
+        );
+
+        let new_total = ctx.accounts.state.total_staked.saturating_add(amount);
+        require!(new_total <= ctx.accounts.state.tvl_cap, IdlError::TvlCapExceeded);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let state = &mut ctx.accounts.state;
+        credit_stake(
+            state,
+            &mut ctx.accounts.staker_account,
+            ctx.accounts.user.key(),
+            ctx.bumps.staker_account,
+            amount,
+            clock.unix_timestamp
+        )?;
+
+        msg!("Revealed stake of {} tokens. Total staked: {}", amount, state.total_staked);
         Ok(())
     }
 
     /// SECURITY FIX: Unstake tokens with actual SPL token transfer
     pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(amount > 0, IdlError::InvalidAmount);
         // RICK FIX: Add pause check (was missing!)
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
@@ -318,7 +428,7 @@ pub mod idl_protocol {
 
         // TIER 1 FIX: Anti-flash-loan - enforce minimum stake duration
         require!(
-            clock.unix_timestamp >= staker.last_stake_timestamp + MIN_STAKE_DURATION,
+            clock.unix_timestamp >= staker.last_stake_timestamp + cfg.min_stake_duration,
             IdlError::StakeTooRecent
         );
 
@@ -356,15 +466,23 @@ pub mod idl_protocol {
         staker.staked_amount = staker.staked_amount.saturating_sub(amount);
         state.total_staked = state.total_staked.saturating_sub(amount);
 
+        emit!(Unstaked {
+            version: EVENT_VERSION,
+            user: staker.owner,
+            amount,
+            staked_amount: staker.staked_amount,
+            total_staked: state.total_staked,
+        });
         msg!("Unstaked {} tokens", amount);
         Ok(())
     }
 
     /// Lock staked tokens for veIDL voting power
     pub fn lock_for_ve(ctx: Context<LockForVe>, lock_duration: i64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
         require!(
-            lock_duration >= MIN_LOCK_DURATION && lock_duration <= MAX_LOCK_DURATION,
+            (cfg.min_lock_duration..=cfg.max_lock_duration).contains(&lock_duration),
             IdlError::InvalidLockDuration
         );
 
@@ -379,7 +497,7 @@ pub mod idl_protocol {
         // Initial veIDL = stake * (duration / max_duration)
         let initial_ve_amount = (staker.staked_amount as u128)
             .checked_mul(lock_duration as u128)
-            .and_then(|v| v.checked_div(MAX_LOCK_DURATION as u128))
+            .and_then(|v| v.checked_div(cfg.max_lock_duration as u128))
             .and_then(|v| u64::try_from(v).ok())
             .ok_or(IdlError::MathOverflow)?;
 
@@ -392,6 +510,11 @@ pub mod idl_protocol {
             .ok_or(IdlError::MathOverflow)?;
         ve_position.lock_duration = lock_duration;  // RICK FIX: Store for decay calc
         ve_position.bump = ctx.bumps.ve_position;
+        ve_position.last_modified = clock.unix_timestamp;
+        ve_position.prev_modified = 0;
+        ve_position.prev_initial_ve_amount = 0;
+        ve_position.prev_lock_end = 0;
+        ve_position.prev_lock_duration = 0;
 
         // Note: total_ve_supply tracks INITIAL amounts.
         // For accurate governance, query current_ve_amount() at vote time.
@@ -399,6 +522,14 @@ pub mod idl_protocol {
             .checked_add(initial_ve_amount)
             .ok_or(IdlError::MathOverflow)?;
 
+        emit!(VeLocked {
+            version: EVENT_VERSION,
+            user: ve_position.owner,
+            locked_stake: ve_position.locked_stake,
+            initial_ve_amount,
+            lock_end: ve_position.lock_end,
+            total_ve_supply: state.total_ve_supply,
+        });
         msg!("Locked {} for {} initial veIDL (decays linearly) until {}",
             staker.staked_amount, initial_ve_amount, ve_position.lock_end);
         Ok(())
@@ -417,12 +548,19 @@ pub mod idl_protocol {
         // Remove from total supply (tracks initial amounts)
         state.total_ve_supply = state.total_ve_supply.saturating_sub(ve_position.initial_ve_amount);
 
+        emit!(VeUnlocked {
+            version: EVENT_VERSION,
+            user: ve_position.owner,
+            locked_stake: ve_position.locked_stake,
+            total_ve_supply: state.total_ve_supply,
+        });
         msg!("Unlocked veIDL position");
         Ok(())
     }
 
     /// RICK FIX: Extend existing lock duration
     pub fn extend_lock(ctx: Context<ExtendLock>, additional_duration: i64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
         require!(additional_duration > 0, IdlError::InvalidLockDuration);
 
@@ -440,13 +578,13 @@ pub mod idl_protocol {
 
         // Ensure total lock doesn't exceed max
         let total_lock_from_now = new_end.saturating_sub(clock.unix_timestamp);
-        require!(total_lock_from_now <= MAX_LOCK_DURATION, IdlError::LockTooLong);
+        require!(total_lock_from_now <= cfg.max_lock_duration, IdlError::LockTooLong);
 
         // Calculate new veIDL based on remaining time
         let new_total_duration = new_end.saturating_sub(ve_position.lock_start);
         let new_initial_ve = (ve_position.locked_stake as u128)
             .checked_mul(new_total_duration as u128)
-            .and_then(|v| v.checked_div(MAX_LOCK_DURATION as u128))
+            .and_then(|v| v.checked_div(cfg.max_lock_duration as u128))
             .and_then(|v| u64::try_from(v).ok())
             .ok_or(IdlError::MathOverflow)?;
 
@@ -456,10 +594,15 @@ pub mod idl_protocol {
             .checked_add(new_initial_ve)
             .ok_or(IdlError::MathOverflow)?;
 
-        ve_position.initial_ve_amount = new_initial_ve;
-        ve_position.lock_end = new_end;
-        ve_position.lock_duration = new_total_duration;
+        ve_position.extend_terms(new_initial_ve, new_end, new_total_duration, clock.unix_timestamp);
 
+        emit!(VeLockExtended {
+            version: EVENT_VERSION,
+            user: ve_position.owner,
+            initial_ve_amount: new_initial_ve,
+            lock_end: new_end,
+            total_ve_supply: state.total_ve_supply,
+        });
         msg!("Extended lock to {} with {} veIDL", new_end, new_initial_ve);
         Ok(())
     }
@@ -473,6 +616,7 @@ pub mod idl_protocol {
         resolution_timestamp: i64,
         description: String,
     ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
         require!(protocol_id.len() <= 32, IdlError::InvalidInput);
         require!(description.len() <= 200, IdlError::InvalidInput);
@@ -482,12 +626,23 @@ pub mod idl_protocol {
         let clock = Clock::get()?;
         // SECURITY FIX: Increase minimum resolution delay to 24 hours
         require!(
-            resolution_timestamp > clock.unix_timestamp + MIN_RESOLUTION_DELAY,
+            resolution_timestamp > clock.unix_timestamp + cfg.min_resolution_delay,
             IdlError::InvalidTimestamp
         );
 
+        lock_creation_stake(
+            cfg,
+            &mut ctx.accounts.creator_stats,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            clock.unix_timestamp
+        )?;
+
         let market = &mut ctx.accounts.market;
         market.creator = ctx.accounts.creator.key();
+        market.creation_stake = cfg.market_creation_stake;
         market.protocol_id = protocol_id;
         market.metric_type = metric_type;
         market.target_value = target_value;
@@ -507,14 +662,344 @@ pub mod idl_protocol {
         market.bump = ctx.bumps.market;
         market.status = MARKET_STATUS_ACTIVE;
         // ATTACK FIX: Initialize multi-oracle consensus
-        market.oracle_count = 1;
+        market.oracle_count = 0;
         market.oracle_votes_yes = 0;
         market.oracle_votes_no = 0;
+        market.parent_market = None;
+        market.hedge_inverse = false;
+        market.market_type = MARKET_TYPE_BINARY;
 
+        emit_market_created(market);
         msg!("Created prediction market for {}", market.protocol_id);
         Ok(())
     }
 
+    /// Create a categorical market with up to MAX_MARKET_OUTCOMES outcomes
+    /// The oracle resolves to a single winning outcome index; outcome labels live in the description.
+    pub fn create_categorical_market(
+        ctx: Context<CreateCategoricalMarket>,
+        protocol_id: String,
+        metric_type: MetricType,
+        outcome_count: u8,
+        resolution_timestamp: i64,
+        description: String,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(protocol_id.len() <= 32, IdlError::InvalidInput);
+        require!(description.len() <= 200, IdlError::InvalidInput);
+        require!(
+            (2..=MAX_MARKET_OUTCOMES as u8).contains(&outcome_count),
+            IdlError::InvalidOutcomeCount
+        );
+
+        let clock = Clock::get()?;
+        require!(
+            resolution_timestamp > clock.unix_timestamp + cfg.min_resolution_delay,
+            IdlError::InvalidTimestamp
+        );
+
+        lock_creation_stake(
+            cfg,
+            &mut ctx.accounts.creator_stats,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            clock.unix_timestamp
+        )?;
+
+        let market = &mut ctx.accounts.market;
+        market.creator = ctx.accounts.creator.key();
+        market.creation_stake = cfg.market_creation_stake;
+        market.protocol_id = protocol_id;
+        market.metric_type = metric_type;
+        market.target_value = 0;
+        market.resolution_timestamp = resolution_timestamp;
+        market.description = description;
+        market.resolved = false;
+        market.resolved_at = None;
+        market.outcome = None;
+        market.actual_value = None;
+        market.oracle = ctx.accounts.oracle.key();
+        market.created_at = clock.unix_timestamp;
+        market.bump = ctx.bumps.market;
+        market.status = MARKET_STATUS_ACTIVE;
+        market.oracle_count = 0;
+        market.parent_market = None;
+        market.market_type = MARKET_TYPE_CATEGORICAL;
+        market.outcome_count = outcome_count;
+        market.outcome_pools = [0; MAX_MARKET_OUTCOMES];
+        market.outcome_pools_effective = [0; MAX_MARKET_OUTCOMES];
+        market.winning_outcome = None;
+
+        emit_market_created(market);
+        msg!("Created {}-outcome market for {}", outcome_count, market.protocol_id);
+        Ok(())
+    }
+
+    /// Create a scalar market over [range_low, range_high]
+    /// YES bets are long and NO bets are short. The pot is split linearly by where the
+    /// resolved value lands in the range, with values outside it clamped to the bounds.
+    pub fn create_scalar_market(
+        ctx: Context<CreateScalarMarket>,
+        protocol_id: String,
+        metric_type: MetricType,
+        range_low: u64,
+        range_high: u64,
+        resolution_timestamp: i64,
+        description: String,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(protocol_id.len() <= 32, IdlError::InvalidInput);
+        require!(description.len() <= 200, IdlError::InvalidInput);
+        require!(range_high > range_low, IdlError::InvalidRange);
+
+        let clock = Clock::get()?;
+        require!(
+            resolution_timestamp > clock.unix_timestamp + cfg.min_resolution_delay,
+            IdlError::InvalidTimestamp
+        );
+
+        lock_creation_stake(
+            cfg,
+            &mut ctx.accounts.creator_stats,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            clock.unix_timestamp
+        )?;
+
+        let market = &mut ctx.accounts.market;
+        market.creator = ctx.accounts.creator.key();
+        market.creation_stake = cfg.market_creation_stake;
+        market.protocol_id = protocol_id;
+        market.metric_type = metric_type;
+        // Midpoint: longs are in the money above it
+        market.target_value = range_low + (range_high - range_low) / 2;
+        market.resolution_timestamp = resolution_timestamp;
+        market.description = description;
+        market.total_yes_actual = 0;
+        market.total_no_actual = 0;
+        market.total_yes_amount = 0;
+        market.total_no_amount = 0;
+        market.resolved = false;
+        market.resolved_at = None;
+        market.outcome = None;
+        market.actual_value = None;
+        market.oracle = ctx.accounts.oracle.key();
+        market.created_at = clock.unix_timestamp;
+        market.bump = ctx.bumps.market;
+        market.status = MARKET_STATUS_ACTIVE;
+        market.oracle_count = 0;
+        market.parent_market = None;
+        market.market_type = MARKET_TYPE_SCALAR;
+        market.range_low = range_low;
+        market.range_high = range_high;
+
+        emit_market_created(market);
+        msg!("Created scalar market for {} over [{}, {}]", market.protocol_id, range_low, range_high);
+        Ok(())
+    }
+
+    /// Create a comparison market: does protocol A beat protocol B on a metric?
+    /// YES wins if A's value is strictly larger, NO if B's is. A tie cancels the market
+    /// so every bet can be refunded.
+    pub fn create_comparison_market(
+        ctx: Context<CreateComparisonMarket>,
+        protocol_a: String,
+        protocol_b: String,
+        metric_type: MetricType,
+        resolution_timestamp: i64,
+        description: String,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(protocol_a.len() <= 32 && protocol_b.len() <= 32, IdlError::InvalidInput);
+        require!(protocol_a != protocol_b, IdlError::InvalidInput);
+        require!(description.len() <= 200, IdlError::InvalidInput);
+
+        let clock = Clock::get()?;
+        require!(
+            resolution_timestamp > clock.unix_timestamp + cfg.min_resolution_delay,
+            IdlError::InvalidTimestamp
+        );
+
+        lock_creation_stake(
+            cfg,
+            &mut ctx.accounts.creator_stats,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            clock.unix_timestamp
+        )?;
+
+        let market = &mut ctx.accounts.market;
+        market.creator = ctx.accounts.creator.key();
+        market.creation_stake = cfg.market_creation_stake;
+        market.protocol_id = protocol_a;
+        market.comparison_protocol_id = protocol_b;
+        market.metric_type = metric_type;
+        market.target_value = 0;
+        market.resolution_timestamp = resolution_timestamp;
+        market.description = description;
+        market.total_yes_actual = 0;
+        market.total_no_actual = 0;
+        market.total_yes_amount = 0;
+        market.total_no_amount = 0;
+        market.resolved = false;
+        market.resolved_at = None;
+        market.outcome = None;
+        market.actual_value = None;
+        market.comparison_value = None;
+        market.oracle = ctx.accounts.oracle.key();
+        market.created_at = clock.unix_timestamp;
+        market.bump = ctx.bumps.market;
+        market.status = MARKET_STATUS_ACTIVE;
+        market.oracle_count = 0;
+        market.parent_market = None;
+        market.market_type = MARKET_TYPE_COMPARISON;
+
+        emit_market_created(market);
+        msg!("Created comparison market: {} vs {}", market.protocol_id, market.comparison_protocol_id);
+        Ok(())
+    }
+
+    /// Create a hedge market linked to an existing market
+    /// Shares the parent's metric, target and resolution time. An inverse hedge resolves YES
+    /// when the parent resolves NO; a complementary hedge mirrors the parent's outcome.
+    /// Winnings pay HEDGE_MARKET_FEE_BPS on top of the normal bet fee.
+    pub fn create_hedge_market(ctx: Context<CreateHedgeMarket>, inverse: bool) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+
+        let parent = &ctx.accounts.parent_market;
+        let clock = Clock::get()?;
+
+        require!(parent.parent_market.is_none(), IdlError::InvalidHedgeParent);
+        require!(parent.market_type == MARKET_TYPE_BINARY, IdlError::InvalidHedgeParent);
+        require!(!parent.resolved, IdlError::MarketResolved);
+        require!(parent.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+        require!(
+            clock.unix_timestamp < parent.resolution_timestamp - cfg.betting_close_window,
+            IdlError::BettingClosed
+        );
+
+        lock_creation_stake(
+            cfg,
+            &mut ctx.accounts.creator_stats,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            clock.unix_timestamp
+        )?;
+
+        let market = &mut ctx.accounts.market;
+        market.creator = ctx.accounts.creator.key();
+        market.creation_stake = cfg.market_creation_stake;
+        market.protocol_id = parent.protocol_id.clone();
+        market.metric_type = parent.metric_type;
+        market.target_value = parent.target_value;
+        market.resolution_timestamp = parent.resolution_timestamp;
+        market.description = parent.description.clone();
+        market.total_yes_actual = 0;
+        market.total_no_actual = 0;
+        market.total_yes_amount = 0;
+        market.total_no_amount = 0;
+        market.resolved = false;
+        market.resolved_at = None;
+        market.outcome = None;
+        market.actual_value = None;
+        market.oracle = parent.oracle;
+        market.created_at = clock.unix_timestamp;
+        market.bump = ctx.bumps.market;
+        market.status = MARKET_STATUS_ACTIVE;
+        market.oracle_count = 0;
+        market.oracle_votes_yes = 0;
+        market.oracle_votes_no = 0;
+        market.parent_market = Some(parent.key());
+        market.hedge_inverse = inverse;
+        market.market_type = MARKET_TYPE_BINARY;
+
+        emit_market_created(market);
+        msg!("Created {} hedge market for {}",
+            if inverse { "inverse" } else { "complementary" }, market.protocol_id);
+        Ok(())
+    }
+
+    /// Settle a hedge market from its parent (permissionless)
+    /// Follows the parent once its dispute window has closed; a cancelled parent cancels the hedge.
+    pub fn resolve_hedge_market(ctx: Context<ResolveHedgeMarket>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let parent = &ctx.accounts.parent_market;
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+
+        if parent.status == MARKET_STATUS_CANCELLED {
+            market.status = MARKET_STATUS_CANCELLED;
+            emit!(MarketCancelled {
+                version: EVENT_VERSION,
+                market: market.key(),
+                creation_stake_slashed: 0,
+            });
+            msg!("Hedge market cancelled with parent: {}", market.protocol_id);
+            return Ok(());
+        }
+
+        require!(
+            parent.resolved && parent.status == MARKET_STATUS_RESOLVED,
+            IdlError::MarketNotResolved
+        );
+        let parent_resolved_at = parent.resolved_at.ok_or(IdlError::MarketNotResolved)?;
+        require!(
+            dispute_window_closed(cfg, parent_resolved_at, clock.unix_timestamp),
+            IdlError::DisputeWindowOpen
+        );
+
+        let parent_outcome = parent.outcome.ok_or(IdlError::MarketNotResolved)?;
+        let outcome = parent_outcome != market.hedge_inverse;
+        market.flush_pending_reveals();
+        market.outcome = Some(outcome);
+        market.actual_value = parent.actual_value;
+        market.resolved = true;
+        market.resolved_at = Some(parent_resolved_at);
+        market.status = MARKET_STATUS_RESOLVED;
+
+        emit_market_resolved(market);
+        msg!("Hedge market resolved from parent: {}", if outcome { "YES" } else { "NO" });
+        Ok(())
+    }
+
+    /// Unveil a market's pending reveal batch once BATCH_REVEAL_DELAY has passed (permissionless crank)
+    pub fn unveil_reveal_batch(ctx: Context<UnveilRevealBatch>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(market.pending_batch_start != 0, IdlError::NoPendingReveals);
+        require!(
+            market.unveil_reveal_batch(cfg, clock.unix_timestamp),
+            IdlError::BatchRevealDelayNotPassed
+        );
+
+        emit!(RevealBatchUnveiled {
+            version: EVENT_VERSION,
+            market: market.key(),
+            total_yes_actual: market.total_yes_actual,
+            total_no_actual: market.total_no_actual,
+        });
+        msg!("Reveal batch unveiled: YES {} / NO {}",
+            market.total_yes_actual, market.total_no_actual);
+        Ok(())
+    }
+
     /// DEPRECATED: Use commit_bet + reveal_bet instead
     /// HACK FIX: Direct betting disabled to prevent front-running
     #[allow(unused_variables)]
@@ -532,7 +1017,8 @@ pub mod idl_protocol {
     }
 
     /// SECURITY FIX: Cancel market and allow refunds (admin only, for emergencies)
-    pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
+    /// MARKET_SPAM FIX: Cancelling as spam slashes the creation stake to the insurance fund
+    pub fn cancel_market(ctx: Context<CancelMarket>, spam: bool) -> Result<()> {
         let market = &mut ctx.accounts.market;
 
         require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
@@ -540,7 +1026,60 @@ pub mod idl_protocol {
 
         market.status = MARKET_STATUS_CANCELLED;
 
-        msg!("Market cancelled: {}", market.protocol_id);
+        let slashed = if spam { std::mem::take(&mut market.creation_stake) } else { 0 };
+        emit!(MarketCancelled {
+            version: EVENT_VERSION,
+            market: market.key(),
+            creation_stake_slashed: slashed,
+        });
+
+        if spam {
+            let state = &mut ctx.accounts.state;
+            state.insurance_fund = state.insurance_fund.saturating_add(slashed);
+            msg!("Market cancelled as spam, creation stake slashed: {}", slashed);
+        } else {
+            msg!("Market cancelled: {}", market.protocol_id);
+        }
+        Ok(())
+    }
+
+    /// Reclaim the market creation stake once the market is cancelled (not as spam) or settled
+    /// past its dispute window
+    pub fn reclaim_creation_stake(ctx: Context<ReclaimCreationStake>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(market.creation_stake > 0, IdlError::NoRewardsToClaim);
+        match market.status {
+            MARKET_STATUS_CANCELLED => {}
+            MARKET_STATUS_RESOLVED => {
+                let resolved_at = market.resolved_at.ok_or(IdlError::MarketNotResolved)?;
+                require!(
+                    dispute_window_closed(cfg, resolved_at, clock.unix_timestamp),
+                    IdlError::DisputeWindowOpen
+                );
+            }
+            _ => return Err(IdlError::MarketNotResolved.into()),
+        }
+
+        let stake = std::mem::take(&mut market.creation_stake);
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.creator_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            stake
+        )?;
+
+        emit!(CreationStakeReclaimed {
+            version: EVENT_VERSION,
+            market: market.key(),
+            creator: market.creator,
+            amount: stake,
+        });
+        msg!("Creation stake reclaimed: {}", stake);
         Ok(())
     }
 
@@ -579,12 +1118,19 @@ pub mod idl_protocol {
             refund_amount
         )?;
 
+        emit!(RefundClaimed {
+            version: EVENT_VERSION,
+            market: market.key(),
+            user: bet.owner,
+            amount: refund_amount,
+        });
         msg!("Refunded {} from cancelled market", refund_amount);
         Ok(())
     }
 
     /// SECURITY FIX: Claim winnings with token transfer and delay
     pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let state = &mut ctx.accounts.state;
         let market = &ctx.accounts.market;
         let bet = &mut ctx.accounts.bet;
@@ -603,45 +1149,43 @@ pub mod idl_protocol {
         // SECURITY FIX: Add delay after resolution before claiming
         let resolved_at = market.resolved_at.ok_or(IdlError::MarketNotResolved)?;
         require!(
-            clock.unix_timestamp >= resolved_at + CLAIM_DELAY_AFTER_RESOLUTION,
+            clock.unix_timestamp >= resolved_at + cfg.claim_delay_after_resolution,
             IdlError::ClaimTooEarly
         );
 
         // SELF-REVIEW FIX: Claims must wait until AFTER dispute window closes
         // Otherwise someone could claim winnings, then market gets disputed
         require!(
-            clock.unix_timestamp >= resolved_at + ORACLE_DISPUTE_WINDOW,
+            dispute_window_closed(cfg, resolved_at, clock.unix_timestamp),
             IdlError::DisputeWindowOpen
         );
 
-        let outcome = market.outcome.unwrap();
-        let is_winner = (bet.bet_yes && outcome) || (!bet.bet_yes && !outcome);
+        let base_winnings = market_payout(bet, market)?;
+        let season_start = state.season_start_at(resolved_at);
 
         bet.claimed = true;
 
-        if !is_winner {
+        // Every settled bet counts once toward the predictor's record
+        let stats = &mut ctx.accounts.predictor_stats;
+        if stats.owner == Pubkey::default() {
+            stats.owner = ctx.accounts.user.key();
+            stats.bump = ctx.bumps.predictor_stats;
+        }
+        // Bonuses are earned by the record before this bet
+        let record_bonus_bps = stats.record_bonus_bps(cfg);
+
+        if base_winnings == 0 {
+            stats.record_settlement(false, bet.amount, clock.unix_timestamp, season_start);
+            emit!(BetLost {
+                version: EVENT_VERSION,
+                market: market.key(),
+                user: bet.owner,
+                amount: bet.amount,
+            });
             msg!("Bet lost, no winnings");
             return Ok(());
         }
 
-        // SECURITY FIX: Use effective_amount for share calculation, actual pool for funds
-        let (winning_pool_effective, losing_pool_actual) = if outcome {
-            (market.total_yes_amount, market.total_no_actual)
-        } else {
-            (market.total_no_amount, market.total_yes_actual)
-        };
-
-        // Calculate winnings share based on effective amounts (includes staker bonus)
-        let winnings_share = if winning_pool_effective > 0 {
-            (bet.effective_amount as u128)
-                .checked_mul(losing_pool_actual as u128)
-                .and_then(|v| v.checked_div(winning_pool_effective as u128))
-                .and_then(|v| u64::try_from(v).ok())
-                .unwrap_or(0)
-        } else {
-            0
-        };
-
         // CONVICTION FIX: Apply conviction bonus if provided and lock expired
         let conviction_bonus_bps = if let Some(ref conviction) = ctx.accounts.conviction_bet {
             // Require lock to have expired
@@ -656,12 +1200,11 @@ pub mod idl_protocol {
 
         // SECURITY FIX: Verify pool has enough balance before transfer
         let pool_balance = ctx.accounts.market_pool.amount;
-        let base_winnings = bet.amount
-            .checked_add(winnings_share)
-            .ok_or(IdlError::MathOverflow)?;
 
         // Apply conviction bonus to winnings (0.5% per day locked, max 15% for 30 days)
         let conviction_bonus = (base_winnings as u128 * conviction_bonus_bps as u128 / 10000) as u64;
+        // Accuracy and streak bonuses apply to the profit only, paid from the vault (see below)
+        let record_bonus = state.take_predictor_bonus(base_winnings.saturating_sub(bet.amount), record_bonus_bps);
         let gross_winnings = base_winnings.saturating_add(conviction_bonus);
 
         // Cap gross_winnings to available pool balance pro-rata
@@ -672,18 +1215,26 @@ pub mod idl_protocol {
             conviction.claimed = true;
         }
 
-        let fee = (gross_winnings as u128 * BET_FEE_BPS as u128 / 10000) as u64;
+        // VIP tier and lootbox discounts; the split below is recomputed from the discounted fee
+        let fee_bps = discounted_fee_bps(
+            cfg,
+            market.fee_bps(cfg),
+            &ctx.accounts.user.key(),
+            Some(&**ctx.accounts.predictor_stats),
+            ctx.accounts.lootbox_reward.as_ref().map(|l| &***l),
+            clock.unix_timestamp
+        );
+        let fees = FeeSplit::new(cfg, (gross_winnings as u128 * fee_bps as u128 / 10000) as u64);
+        let fee = fees.fee;
         let net_winnings = gross_winnings.saturating_sub(fee);
 
-        // TIER 3: Insurance fund takes 1% of total fee first
-        let insurance_fee = (fee as u128 * INSURANCE_FEE_BPS as u128 / 10000) as u64;
-        let distributable_fee = fee.saturating_sub(insurance_fee);
-
-        // Calculate fee distribution (from remaining after insurance)
-        let staker_fee = (distributable_fee as u128 * STAKER_FEE_SHARE_BPS as u128 / 10000) as u64;
-        let creator_fee = (distributable_fee as u128 * CREATOR_FEE_SHARE_BPS as u128 / 10000) as u64;
-        let treasury_fee = (distributable_fee as u128 * TREASURY_FEE_SHARE_BPS as u128 / 10000) as u64;
-        let burn_amount = (distributable_fee as u128 * BURN_FEE_SHARE_BPS as u128 / 10000) as u64;
+        // Scalar payouts below the stake count as a loss of the difference
+        if base_winnings >= bet.amount {
+            ctx.accounts.predictor_stats.record_settlement(true, net_winnings, clock.unix_timestamp, season_start);
+        } else {
+            ctx.accounts.predictor_stats
+                .record_settlement(false, bet.amount - base_winnings, clock.unix_timestamp, season_start);
+        }
 
         // PDA signer seeds for market pool
         let market_key = market.key();
@@ -694,84 +1245,45 @@ pub mod idl_protocol {
             &[market_pool_bump],
         ];
         let signer_seeds = &[&market_seeds[..]];
-        let cpi_program = ctx.accounts.token_program.to_account_info();
 
-        // CRITICAL FIX: Transfer net winnings to user
-        let cpi_accounts_user = Transfer {
-            from: ctx.accounts.market_pool.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.market_pool.to_account_info(),
+        // AUTO-COMPOUND: Restake the net winnings into the user's stake where the TVL cap allows
+        let restaked = match ctx.accounts.staker_account.as_mut() {
+            Some(staker) if ctx.accounts.predictor_stats.auto_compound => {
+                compound_into_stake(state, staker, net_winnings, clock.unix_timestamp)?
+            }
+            _ => 0,
         };
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts_user, signer_seeds),
-            net_winnings
+        transfer_from_market_pool(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.market_pool.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            restaked
         )?;
 
-        // Transfer creator fee
-        let cpi_accounts_creator = Transfer {
-            from: ctx.accounts.market_pool.to_account_info(),
-            to: ctx.accounts.creator_token_account.to_account_info(),
-            authority: ctx.accounts.market_pool.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts_creator, signer_seeds),
-            creator_fee
-        )?;
-
-        // Transfer treasury fee
-        let cpi_accounts_treasury = Transfer {
-            from: ctx.accounts.market_pool.to_account_info(),
-            to: ctx.accounts.treasury_token_account.to_account_info(),
-            authority: ctx.accounts.market_pool.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts_treasury, signer_seeds),
-            treasury_fee
-        )?;
-
-        // Transfer staker rewards to vault
-        let cpi_accounts_vault = Transfer {
-            from: ctx.accounts.market_pool.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-            authority: ctx.accounts.market_pool.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts_vault, signer_seeds),
-            staker_fee
+        // CRITICAL FIX: Transfer net winnings to user
+        transfer_from_market_pool(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.market_pool.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            signer_seeds,
+            net_winnings - restaked
         )?;
 
-        // RICK FIX: Send "burn" to burn_vault instead of actual burn
-        // (Actual SPL burn requires mint authority which market_pool doesn't have)
-        let cpi_accounts_burn = Transfer {
-            from: ctx.accounts.market_pool.to_account_info(),
-            to: ctx.accounts.burn_vault.to_account_info(),
-            authority: ctx.accounts.market_pool.to_account_info(),
+        let fee_accounts = FeeAccounts {
+            market_pool: ctx.accounts.market_pool.to_account_info(),
+            creator: ctx.accounts.creator_token_account.to_account_info(),
+            treasury: ctx.accounts.treasury_token_account.to_account_info(),
+            vault: ctx.accounts.vault.to_account_info(),
+            burn_vault: ctx.accounts.burn_vault.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
         };
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts_burn, signer_seeds),
-            burn_amount
-        )?;
-
-        // TIER 3: Transfer insurance fee to vault (tracked separately in state)
-        if insurance_fee > 0 {
-            let cpi_accounts_insurance = Transfer {
-                from: ctx.accounts.market_pool.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
-                authority: ctx.accounts.market_pool.to_account_info(),
-            };
-            token::transfer(
-                CpiContext::new_with_signer(cpi_program, cpi_accounts_insurance, signer_seeds),
-                insurance_fee
-            )?;
-        }
-
-        // SECURITY FIX: Update reward checkpoint before adding to pool
-        update_reward_per_token(state, staker_fee);
+        distribute_fees(&fees, &fee_accounts, signer_seeds, state)?;
 
         // REFERRAL FIX: Credit referral fees if user has a referrer
         // 5% of total fee goes to referrer's pending_fees (not transferred, just credited)
         let referral_fee = if let Some(ref mut user_referral) = ctx.accounts.user_referral {
-            let referral_amount = (fee as u128 * REFERRAL_FEE_BPS as u128 / 10000) as u64;
+            let referral_amount = (fee as u128 * cfg.referral_fee_bps as u128 / 10000) as u64;
             user_referral.pending_fees = user_referral.pending_fees
                 .checked_add(referral_amount)
                 .ok_or(IdlError::MathOverflow)?;
@@ -781,29 +1293,58 @@ pub mod idl_protocol {
             0
         };
 
-        // Update state tracking
-        state.reward_pool = state.reward_pool
-            .checked_add(staker_fee)
-            .ok_or(IdlError::MathOverflow)?;
-        state.total_burned = state.total_burned
-            .checked_add(burn_amount)
-            .ok_or(IdlError::MathOverflow)?;
-        state.total_fees_collected = state.total_fees_collected
-            .checked_add(fee)
-            .ok_or(IdlError::MathOverflow)?;
-        // TIER 3: Track insurance fund
-        state.insurance_fund = state.insurance_fund
-            .checked_add(insurance_fee)
-            .ok_or(IdlError::MathOverflow)?;
+        // SEASON_TRANSITION FIX: Season bonus on profit, phased by when the market resolved
+        let season_bonus = if let Some(ref mut season) = ctx.accounts.season {
+            let profit = net_winnings.saturating_sub(bet.amount);
+            let bonus = (profit as u128 * season.bonus_bps_at(cfg, resolved_at) as u128 / 10000) as u64;
+            let bonus = std::cmp::min(
+                bonus,
+                season.total_rewards.saturating_sub(season.bonus_distributed)
+            );
+            season.bonus_distributed = season.bonus_distributed.saturating_add(bonus);
+            bonus
+        } else {
+            0
+        };
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            season_bonus.saturating_add(record_bonus)
+        )?;
 
-        msg!("Claimed {} (fee: {}, stakers: {}, burned: {}, insurance: {}, referral: {})",
-             net_winnings, fee, staker_fee, burn_amount, insurance_fee, referral_fee);
+        emit!(WinningsClaimed {
+            version: EVENT_VERSION,
+            market: market.key(),
+            user: bet.owner,
+            bet_amount: bet.amount,
+            gross_winnings,
+            fee_bps,
+            fee,
+            staker_fee: fees.staker,
+            creator_fee: fees.creator,
+            treasury_fee: fees.treasury,
+            burn_fee: fees.burn,
+            insurance_fee: fees.insurance,
+            referral_fee,
+            conviction_bonus,
+            record_bonus,
+            season_bonus,
+            net_winnings,
+            restaked,
+        });
+        msg!("Claimed {} (fee: {}, stakers: {}, burned: {}, insurance: {}, referral: {}, season: {}, restaked: {})",
+             net_winnings, fee, fees.staker, fees.burn, fees.insurance, referral_fee, season_bonus,
+             restaked);
         Ok(())
     }
 
     /// Claim staking rewards from reward pool
     /// SECURITY FIX: Use checkpoint system to prevent race conditions
     pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let state = &ctx.accounts.state;
         let staker = &ctx.accounts.staker_account;
 
@@ -813,7 +1354,7 @@ pub mod idl_protocol {
         // RICK FIX: Enforce claim cooldown (1 hour between claims)
         let clock = Clock::get()?;
         require!(
-            clock.unix_timestamp >= staker.last_reward_claim + REWARD_CLAIM_COOLDOWN,
+            clock.unix_timestamp >= staker.last_reward_claim + cfg.reward_claim_cooldown,
             IdlError::ClaimCooldown
         );
 
@@ -831,41 +1372,96 @@ pub mod idl_protocol {
             IdlError::InsufficientPoolBalance
         );
 
-        let state_bump = ctx.accounts.state.bump;
+        // Update staker checkpoint
+        let staker = &mut ctx.accounts.staker_account;
+        let state = &mut ctx.accounts.state;
 
-        // Transfer rewards from vault to user
-        let seeds = &[b"state".as_ref(), &[state_bump]];
-        let signer_seeds = &[&seeds[..]];
+        staker.rewards_claimed = staker.rewards_claimed
+            .checked_add(total_rewards)
+            .ok_or(IdlError::MathOverflow)?;
+        staker.reward_per_token_paid = state.reward_per_token_stored;
+        staker.pending_rewards = 0;
+        staker.last_reward_claim = clock.unix_timestamp;  // RICK FIX: Update cooldown
+        state.reward_pool = state.reward_pool.saturating_sub(total_rewards);
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.state.to_account_info(),
+        // AUTO-COMPOUND: Rewards already sit in the vault, so restaking is pure accounting
+        let auto_compound = ctx.accounts.predictor_stats
+            .as_ref()
+            .is_some_and(|s| s.auto_compound);
+        let restaked = if auto_compound {
+            compound_into_stake(state, staker, total_rewards, clock.unix_timestamp)?
+        } else {
+            0
         };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(
-            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds),
-            total_rewards
+        let payout = total_rewards - restaked;
+
+        // Transfer the rest from vault to user
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            payout
         )?;
 
-        // Update staker checkpoint after transfer
-        let staker = &mut ctx.accounts.staker_account;
+        emit!(StakingRewardsClaimed {
+            version: EVENT_VERSION,
+            user: ctx.accounts.staker_account.owner,
+            amount: total_rewards,
+            restaked,
+        });
+        msg!("Claimed {} staking rewards (restaked: {}, total claimed: {})",
+            total_rewards, restaked, ctx.accounts.staker_account.rewards_claimed);
+        Ok(())
+    }
+
+    /// AUTO-COMPOUND: Restake an opted-in staker's rewards (permissionless crank)
+    /// Follows the same cooldown as claim_staking_rewards; rewards that don't fit under the
+    /// TVL cap stay pending for the staker to claim.
+    pub fn compound_staking_rewards(ctx: Context<CompoundStakingRewards>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+
         let state = &mut ctx.accounts.state;
+        let staker = &mut ctx.accounts.staker_account;
+        let clock = Clock::get()?;
 
-        staker.rewards_claimed = staker.rewards_claimed
-            .checked_add(total_rewards)
+        require!(
+            clock.unix_timestamp >= staker.last_reward_claim + cfg.reward_claim_cooldown,
+            IdlError::ClaimCooldown
+        );
+
+        let earned = calculate_earned(staker, state);
+        let total_rewards = earned
+            .checked_add(staker.pending_rewards)
             .ok_or(IdlError::MathOverflow)?;
+        require!(total_rewards > 0, IdlError::NoRewardsToClaim);
+
         staker.reward_per_token_paid = state.reward_per_token_stored;
         staker.pending_rewards = 0;
-        staker.last_reward_claim = Clock::get()?.unix_timestamp;  // RICK FIX: Update cooldown
-        state.reward_pool = state.reward_pool.saturating_sub(total_rewards);
 
-        msg!("Claimed {} staking rewards (total claimed: {})", total_rewards, staker.rewards_claimed);
+        let restaked = compound_into_stake(state, staker, total_rewards, clock.unix_timestamp)?;
+        require!(restaked > 0, IdlError::TvlCapExceeded);
+
+        staker.pending_rewards = total_rewards - restaked;
+        staker.rewards_claimed = staker.rewards_claimed.saturating_add(restaked);
+        staker.last_reward_claim = clock.unix_timestamp;
+        state.reward_pool = state.reward_pool.saturating_sub(restaked);
+
+        emit!(StakingRewardsCompounded {
+            version: EVENT_VERSION,
+            user: staker.owner,
+            keeper: ctx.accounts.keeper.key(),
+            restaked,
+        });
+        msg!("Compounded {} rewards for {}", restaked, staker.owner);
         Ok(())
     }
 
     /// SECURITY FIX: Issue badge based on VERIFIED on-chain volume
     pub fn issue_badge(ctx: Context<IssueBadge>, tier: BadgeTier) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let state = &mut ctx.accounts.state;
         let badge = &mut ctx.accounts.badge;
         let user_volume = &ctx.accounts.user_volume;
@@ -876,16 +1472,16 @@ pub mod idl_protocol {
 
         // 10/10 FIX: Require 7 days since last volume update (prevents rapid wash trading)
         require!(
-            clock.unix_timestamp >= user_volume.last_updated + BADGE_HOLD_TIME,
+            clock.unix_timestamp >= user_volume.last_updated + cfg.badge_hold_time,
             IdlError::BadgeHoldTimeNotMet
         );
 
         let required_volume = match tier {
-            BadgeTier::Bronze => BADGE_TIER_BRONZE,
-            BadgeTier::Silver => BADGE_TIER_SILVER,
-            BadgeTier::Gold => BADGE_TIER_GOLD,
-            BadgeTier::Platinum => BADGE_TIER_PLATINUM,
-            BadgeTier::Diamond => BADGE_TIER_DIAMOND,
+            BadgeTier::Bronze => cfg.badge_tier_bronze,
+            BadgeTier::Silver => cfg.badge_tier_silver,
+            BadgeTier::Gold => cfg.badge_tier_gold,
+            BadgeTier::Platinum => cfg.badge_tier_platinum,
+            BadgeTier::Diamond => cfg.badge_tier_diamond,
             BadgeTier::None => 0,
         };
         require!(volume_usd >= required_volume, IdlError::InsufficientVolume);
@@ -896,11 +1492,11 @@ pub mod idl_protocol {
         }
 
         let ve_grant = match tier {
-            BadgeTier::Bronze => BADGE_VEIDL_BRONZE,
-            BadgeTier::Silver => BADGE_VEIDL_SILVER,
-            BadgeTier::Gold => BADGE_VEIDL_GOLD,
-            BadgeTier::Platinum => BADGE_VEIDL_PLATINUM,
-            BadgeTier::Diamond => BADGE_VEIDL_DIAMOND,
+            BadgeTier::Bronze => cfg.badge_veidl_bronze,
+            BadgeTier::Silver => cfg.badge_veidl_silver,
+            BadgeTier::Gold => cfg.badge_veidl_gold,
+            BadgeTier::Platinum => cfg.badge_veidl_platinum,
+            BadgeTier::Diamond => cfg.badge_veidl_diamond,
             BadgeTier::None => 0,
         };
 
@@ -909,17 +1505,31 @@ pub mod idl_protocol {
             state.total_ve_supply = state.total_ve_supply.saturating_sub(badge.ve_amount);
         }
 
+        // An upgrade keeps the original issue time and the replaced grant for vote snapshots
+        if badge.owner == Pubkey::default() {
+            badge.issued_at = clock.unix_timestamp;
+            badge.last_modified = clock.unix_timestamp;
+            badge.ve_amount = ve_grant;
+        } else {
+            badge.upgrade_grant(ve_grant, clock.unix_timestamp);
+        }
+
         badge.owner = ctx.accounts.recipient.key();
         badge.tier = tier;
         badge.volume_usd = volume_usd;
-        badge.ve_amount = ve_grant;
-        badge.issued_at = Clock::get()?.unix_timestamp;
         badge.bump = ctx.bumps.badge;
 
         state.total_ve_supply = state.total_ve_supply
             .checked_add(ve_grant)
             .ok_or(IdlError::MathOverflow)?;
 
+        emit!(BadgeIssued {
+            version: EVENT_VERSION,
+            owner: badge.owner,
+            tier,
+            ve_amount: ve_grant,
+            volume_usd,
+        });
         msg!("Issued {:?} badge with {} veIDL (verified volume: {})", tier, ve_grant, volume_usd);
         Ok(())
     }
@@ -931,13 +1541,64 @@ pub mod idl_protocol {
 
         state.total_ve_supply = state.total_ve_supply.saturating_sub(badge.ve_amount);
 
+        emit!(BadgeRevoked {
+            version: EVENT_VERSION,
+            owner: badge.owner,
+            ve_amount: badge.ve_amount,
+        });
         msg!("Revoked badge from {}", badge.owner);
         Ok(())
     }
 
+    /// Grow an account created by an earlier program version to its current size
+    /// (permissionless; the payer covers the extra rent). Fields added since are appended,
+    /// so the zeroed tail reads as their defaults. See migrated_space for the accounts covered
+    /// and migrate_legacy_fields for values that can't start at zero.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let old_len = account.data_len();
+        let discriminator: [u8; 8] = account.try_borrow_data()?
+            .get(..8)
+            .and_then(|d| d.try_into().ok())
+            .ok_or(IdlError::InvalidInput)?;
+        let new_len = migrated_space(&discriminator).ok_or(IdlError::InvalidInput)?;
+        require!(old_len < new_len, IdlError::AccountAlreadyMigrated);
+
+        let rent = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(account.lamports());
+        if rent > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: account.clone(),
+                    }
+                ),
+                rent
+            )?;
+        }
+        account.realloc(new_len, true)?;
+        migrate_legacy_fields(&account, &discriminator)?;
+
+        emit!(AccountMigrated {
+            version: EVENT_VERSION,
+            account: account.key(),
+            old_len: old_len as u64,
+            new_len: new_len as u64,
+        });
+        msg!("Migrated account {} from {} to {} bytes", account.key(), old_len, new_len);
+        Ok(())
+    }
+
     /// Pause/unpause protocol
     pub fn set_paused(ctx: Context<AdminOnly>, paused: bool) -> Result<()> {
         ctx.accounts.state.paused = paused;
+        emit!(ProtocolPaused {
+            version: EVENT_VERSION,
+            paused,
+        });
         msg!("Protocol paused: {}", paused);
         Ok(())
     }
@@ -945,8 +1606,15 @@ pub mod idl_protocol {
     /// RICK FIX: Initiate authority transfer with timelock
     pub fn initiate_authority_transfer(ctx: Context<AdminOnly>, new_authority: Pubkey) -> Result<()> {
         let state = &mut ctx.accounts.state;
+        let now = Clock::get()?.unix_timestamp;
         state.pending_authority = Some(new_authority);
-        state.authority_transfer_time = Some(Clock::get()?.unix_timestamp);
+        state.authority_transfer_time = Some(now);
+        emit!(AuthorityTransferInitiated {
+            version: EVENT_VERSION,
+            current_authority: state.authority,
+            new_authority,
+            executable_at: now + AUTHORITY_TIMELOCK,
+        });
         msg!("Authority transfer initiated to {}. Must wait {} seconds.", new_authority, AUTHORITY_TIMELOCK);
         Ok(())
     }
@@ -967,6 +1635,10 @@ pub mod idl_protocol {
         state.pending_authority = None;
         state.authority_transfer_time = None;
 
+        emit!(AuthorityTransferred {
+            version: EVENT_VERSION,
+            new_authority,
+        });
         msg!("Authority transferred to {}", new_authority);
         Ok(())
     }
@@ -979,22 +1651,179 @@ pub mod idl_protocol {
         state.pending_authority = None;
         state.authority_transfer_time = None;
 
+        emit!(AuthorityTransferCancelled {
+            version: EVENT_VERSION,
+            authority: state.authority,
+        });
         msg!("Authority transfer cancelled");
         Ok(())
     }
 
+    /// Propose new economic parameters; they apply after AUTHORITY_TIMELOCK
+    pub fn propose_config_update(ctx: Context<ConfigAdmin>, params: ConfigParams) -> Result<()> {
+        params.validate()?;
+        let config = &mut ctx.accounts.config;
+        let now = Clock::get()?.unix_timestamp;
+        config.pending_params = Some(params);
+        config.update_time = Some(now);
+        emit!(ConfigUpdateProposed {
+            version: EVENT_VERSION,
+            params,
+            executable_at: now + AUTHORITY_TIMELOCK,
+        });
+        msg!("Config update proposed. Must wait {} seconds.", AUTHORITY_TIMELOCK);
+        Ok(())
+    }
+
+    /// Apply the pending config update after the timelock expires
+    pub fn apply_config_update(ctx: Context<ConfigAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let update_time = config.update_time.ok_or(IdlError::NoConfigUpdatePending)?;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= update_time + AUTHORITY_TIMELOCK,
+            IdlError::TimelockNotExpired
+        );
+
+        config.params = config.pending_params.take().ok_or(IdlError::NoConfigUpdatePending)?;
+        config.update_time = None;
+
+        emit!(ConfigUpdateApplied {
+            version: EVENT_VERSION,
+            params: config.params,
+        });
+        msg!("Config update applied");
+        Ok(())
+    }
+
+    /// Cancel the pending config update
+    pub fn cancel_config_update(ctx: Context<ConfigAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.pending_params.is_some(), IdlError::NoConfigUpdatePending);
+
+        config.pending_params = None;
+        config.update_time = None;
+
+        emit!(ConfigUpdateCancelled {
+            version: EVENT_VERSION,
+        });
+        msg!("Config update cancelled");
+        Ok(())
+    }
+
     /// TIER 3: Raise TVL cap (gradual rollout)
-    pub fn raise_tvl_cap(ctx: Context<AdminOnly>) -> Result<()> {
+    /// TVL_CAP_RACE FIX: Opens a TVL_RAISE_QUEUE_WINDOW queue; the new headroom is split pro-rata
+    /// between queued deposits and direct stakes are blocked until every entry is settled.
+    pub fn raise_tvl_cap(ctx: Context<RaiseTvlCap>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let state = &mut ctx.accounts.state;
+        let clock = Clock::get()?;
+
+        require!(!state.tvl_raise_active(clock.unix_timestamp), IdlError::TvlRaiseQueueActive);
 
         let new_cap = state.tvl_cap
-            .checked_add(TVL_CAP_INCREMENT)
+            .checked_add(cfg.tvl_cap_increment)
             .ok_or(IdlError::MathOverflow)?;
 
         require!(new_cap <= MAX_TVL_CAP, IdlError::MaxTvlCapReached);
 
-        state.tvl_cap = new_cap;
-        msg!("TVL cap raised to {}", new_cap);
+        state.open_tvl_raise(new_cap, cfg.tvl_raise_queue_window, clock.unix_timestamp);
+        emit!(TvlRaiseOpened {
+            version: EVENT_VERSION,
+            raise_id: state.tvl_raise_id,
+            new_cap,
+            headroom: state.tvl_raise_headroom,
+            queue_ends_at: state.tvl_raise_end,
+        });
+        msg!("TVL cap raised to {}, queue open until {}", new_cap, state.tvl_raise_end);
+        Ok(())
+    }
+
+    /// TVL_CAP_RACE FIX: Queue a deposit for the open TVL raise (escrowed in the vault)
+    pub fn join_tvl_raise(ctx: Context<JoinTvlRaise>, amount: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(amount >= cfg.min_stake_amount, IdlError::StakeTooSmall);
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp < ctx.accounts.state.tvl_raise_end, IdlError::TvlRaiseClosed);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount
+        )?;
+
+        let state = &mut ctx.accounts.state;
+        state.tvl_raise_requested = state.tvl_raise_requested
+            .checked_add(amount)
+            .ok_or(IdlError::MathOverflow)?;
+        state.tvl_raise_pending = state.tvl_raise_pending
+            .checked_add(amount)
+            .ok_or(IdlError::MathOverflow)?;
+
+        let entry = &mut ctx.accounts.entry;
+        entry.owner = ctx.accounts.user.key();
+        entry.raise_id = state.tvl_raise_id;
+        entry.amount = amount;
+        entry.bump = ctx.bumps.entry;
+
+        emit!(TvlRaiseJoined {
+            version: EVENT_VERSION,
+            raise_id: entry.raise_id,
+            user: entry.owner,
+            amount,
+        });
+        msg!("Queued {} for TVL raise {}", amount, entry.raise_id);
+        Ok(())
+    }
+
+    /// TVL_CAP_RACE FIX: Settle a queue entry after the window closes (permissionless)
+    /// Stakes the entry's pro-rata share of the headroom and refunds the rest.
+    pub fn settle_tvl_raise_entry(ctx: Context<SettleTvlRaiseEntry>) -> Result<()> {
+        let clock = Clock::get()?;
+        let state = &mut ctx.accounts.state;
+        let entry = &ctx.accounts.entry;
+
+        require!(clock.unix_timestamp >= state.tvl_raise_end, IdlError::TvlRaiseQueueActive);
+
+        let allocation = state.tvl_raise_allocation(entry.amount);
+        let refund = entry.amount - allocation;
+
+        if allocation > 0 {
+            credit_stake(
+                state,
+                &mut ctx.accounts.staker_account,
+                entry.owner,
+                ctx.bumps.staker_account,
+                allocation,
+                clock.unix_timestamp
+            )?;
+        }
+        state.tvl_raise_pending = state.tvl_raise_pending.saturating_sub(entry.amount);
+
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.owner_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            refund
+        )?;
+
+        emit!(TvlRaiseEntrySettled {
+            version: EVENT_VERSION,
+            raise_id: ctx.accounts.entry.raise_id,
+            user: ctx.accounts.entry.owner,
+            staked: allocation,
+            refunded: refund,
+        });
+        msg!("TVL raise entry settled: {} staked, {} refunded", allocation, refund);
         Ok(())
     }
 
@@ -1033,6 +1862,12 @@ pub mod idl_protocol {
 
         // Update state after transfer
         ctx.accounts.state.insurance_fund = ctx.accounts.state.insurance_fund.saturating_sub(amount);
+        emit!(InsuranceWithdrawn {
+            version: EVENT_VERSION,
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            insurance_fund: ctx.accounts.state.insurance_fund,
+        });
         msg!("Withdrew {} from insurance fund", amount);
         Ok(())
     }
@@ -1041,6 +1876,7 @@ pub mod idl_protocol {
 
     /// 10/10 FIX: Commit a bet (step 1 of commit-reveal)
     pub fn commit_bet(ctx: Context<CommitBet>, commitment: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
         let market = &ctx.accounts.market;
@@ -1051,10 +1887,19 @@ pub mod idl_protocol {
 
         // Must commit before betting closes
         require!(
-            clock.unix_timestamp < market.resolution_timestamp - BETTING_CLOSE_WINDOW,
+            clock.unix_timestamp < market.resolution_timestamp - cfg.betting_close_window,
             IdlError::BettingClosed
         );
 
+        post_commitment_bond(
+            cfg,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_account.amount
+        )?;
+
         let bet_commitment = &mut ctx.accounts.bet_commitment;
         bet_commitment.owner = ctx.accounts.user.key();
         bet_commitment.market = market.key();
@@ -1062,8 +1907,56 @@ pub mod idl_protocol {
         bet_commitment.commit_time = clock.unix_timestamp;
         bet_commitment.revealed = false;
         bet_commitment.bump = ctx.bumps.bet_commitment;
+        bet_commitment.bond_amount = cfg.commitment_bond_amount;
+        bet_commitment.bond_payer = ctx.accounts.user.key();
+
+        emit!(BetCommitted {
+            version: EVENT_VERSION,
+            market: bet_commitment.market,
+            bettor: bet_commitment.owner,
+            bond_amount: bet_commitment.bond_amount,
+            reveal_deadline: bet_commitment.commit_time + cfg.bet_reveal_window,
+        });
+        msg!("Bet committed, must reveal within {} seconds", cfg.bet_reveal_window);
+        Ok(())
+    }
+
+    /// Close an unrevealed commitment after COMMITMENT_EXPIRY so the owner can commit again
+    /// STALE_COMMITMENT FIX: The bond is refunded if the market stopped taking reveals before the
+    /// reveal window ended (cancelled, or the window ran past the resolution time). Otherwise the
+    /// reveal was withheld and the bond is forfeited to the insurance fund.
+    pub fn reclaim_commitment_bond(ctx: Context<ReclaimCommitmentBond>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let commitment = &ctx.accounts.bet_commitment;
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
 
-        msg!("Bet committed, must reveal within {} seconds", BET_REVEAL_WINDOW);
+        let withheld = commitment.check_expired(cfg, market, clock.unix_timestamp)?;
+        let bond_amount = commitment.bond_amount;
+
+        if withheld {
+            // Bond stays in the vault as protocol reserves
+            ctx.accounts.state.insurance_fund = ctx.accounts.state.insurance_fund
+                .saturating_add(bond_amount);
+            msg!("Reveal withheld: commitment bond {} forfeited", bond_amount);
+        } else {
+            transfer_from_vault(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.vault.to_account_info(),
+                &ctx.accounts.payer_token_account.to_account_info(),
+                &ctx.accounts.state.to_account_info(),
+                ctx.accounts.state.bump,
+                bond_amount
+            )?;
+            msg!("Commitment bond reclaimed: {}", bond_amount);
+        }
+        emit!(CommitmentBondReleased {
+            version: EVENT_VERSION,
+            market: market.key(),
+            bettor: commitment.owner,
+            bond_amount,
+            forfeited: withheld,
+        });
         Ok(())
     }
 
@@ -1075,9 +1968,10 @@ pub mod idl_protocol {
         nonce: u64,
         salt: [u8; 32]
     ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
-        require!(amount >= MIN_BET_AMOUNT, IdlError::BetTooSmall);
-        require!(amount <= MAX_BET_AMOUNT, IdlError::BetTooLarge);
+        require!(amount >= cfg.min_bet_amount, IdlError::BetTooSmall);
+        require!(amount <= cfg.max_bet_amount, IdlError::BetTooLarge);
 
         let commitment = &mut ctx.accounts.bet_commitment;
         let market = &mut ctx.accounts.market;
@@ -1085,33 +1979,55 @@ pub mod idl_protocol {
 
         require!(!commitment.revealed, IdlError::AlreadyRevealed);
         require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.is_two_sided(), IdlError::WrongMarketType);
 
         // Check reveal window
         require!(
-            clock.unix_timestamp >= commitment.commit_time + BET_COMMIT_WINDOW,
+            clock.unix_timestamp <= commitment.commit_time + cfg.commitment_expiry,
+            IdlError::CommitmentExpired
+        );
+        require!(
+            clock.unix_timestamp >= commitment.commit_time + cfg.bet_commit_window,
             IdlError::RevealTooEarly
         );
         require!(
-            clock.unix_timestamp <= commitment.commit_time + BET_REVEAL_WINDOW,
+            clock.unix_timestamp <= commitment.commit_time + cfg.bet_reveal_window,
             IdlError::RevealTooLate
         );
 
         // Verify commitment hash
-        let mut hasher_input = Vec::new();
-        hasher_input.extend_from_slice(&amount.to_le_bytes());
-        hasher_input.push(if bet_yes { 1 } else { 0 });
-        hasher_input.extend_from_slice(&nonce.to_le_bytes());
-        hasher_input.extend_from_slice(&salt);
-
-        let computed_hash = anchor_lang::solana_program::hash::hash(&hasher_input);
         require!(
-            computed_hash.to_bytes() == commitment.commitment,
+            bet_commitment_hash(amount, bet_yes as u8, nonce, &salt) == commitment.commitment,
             IdlError::InvalidCommitment
         );
 
         commitment.revealed = true;
+        let commit_time = commitment.commit_time;
+
+        // Commitment bond comes back on reveal (the commitment account is closed)
+        let bond_amount = commitment.bond_amount;
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            bond_amount
+        )?;
+
+        // PREDICTION MINING: Creator's volume fee is taken from the stake
+        let creator_fee = accrue_creator_volume_fee(cfg, &mut ctx.accounts.creator_stats, amount);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            creator_fee
+        )?;
+        let amount = amount - creator_fee;
 
-        // Transfer tokens
         let cpi_accounts = Transfer {
             from: ctx.accounts.user_token_account.to_account_info(),
             to: ctx.accounts.market_pool.to_account_info(),
@@ -1127,26 +2043,22 @@ pub mod idl_protocol {
             .as_ref()
             .map(|s| s.staked_amount)
             .unwrap_or(0);
+        let early_bird_bonus = early_bird_bonus(cfg, market, commit_time, amount);
+        // Active lootbox stake boost adds its bps on top
+        let lootbox_boost = ctx.accounts.lootbox_reward
+            .as_ref()
+            .map(|l| l.stake_boost(amount, clock.unix_timestamp))
+            .unwrap_or(0);
+        let effective_amount = with_stake_bonus(cfg, amount, staked_amount)
+            .saturating_add(early_bird_bonus)
+            .saturating_add(lootbox_boost);
 
-        let stake_millions = staked_amount / 1_000_000;
-        let stake_bonus = std::cmp::min(
-            stake_millions.saturating_mul(STAKE_BONUS_PER_MILLION),
-            MAX_STAKE_BONUS_BPS
-        );
-        let multiplier = 10000u64.saturating_add(stake_bonus);
-        let effective_amount = (amount as u128)
-            .saturating_mul(multiplier as u128)
-            / 10000;
-        let effective_amount = effective_amount as u64;
-
-        // Update market
-        if bet_yes {
-            market.total_yes_actual = market.total_yes_actual.saturating_add(amount);
-            market.total_yes_amount = market.total_yes_amount.saturating_add(effective_amount);
-        } else {
-            market.total_no_actual = market.total_no_actual.saturating_add(amount);
-            market.total_no_amount = market.total_no_amount.saturating_add(effective_amount);
-        }
+        // COMMITMENT_SNIPE FIX: Held in the pending batch until BATCH_REVEAL_DELAY has passed
+        market.add_pending_reveal(cfg, bet_yes as u8, amount, effective_amount, clock.unix_timestamp);
+
+        let odds = &mut ctx.accounts.dynamic_odds;
+        odds.init_for(cfg, market, ctx.bumps.dynamic_odds, clock.unix_timestamp);
+        odds.record_volume(cfg, market, amount, clock.unix_timestamp);
 
         // Create bet record
         let bet = &mut ctx.accounts.bet;
@@ -1155,10 +2067,12 @@ pub mod idl_protocol {
         bet.amount = amount;
         bet.effective_amount = effective_amount;
         bet.bet_yes = bet_yes;
+        bet.outcome_index = bet_yes as u8;
         bet.timestamp = clock.unix_timestamp;
         bet.claimed = false;
         bet.nonce = nonce;
         bet.bump = ctx.bumps.bet;
+        bet.early_bird_bonus = early_bird_bonus;
 
         // AUDIT FIX: Update user volume for badge tracking (was missing in commit-reveal path)
         let user_volume = &mut ctx.accounts.user_volume;
@@ -1171,74 +2085,264 @@ pub mod idl_protocol {
             .ok_or(IdlError::MathOverflow)?;
         user_volume.last_updated = clock.unix_timestamp;
 
+        emit!(BetRevealed {
+            version: EVENT_VERSION,
+            market: bet.market,
+            bettor: bet.owner,
+            outcome_index: bet.outcome_index,
+            amount,
+            effective_amount: bet.effective_amount,
+            creator_fee,
+        });
         msg!("Bet revealed: {} on {}", amount, if bet_yes { "YES" } else { "NO" });
         Ok(())
     }
 
-    /// 10/10 FIX: Oracle deposits bond before they can resolve markets
-    pub fn deposit_oracle_bond(ctx: Context<DepositOracleBond>) -> Result<()> {
-        // Transfer bond from oracle to vault
+    /// Reveal a committed bet on one outcome of a categorical market
+    /// The commitment hashes the outcome index in place of the YES/NO byte.
+    pub fn reveal_categorical_bet(
+        ctx: Context<RevealCategoricalBet>,
+        amount: u64,
+        outcome_index: u8,
+        nonce: u64,
+        salt: [u8; 32]
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(amount >= cfg.min_bet_amount, IdlError::BetTooSmall);
+        require!(amount <= cfg.max_bet_amount, IdlError::BetTooLarge);
+
+        let commitment = &mut ctx.accounts.bet_commitment;
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(!commitment.revealed, IdlError::AlreadyRevealed);
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.market_type == MARKET_TYPE_CATEGORICAL, IdlError::WrongMarketType);
+        require!(outcome_index < market.outcome_count, IdlError::InvalidOutcome);
+        require!(
+            clock.unix_timestamp <= commitment.commit_time + cfg.commitment_expiry,
+            IdlError::CommitmentExpired
+        );
+        require!(
+            clock.unix_timestamp >= commitment.commit_time + cfg.bet_commit_window,
+            IdlError::RevealTooEarly
+        );
+        require!(
+            clock.unix_timestamp <= commitment.commit_time + cfg.bet_reveal_window,
+            IdlError::RevealTooLate
+        );
+        require!(
+            bet_commitment_hash(amount, outcome_index, nonce, &salt) == commitment.commitment,
+            IdlError::InvalidCommitment
+        );
+
+        commitment.revealed = true;
+        let commit_time = commitment.commit_time;
+
+        // Commitment bond comes back on reveal (the commitment account is closed)
+        let bond_amount = commitment.bond_amount;
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            bond_amount
+        )?;
+
+        // PREDICTION MINING: Creator's volume fee is taken from the stake
+        let creator_fee = accrue_creator_volume_fee(cfg, &mut ctx.accounts.creator_stats, amount);
         let cpi_accounts = Transfer {
-            from: ctx.accounts.oracle_token_account.to_account_info(),
+            from: ctx.accounts.user_token_account.to_account_info(),
             to: ctx.accounts.vault.to_account_info(),
-            authority: ctx.accounts.oracle.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
         };
         token::transfer(
             CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            ORACLE_BOND_AMOUNT
+            creator_fee
         )?;
+        let amount = amount - creator_fee;
 
-        let bond = &mut ctx.accounts.oracle_bond;
-        bond.oracle = ctx.accounts.oracle.key();
-        bond.bond_amount = ORACLE_BOND_AMOUNT;
-        bond.bonded_at = Clock::get()?.unix_timestamp;
-        bond.slashed = false;
-        bond.bump = ctx.bumps.oracle_bond;
-        bond.active_resolution = None;  // SELF-REVIEW FIX: Initialize
-
-        msg!("Oracle bond deposited: {}", ORACLE_BOND_AMOUNT);
-        Ok(())
-    }
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.market_pool.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount
+        )?;
 
-    /// 10/10 FIX: Oracle commits resolution (step 1)
-    pub fn commit_resolution(ctx: Context<CommitResolution>, commitment: [u8; 32]) -> Result<()> {
-        let market = &ctx.accounts.market;
-        let oracle_bond = &mut ctx.accounts.oracle_bond;  // SELF-REVIEW FIX: Make mutable
-        let clock = Clock::get()?;
+        let staked_amount = ctx.accounts.staker_account
+            .as_ref()
+            .map(|s| s.staked_amount)
+            .unwrap_or(0);
+        let early_bird_bonus = early_bird_bonus(cfg, market, commit_time, amount);
+        // Active lootbox stake boost adds its bps on top
+        let lootbox_boost = ctx.accounts.lootbox_reward
+            .as_ref()
+            .map(|l| l.stake_boost(amount, clock.unix_timestamp))
+            .unwrap_or(0);
+        let effective_amount = with_stake_bonus(cfg, amount, staked_amount)
+            .saturating_add(early_bird_bonus)
+            .saturating_add(lootbox_boost);
 
-        require!(oracle_bond.bond_amount >= ORACLE_BOND_AMOUNT, IdlError::InsufficientOracleBond);
-        require!(!oracle_bond.slashed, IdlError::OracleSlashed);
-        require!(!market.resolved, IdlError::MarketResolved);
-        require!(clock.unix_timestamp >= market.resolution_timestamp, IdlError::ResolutionTooEarly);
+        market.add_pending_reveal(cfg, outcome_index, amount, effective_amount, clock.unix_timestamp);
 
-        // SELF-REVIEW FIX: Prevent multi-market exploit - only one resolution at a time
-        require!(
-            oracle_bond.active_resolution.is_none(),
-            IdlError::OracleHasPendingResolution
-        );
+        let bet = &mut ctx.accounts.bet;
+        bet.owner = ctx.accounts.user.key();
+        bet.market = market.key();
+        bet.amount = amount;
+        bet.effective_amount = effective_amount;
+        bet.bet_yes = false;
+        bet.outcome_index = outcome_index;
+        bet.timestamp = clock.unix_timestamp;
+        bet.claimed = false;
+        bet.nonce = nonce;
+        bet.bump = ctx.bumps.bet;
+        bet.early_bird_bonus = early_bird_bonus;
+
+        let user_volume = &mut ctx.accounts.user_volume;
+        if user_volume.user == Pubkey::default() {
+            user_volume.user = ctx.accounts.user.key();
+            user_volume.bump = ctx.bumps.user_volume;
+        }
+        user_volume.total_volume_usd = user_volume.total_volume_usd
+            .checked_add(amount)
+            .ok_or(IdlError::MathOverflow)?;
+        user_volume.last_updated = clock.unix_timestamp;
+
+        emit!(BetRevealed {
+            version: EVENT_VERSION,
+            market: bet.market,
+            bettor: bet.owner,
+            outcome_index: bet.outcome_index,
+            amount,
+            effective_amount: bet.effective_amount,
+            creator_fee,
+        });
+        msg!("Bet revealed: {} on outcome {}", amount, outcome_index);
+        Ok(())
+    }
+
+    /// 10/10 FIX: Oracle deposits bond before they can resolve markets
+    pub fn deposit_oracle_bond(ctx: Context<DepositOracleBond>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        // Transfer bond from oracle to vault
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.oracle_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.oracle.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            cfg.oracle_bond_amount
+        )?;
+
+        let bond = &mut ctx.accounts.oracle_bond;
+        bond.oracle = ctx.accounts.oracle.key();
+        bond.bond_amount = cfg.oracle_bond_amount;
+        bond.bonded_at = Clock::get()?.unix_timestamp;
+        bond.slashed = false;
+        bond.bump = ctx.bumps.oracle_bond;
+        bond.active_resolution = None;  // SELF-REVIEW FIX: Initialize
+
+        emit!(OracleBondDeposited {
+            version: EVENT_VERSION,
+            oracle: bond.oracle,
+            amount: bond.bond_amount,
+        });
+        msg!("Oracle bond deposited: {}", cfg.oracle_bond_amount);
+        Ok(())
+    }
+
+    /// Register a bonded oracle to vote on a market's resolution (admin only)
+    /// Each market takes up to MAX_ORACLES_PER_MARKET oracles, each with its own commitment.
+    pub fn register_market_oracle(ctx: Context<RegisterMarketOracle>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let market = &mut ctx.accounts.market;
+        let oracle_bond = &ctx.accounts.oracle_bond;
+
+        require!(oracle_bond.bond_amount >= cfg.oracle_bond_amount, IdlError::InsufficientOracleBond);
+        require!(!oracle_bond.slashed, IdlError::OracleSlashed);
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+        require!(market.parent_market.is_none(), IdlError::HedgeResolvesFromParent);
+        require!(market.oracle_count < MAX_ORACLES_PER_MARKET, IdlError::MaxOraclesReached);
 
         let res_commit = &mut ctx.accounts.resolution_commitment;
         res_commit.market = market.key();
-        res_commit.oracle = ctx.accounts.oracle.key();
-        res_commit.commitment = commitment;
-        res_commit.commit_time = clock.unix_timestamp;
+        res_commit.oracle = oracle_bond.oracle;
+        res_commit.commitment = [0; 32];
+        res_commit.commit_time = 0;
         res_commit.revealed = false;
         res_commit.disputed = false;
         res_commit.bump = ctx.bumps.resolution_commitment;
+        res_commit.value = 0;
+        res_commit.value_b = 0;
+
+        market.oracle_count += 1;
+
+        emit!(OracleRegistered {
+            version: EVENT_VERSION,
+            market: market.key(),
+            oracle: oracle_bond.oracle,
+            oracle_count: market.oracle_count,
+        });
+        msg!("Oracle {} registered ({} of {})", oracle_bond.oracle, market.oracle_count, MAX_ORACLES_PER_MARKET);
+        Ok(())
+    }
+
+    /// 10/10 FIX: Oracle commits resolution (step 1)
+    pub fn commit_resolution(ctx: Context<CommitResolution>, commitment: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let market = &ctx.accounts.market;
+        let oracle_bond = &mut ctx.accounts.oracle_bond;  // SELF-REVIEW FIX: Make mutable
+        let clock = Clock::get()?;
+
+        require!(oracle_bond.bond_amount >= cfg.oracle_bond_amount, IdlError::InsufficientOracleBond);
+        require!(!oracle_bond.slashed, IdlError::OracleSlashed);
+        require!(!market.resolved, IdlError::MarketResolved);
+        // Hedge markets settle from their parent
+        require!(market.parent_market.is_none(), IdlError::HedgeResolvesFromParent);
+        require!(clock.unix_timestamp >= market.resolution_timestamp, IdlError::ResolutionTooEarly);
+        require!(
+            market.oracle_count >= cfg.min_oracle_consensus,
+            IdlError::InsufficientOracleConsensus
+        );
+
+        // SELF-REVIEW FIX: Prevent multi-market exploit - only one resolution at a time
+        require!(
+            oracle_bond.active_resolution.is_none(),
+            IdlError::OracleHasPendingResolution
+        );
 
-        // SELF-REVIEW FIX: Lock this oracle to this market until resolution complete
+        let res_commit = &mut ctx.accounts.resolution_commitment;
+        require!(res_commit.commit_time == 0, IdlError::ResolutionAlreadyCommitted);
+        res_commit.commitment = commitment;
+        res_commit.commit_time = clock.unix_timestamp;
+
+        // SELF-REVIEW FIX: Lock this oracle to this market until its dispute window closes
         oracle_bond.active_resolution = Some(market.key());
 
+        emit!(ResolutionCommitted {
+            version: EVENT_VERSION,
+            market: market.key(),
+            oracle: oracle_bond.oracle,
+        });
         msg!("Resolution committed");
         Ok(())
     }
 
     /// 10/10 FIX: Oracle reveals resolution (step 2)
+    /// ATTACK FIX: Only records the vote - the market resolves in finalize_resolution
     pub fn reveal_resolution(
         ctx: Context<RevealResolution>,
         actual_value: u64,
         nonce: u64
     ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let res_commit = &mut ctx.accounts.resolution_commitment;
         let market = &mut ctx.accounts.market;
         let clock = Clock::get()?;
@@ -1248,7 +2352,7 @@ pub mod idl_protocol {
 
         // Must wait minimum time after commit
         require!(
-            clock.unix_timestamp >= res_commit.commit_time + BET_COMMIT_WINDOW,
+            clock.unix_timestamp >= res_commit.commit_time + cfg.bet_commit_window,
             IdlError::RevealTooEarly
         );
 
@@ -1263,77 +2367,427 @@ pub mod idl_protocol {
             IdlError::InvalidCommitment
         );
 
+        require!(market.market_type != MARKET_TYPE_COMPARISON, IdlError::WrongMarketType);
+        if market.market_type == MARKET_TYPE_CATEGORICAL {
+            require!(actual_value < market.outcome_count as u64, IdlError::InvalidOutcome);
+        }
+
+        // ATTACK FIX: Record this oracle's vote; finalize_resolution settles on consensus
         res_commit.revealed = true;
+        res_commit.value = actual_value;
+
+        emit!(ResolutionRevealed {
+            version: EVENT_VERSION,
+            market: market.key(),
+            oracle: res_commit.oracle,
+            value: res_commit.value,
+            value_b: res_commit.value_b,
+        });
+        msg!("Oracle revealed resolution: {}", actual_value);
+        Ok(())
+    }
 
-        // Resolve market
-        let outcome = actual_value >= market.target_value;
-        market.outcome = Some(outcome);
-        market.actual_value = Some(actual_value);
+    /// Oracle reveals both values of a comparison market in one commit-reveal
+    /// Commitment is hash(value_a, value_b, nonce). A consensus tie cancels the market for refunds.
+    pub fn reveal_comparison_resolution(
+        ctx: Context<RevealResolution>,
+        value_a: u64,
+        value_b: u64,
+        nonce: u64
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let res_commit = &mut ctx.accounts.resolution_commitment;
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(market.market_type == MARKET_TYPE_COMPARISON, IdlError::WrongMarketType);
+        require!(!res_commit.revealed, IdlError::AlreadyRevealed);
+        require!(!res_commit.disputed, IdlError::ResolutionDisputed);
+        require!(
+            clock.unix_timestamp >= res_commit.commit_time + cfg.bet_commit_window,
+            IdlError::RevealTooEarly
+        );
+
+        let mut hasher_input = Vec::new();
+        hasher_input.extend_from_slice(&value_a.to_le_bytes());
+        hasher_input.extend_from_slice(&value_b.to_le_bytes());
+        hasher_input.extend_from_slice(&nonce.to_le_bytes());
+
+        let computed_hash = anchor_lang::solana_program::hash::hash(&hasher_input);
+        require!(
+            computed_hash.to_bytes() == res_commit.commitment,
+            IdlError::InvalidCommitment
+        );
+
+        res_commit.revealed = true;
+        res_commit.value = value_a;
+        res_commit.value_b = value_b;
+
+        emit!(ResolutionRevealed {
+            version: EVENT_VERSION,
+            market: market.key(),
+            oracle: res_commit.oracle,
+            value: res_commit.value,
+            value_b: res_commit.value_b,
+        });
+        msg!("Oracle revealed comparison: {} vs {}", value_a, value_b);
+        Ok(())
+    }
+
+    /// Settle a market once its oracles agree (permissionless)
+    /// remaining_accounts: (resolution_commitment, oracle_bond) pairs for every registered oracle.
+    /// Runs once all oracles have revealed, or ORACLE_REVEAL_DEADLINE after the resolution time.
+    /// At least MIN_ORACLE_CONSENSUS oracles and ORACLE_CONSENSUS_THRESHOLD% of reveals must agree;
+    /// dissenting oracles are slashed, as are oracles that committed but never revealed.
+    /// Without consensus the market is cancelled for refunds.
+    pub fn finalize_resolution<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeResolution<'info>>
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+        let market_key = market.key();
+
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+        require!(
+            market.oracle_count >= cfg.min_oracle_consensus,
+            IdlError::InsufficientOracleConsensus
+        );
+
+        let oracles = load_market_oracles(ctx.remaining_accounts, market, market_key, ctx.program_id)?;
+        let votes: Vec<Option<(u64, u64)>> = oracles
+            .iter()
+            .map(|(res_commit, _)| {
+                (res_commit.revealed && !res_commit.disputed)
+                    .then_some((res_commit.value, res_commit.value_b))
+            })
+            .collect();
+        let withheld: Vec<bool> = oracles
+            .iter()
+            .map(|(res_commit, _)| res_commit.commit_time != 0 && !res_commit.revealed)
+            .collect();
+        let mut bonds: Vec<Account<'info, OracleBond>> = oracles.into_iter().map(|(_, bond)| bond).collect();
+
+        let all_revealed = votes.iter().all(|vote| vote.is_some());
+        require!(
+            all_revealed || clock.unix_timestamp >= market.resolution_timestamp + cfg.oracle_reveal_deadline,
+            IdlError::OracleRevealsPending
+        );
+
+        // Tally reveals by the outcome they imply
+        let keys: Vec<Option<u64>> = votes
+            .iter()
+            .map(|vote| vote.map(|(value, value_b)| market.consensus_key(value, value_b)))
+            .collect();
+        let revealed = keys.iter().flatten().count();
+        let (winning_key, agreeing, consensus) = tally_oracle_votes(cfg, &keys);
+        let consensus_key = consensus.then_some(winning_key);
+
+        // Slash oracles that voted against consensus or withheld their vote. With consensus every
+        // oracle stays locked to this market until its dispute window closes (see release_oracle_bond);
+        // a cancelled market can't be disputed, so its locks are released here.
+        let mut slashed_total = 0u64;
+        for ((bond, key), withheld) in bonds.iter_mut().zip(keys.iter()).zip(withheld) {
+            if oracle_slashable(*key, withheld, consensus_key) {
+                let slash_amount = (bond.bond_amount * cfg.oracle_slash_percent) / 100;
+                bond.bond_amount = bond.bond_amount.saturating_sub(slash_amount);
+                bond.slashed = true;
+                slashed_total = slashed_total.saturating_add(slash_amount);
+                emit!(OracleSlashed {
+                    version: EVENT_VERSION,
+                    market: market_key,
+                    oracle: bond.oracle,
+                    amount: slash_amount,
+                });
+            }
+            if !consensus && bond.active_resolution == Some(market_key) {
+                bond.active_resolution = None;
+            }
+            bond.exit(ctx.program_id)?;
+        }
+        ctx.accounts.state.insurance_fund = ctx.accounts.state.insurance_fund
+            .saturating_add(slashed_total);
+
+        if !consensus {
+            market.status = MARKET_STATUS_CANCELLED;
+            emit!(MarketCancelled {
+                version: EVENT_VERSION,
+                market: market_key,
+                creation_stake_slashed: 0,
+            });
+            msg!("No oracle consensus ({} of {} agree): market cancelled for refunds", agreeing, revealed);
+            return Ok(());
+        }
+
+        if market.is_two_sided() {
+            market.oracle_votes_yes = keys.iter().flatten().filter(|k| **k == 1).count() as u8;
+            market.oracle_votes_no = keys.iter().flatten().filter(|k| **k == 0).count() as u8;
+        }
+
+        let (value, value_b) = keys
+            .iter()
+            .zip(votes.iter())
+            .find(|(key, _)| **key == Some(winning_key))
+            .and_then(|(_, vote)| *vote)
+            .ok_or(IdlError::InsufficientOracleConsensus)?;
+
+        // Comparison ties refund everyone
+        if market.market_type == MARKET_TYPE_COMPARISON && value == value_b {
+            market.actual_value = Some(value);
+            market.comparison_value = Some(value_b);
+            market.status = MARKET_STATUS_CANCELLED;
+            emit!(MarketCancelled {
+                version: EVENT_VERSION,
+                market: market_key,
+                creation_stake_slashed: 0,
+            });
+            msg!("Comparison tied at {}: market cancelled for refunds", value);
+            return Ok(());
+        }
+
+        market.flush_pending_reveals();
+        market.apply_resolution(value, value_b)?;
         market.resolved = true;
         market.resolved_at = Some(clock.unix_timestamp);
         market.status = MARKET_STATUS_RESOLVED;
 
-        msg!("Market resolved via commit-reveal: {}", if outcome { "YES" } else { "NO" });
+        emit_market_resolved(market);
+        msg!("Market resolved by {} of {} oracles: {} (slashed: {})",
+            agreeing, revealed, value, slashed_total);
         Ok(())
     }
 
-    /// 10/10 FIX: Dispute a resolution (slashes oracle if authority agrees)
-    /// AUDIT FIX: Now also cancels market (safer than re-resolution since claims may have happened)
+    /// Dispute a resolution by posting DISPUTE_BOND_AMOUNT (anyone, inside the dispute window)
+    /// DISPUTE_GRIEF FIX: Opens a veIDL vote instead of cancelling the market outright.
+    /// Claims are frozen until finalize_dispute settles the vote.
     pub fn dispute_resolution(ctx: Context<DisputeResolution>) -> Result<()> {
-        let res_commit = &mut ctx.accounts.resolution_commitment;
-        let oracle_bond = &mut ctx.accounts.oracle_bond;
+        let cfg = &ctx.accounts.config.params;
         let market = &mut ctx.accounts.market;
         let clock = Clock::get()?;
 
-        require!(res_commit.revealed, IdlError::NotRevealed);
-        require!(!res_commit.disputed, IdlError::ResolutionDisputed);
+        require!(market.status == MARKET_STATUS_RESOLVED, IdlError::MarketNotResolved);
+        require!(market.parent_market.is_none(), IdlError::HedgeResolvesFromParent);
 
         // Must be within dispute window
+        let resolved_at = market.resolved_at.ok_or(IdlError::MarketNotResolved)?;
         require!(
-            clock.unix_timestamp <= res_commit.commit_time + ORACLE_DISPUTE_WINDOW,
+            !dispute_window_closed(cfg, resolved_at, clock.unix_timestamp),
             IdlError::DisputeWindowClosed
         );
+        require!(
+            ctx.accounts.disputer_token_account.amount >= cfg.dispute_bond_amount,
+            IdlError::DisputeBondRequired
+        );
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.disputer_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.disputer.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            cfg.dispute_bond_amount
+        )?;
 
-        res_commit.disputed = true;
+        market.status = MARKET_STATUS_DISPUTED;
+
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.market = market.key();
+        dispute.disputer = ctx.accounts.disputer.key();
+        dispute.bond_amount = cfg.dispute_bond_amount;
+        dispute.opened_at = clock.unix_timestamp;
+        // VE_DECAY_ARBITRAGE FIX: Power is measured before the dispute could be anticipated
+        dispute.snapshot_time = clock.unix_timestamp - cfg.vote_snapshot_delay;
+        dispute.voting_ends_at = clock.unix_timestamp + cfg.dispute_vote_period;
+        dispute.votes_uphold = 0;
+        dispute.votes_overturn = 0;
+        dispute.votes_cancel = 0;
+        dispute.settled = false;
+        dispute.result = DISPUTE_CHOICE_UPHOLD;
+        dispute.bump = ctx.bumps.dispute;
+
+        emit!(DisputeOpened {
+            version: EVENT_VERSION,
+            market: dispute.market,
+            dispute: dispute.key(),
+            disputer: dispute.disputer,
+            bond_amount: dispute.bond_amount,
+            snapshot_time: dispute.snapshot_time,
+            voting_ends_at: dispute.voting_ends_at,
+        });
+        msg!("Resolution disputed, voting open until {}", dispute.voting_ends_at);
+        Ok(())
+    }
 
-        // SELF-REVIEW FIX: Cancel market instead of reset
-        // Re-resolution is dangerous because:
-        // 1. Some bets may have already been claimed
-        // 2. Old resolution commitment exists and is "disputed"
-        // 3. New oracle would need fresh commitment but old one blocks re-use
-        // Cancellation allows refunds which is safer
-        market.resolved = false;
-        market.resolved_at = None;
-        market.outcome = None;
-        market.actual_value = None;
-        market.status = MARKET_STATUS_CANCELLED;  // Cancel, don't reset to active
+    /// Vote on an open dispute with veIDL + badge power at the dispute's snapshot
+    pub fn vote_on_dispute(ctx: Context<VoteOnDispute>, choice: u8) -> Result<()> {
+        let dispute = &mut ctx.accounts.dispute;
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(!dispute.settled, IdlError::ResolutionDisputed);
+        require!(clock.unix_timestamp < dispute.voting_ends_at, IdlError::DisputeWindowClosed);
+        require!(choice <= DISPUTE_CHOICE_CANCEL, IdlError::InvalidInput);
+        if choice == DISPUTE_CHOICE_OVERTURN {
+            require!(
+                market.market_type == MARKET_TYPE_BINARY || market.market_type == MARKET_TYPE_COMPARISON,
+                IdlError::WrongMarketType
+            );
+        }
+
+        // Only power that existed at the snapshot counts
+        let weight = snapshot_voting_power(
+            ctx.accounts.ve_position.as_ref().map(|vp| &***vp),
+            ctx.accounts.badge.as_ref().map(|b| &***b),
+            dispute.snapshot_time
+        );
+        require!(weight > 0, IdlError::VoteSnapshotNotReady);
+
+        dispute.record_vote(choice, weight);
+
+        let vote = &mut ctx.accounts.dispute_vote;
+        vote.dispute = dispute.key();
+        vote.voter = ctx.accounts.voter.key();
+        vote.choice = choice;
+        vote.weight = weight;
+        vote.bump = ctx.bumps.dispute_vote;
+
+        emit!(DisputeVoteCast {
+            version: EVENT_VERSION,
+            dispute: vote.dispute,
+            voter: vote.voter,
+            choice,
+            weight,
+        });
+        msg!("Dispute vote: choice {} with {} power", choice, weight);
+        Ok(())
+    }
+
+    /// Settle a dispute after voting closes (permissionless)
+    /// remaining_accounts: (resolution_commitment, oracle_bond) pairs for every registered oracle.
+    /// Uphold (also wins ties): disputer bond slashed by DISPUTE_SLASH_IF_INVALID%.
+    /// Overturn / cancel: disputer bond returned, oracles that backed the resolution slashed.
+    pub fn finalize_dispute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeDispute<'info>>
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let clock = Clock::get()?;
+        let dispute = &mut ctx.accounts.dispute;
+        let market = &mut ctx.accounts.market;
+        let market_key = market.key();
+
+        require!(!dispute.settled, IdlError::ResolutionDisputed);
+        require!(clock.unix_timestamp >= dispute.voting_ends_at, IdlError::DisputeWindowOpen);
+        require!(market.status == MARKET_STATUS_DISPUTED, IdlError::MarketNotResolved);
+
+        let result = dispute.outcome();
+
+        // Slash the oracles whose votes produced the rejected resolution
+        let mut oracle_slash = 0u64;
+        if result != DISPUTE_CHOICE_UPHOLD {
+            let resolved_key = market.consensus_key(
+                market.actual_value.unwrap_or(0),
+                market.comparison_value.unwrap_or(0)
+            );
+            for (mut res_commit, mut bond) in load_market_oracles(ctx.remaining_accounts, market, market_key, ctx.program_id)? {
+                if res_commit.revealed
+                    && market.consensus_key(res_commit.value, res_commit.value_b) == resolved_key
+                {
+                    let slash_amount = (bond.bond_amount * cfg.oracle_slash_percent) / 100;
+                    bond.bond_amount = bond.bond_amount.saturating_sub(slash_amount);
+                    bond.slashed = true;
+                    oracle_slash = oracle_slash.saturating_add(slash_amount);
+                    emit!(OracleSlashed {
+                        version: EVENT_VERSION,
+                        market: market_key,
+                        oracle: bond.oracle,
+                        amount: slash_amount,
+                    });
+                    res_commit.disputed = true;
+                    res_commit.exit(ctx.program_id)?;
+                    bond.exit(ctx.program_id)?;
+                }
+            }
+        }
+
+        let bond_slash = if result == DISPUTE_CHOICE_UPHOLD {
+            (dispute.bond_amount as u128 * cfg.dispute_slash_if_invalid as u128 / 100) as u64
+        } else {
+            0
+        };
+        let bond_refund = dispute.bond_amount.saturating_sub(bond_slash);
 
-        // Slash oracle bond - slashed tokens remain in vault as protocol reserves
-        // Oracle can only withdraw remaining bond_amount after this
-        let slash_amount = (oracle_bond.bond_amount * ORACLE_SLASH_PERCENT) / 100;
-        oracle_bond.bond_amount = oracle_bond.bond_amount.saturating_sub(slash_amount);
-        oracle_bond.slashed = true;
-        oracle_bond.active_resolution = None;  // SELF-REVIEW FIX: Clear lock on slash
+        if bond_refund > 0 {
+            let state_bump = ctx.accounts.state.bump;
+            let seeds = &[b"state".as_ref(), &[state_bump]];
+            let signer_seeds = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.disputer_token_account.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds
+                ),
+                bond_refund
+            )?;
+        }
 
-        // Add slashed amount to insurance fund (protocol reserves)
+        // Slashed bonds remain in vault as protocol reserves
         ctx.accounts.state.insurance_fund = ctx.accounts.state.insurance_fund
-            .saturating_add(slash_amount);
+            .saturating_add(oracle_slash)
+            .saturating_add(bond_slash);
+
+        match result {
+            DISPUTE_CHOICE_OVERTURN => {
+                let outcome = market.outcome.ok_or(IdlError::MarketNotResolved)?;
+                market.outcome = Some(!outcome);
+                market.status = MARKET_STATUS_RESOLVED;
+            }
+            DISPUTE_CHOICE_CANCEL => {
+                market.resolved = false;
+                market.resolved_at = None;
+                market.outcome = None;
+                market.winning_outcome = None;
+                market.actual_value = None;
+                market.status = MARKET_STATUS_CANCELLED;
+            }
+            _ => market.status = MARKET_STATUS_RESOLVED,
+        }
 
-        msg!("Oracle disputed and slashed: {}. Market CANCELLED for refunds.", slash_amount);
+        dispute.settled = true;
+        dispute.result = result;
+
+        emit!(DisputeSettled {
+            version: EVENT_VERSION,
+            market: market_key,
+            dispute: dispute.key(),
+            result,
+            votes_uphold: dispute.votes_uphold,
+            votes_overturn: dispute.votes_overturn,
+            votes_cancel: dispute.votes_cancel,
+            disputer_slashed: bond_slash,
+            oracles_slashed: oracle_slash,
+        });
+        msg!("Dispute settled: result {} (disputer slashed: {}, oracles slashed: {})",
+            result, bond_slash, oracle_slash);
         Ok(())
     }
 
     /// AUDIT FIX: Allow oracle to withdraw bond after successful undisputed resolution
     pub fn withdraw_oracle_bond(ctx: Context<WithdrawOracleBond>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let oracle_bond = &mut ctx.accounts.oracle_bond;  // SELF-REVIEW FIX: Make mutable
         let res_commit = &ctx.accounts.resolution_commitment;
+        let market = &ctx.accounts.market;
         let clock = Clock::get()?;
 
-        // Must be after dispute window
-        require!(
-            clock.unix_timestamp > res_commit.commit_time + ORACLE_DISPUTE_WINDOW,
-            IdlError::DisputeWindowOpen
-        );
+        // Market (the locked one, if any) must be settled and past its dispute window
+        require_oracle_bond_unlocked(cfg, market, clock.unix_timestamp)?;
 
         // Must not have been disputed
         require!(!res_commit.disputed, IdlError::ResolutionDisputed);
@@ -1365,10 +2819,36 @@ pub mod idl_protocol {
         oracle_bond.bond_amount = 0;
         oracle_bond.active_resolution = None;  // SELF-REVIEW FIX: Clear lock
 
+        emit!(OracleBondWithdrawn {
+            version: EVENT_VERSION,
+            oracle: oracle_bond.oracle,
+            market: market.key(),
+            amount: bond_amount,
+        });
         msg!("Oracle bond withdrawn: {}", bond_amount);
         Ok(())
     }
 
+    /// Release an oracle's resolution lock once its market's dispute window has closed
+    /// and no dispute is open (permissionless), so the oracle can commit on another market.
+    pub fn release_oracle_bond(ctx: Context<ReleaseOracleBond>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let clock = Clock::get()?;
+
+        require_oracle_bond_unlocked(cfg, &ctx.accounts.market, clock.unix_timestamp)?;
+
+        let oracle_bond = &mut ctx.accounts.oracle_bond;
+        oracle_bond.active_resolution = None;
+
+        emit!(OracleBondReleased {
+            version: EVENT_VERSION,
+            oracle: oracle_bond.oracle,
+            market: ctx.accounts.market.key(),
+        });
+        msg!("Oracle bond released from market {}", ctx.accounts.market.key());
+        Ok(())
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════════
     // PUMP MECHANICS - New tokenomics instructions
     // ═══════════════════════════════════════════════════════════════════════════════
@@ -1392,6 +2872,11 @@ pub mod idl_protocol {
         referral.registered_at = clock.unix_timestamp;
         referral.bump = ctx.bumps.referral_account;
 
+        emit!(ReferralRegistered {
+            version: EVENT_VERSION,
+            user: referral.user,
+            referrer: referral.referrer,
+        });
         msg!("Referral registered: {} referred by {}", referral.user, referral.referrer);
         Ok(())
     }
@@ -1419,39 +2904,104 @@ pub mod idl_protocol {
     /// Enable/disable auto-compound for a user
     pub fn set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
         ctx.accounts.predictor_stats.auto_compound = enabled;
+        emit!(AutoCompoundSet {
+            version: EVENT_VERSION,
+            user: ctx.accounts.user.key(),
+            enabled,
+        });
         msg!("Auto-compound set to {} for {}", enabled, ctx.accounts.user.key());
         Ok(())
     }
 
+    /// Add to the vault budget for accuracy and streak bonuses (admin only)
+    pub fn fund_predictor_bonuses(ctx: Context<FundPredictorBonuses>, amount: u64) -> Result<()> {
+        require!(amount > 0, IdlError::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount
+        )?;
+
+        let state = &mut ctx.accounts.state;
+        state.predictor_bonus_pool = state.predictor_bonus_pool
+            .checked_add(amount)
+            .ok_or(IdlError::MathOverflow)?;
+
+        emit!(PredictorBonusesFunded {
+            version: EVENT_VERSION,
+            amount,
+            predictor_bonus_pool: state.predictor_bonus_pool,
+        });
+        msg!("Predictor bonus pool funded with {}, now {}", amount, state.predictor_bonus_pool);
+        Ok(())
+    }
+
     /// Update VIP tier based on current stake
     pub fn update_vip_tier(ctx: Context<UpdateVipTier>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let staker = &ctx.accounts.staker_account;
         let stats = &mut ctx.accounts.predictor_stats;
 
-        let new_tier = PredictorStats::calculate_vip_tier(staker.staked_amount);
+        // Lootbox VIP upgrades stack on top of the stake-based tier
+        let new_tier = PredictorStats::calculate_vip_tier(cfg, staker.staked_amount)
+            .saturating_add(stats.vip_bonus_tiers)
+            .min(4);
         stats.vip_tier = new_tier;
 
+        emit!(VipTierUpdated {
+            version: EVENT_VERSION,
+            user: stats.owner,
+            vip_tier: new_tier,
+        });
         msg!("VIP tier updated to {} for {}", new_tier, stats.owner);
         Ok(())
     }
 
     /// Create a new season (admin only)
+    /// `prize_shares_bps[i]` is the share of the prize pool paid to rank i+1
     pub fn create_season(
         ctx: Context<CreateSeason>,
         season_number: u64,
         prize_pool: u64,
+        prize_shares_bps: Vec<u16>,
     ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(prize_shares_bps.len() <= LEADERBOARD_MAX_RANKS, IdlError::InvalidInput);
+        let total_share_bps: u64 = prize_shares_bps.iter().map(|s| *s as u64).sum();
+        require!(total_share_bps <= 10000, IdlError::InvalidPrizeShares);
+
         let clock = Clock::get()?;
-        let season = &mut ctx.accounts.season;
+        let state = &mut ctx.accounts.state;
+        // One season runs at a time so every settlement belongs to at most one season
+        require!(clock.unix_timestamp > state.season_end, IdlError::SeasonNotEnded);
 
+        let season = &mut ctx.accounts.season;
         season.season_number = season_number;
         season.start_time = clock.unix_timestamp;
-        season.end_time = clock.unix_timestamp + SEASON_DURATION;
+        season.end_time = clock.unix_timestamp + cfg.season_duration;
         season.total_rewards = 0;
-        season.distributed_rewards = 0;
+        season.bonus_distributed = 0;
+        season.prize_distributed = 0;
         season.active = true;
         season.prize_pool = prize_pool;
+        season.prize_shares_bps = [0u16; LEADERBOARD_MAX_RANKS];
+        season.prize_shares_bps[..prize_shares_bps.len()].copy_from_slice(&prize_shares_bps);
+        season.ranked_count = 0;
+        season.finalized = false;
         season.bump = ctx.bumps.season;
+        season.entry_count = 0;
+        season.leaders = [Pubkey::default(); LEADERBOARD_MAX_RANKS];
+        season.leader_winnings = [0; LEADERBOARD_MAX_RANKS];
+        season.leader_accuracy = [0; LEADERBOARD_MAX_RANKS];
+        season.leader_count = 0;
+
+        state.season_start = season.start_time;
+        state.season_end = season.end_time;
 
         // Transfer prize pool to vault if provided
         if prize_pool > 0 {
@@ -1466,44 +3016,188 @@ pub mod idl_protocol {
             )?;
         }
 
+        emit!(SeasonCreated {
+            version: EVENT_VERSION,
+            season: ctx.accounts.season.key(),
+            season_number,
+            prize_pool,
+            end_time: ctx.accounts.season.end_time,
+        });
         msg!("Season {} created with {} prize pool", season_number, prize_pool);
         Ok(())
     }
 
-    /// End the current season (admin only)
-    pub fn end_season(ctx: Context<EndSeason>) -> Result<()> {
+    /// Fund a season's bonus rewards (admin only)
+    /// Winnings claimed on markets resolved during the season draw their bonus from this budget.
+    pub fn fund_season(ctx: Context<FundSeason>, amount: u64) -> Result<()> {
+        require!(amount > 0, IdlError::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount
+        )?;
+
         let season = &mut ctx.accounts.season;
-        season.active = false;
+        season.total_rewards = season.total_rewards
+            .checked_add(amount)
+            .ok_or(IdlError::MathOverflow)?;
 
-        msg!("Season {} ended", season.season_number);
+        emit!(SeasonFunded {
+            version: EVENT_VERSION,
+            season: season.key(),
+            amount,
+            total_rewards: season.total_rewards,
+        });
+        msg!("Season {} funded with {} bonus rewards", season.season_number, amount);
         Ok(())
     }
 
-    /// Initialize creator stats for prediction mining
-    pub fn init_creator_stats(ctx: Context<InitCreatorStats>) -> Result<()> {
-        let stats = &mut ctx.accounts.creator_stats;
+    /// Lock the season's leaderboard once the season is over (permissionless)
+    /// The top LEADERBOARD_MAX_RANKS are kept sorted as entries are submitted, so nothing is left to rank.
+    pub fn end_season(ctx: Context<EndSeason>) -> Result<()> {
+        let season = &mut ctx.accounts.season;
+        let clock = Clock::get()?;
 
-        stats.creator = ctx.accounts.creator.key();
-        stats.markets_created = 0;
-        stats.total_volume = 0;
-        stats.total_fees_earned = 0;
-        stats.pending_fees = 0;
-        stats.last_claim = 0;
-        stats.bump = ctx.bumps.creator_stats;
+        season.finalize(clock.unix_timestamp)?;
 
-        msg!("Creator stats initialized for {}", stats.creator);
+        emit!(SeasonEnded {
+            version: EVENT_VERSION,
+            season: season.key(),
+            entry_count: season.entry_count,
+            ranked_count: season.ranked_count,
+        });
+        msg!("Season {} ended, {} predictors ranked", season.season_number, season.ranked_count);
         Ok(())
     }
 
-    /// Claim creator fees (prediction mining)
-    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
-        let stats = &mut ctx.accounts.creator_stats;
-        let pending = stats.pending_fees;
+    /// Snapshot a user's season accuracy and winnings into their leaderboard entry
+    /// Only predictions on markets resolved during the season count (see record_settlement),
+    /// whenever the user first submits. Re-submitting moves the user to their new place.
+    pub fn submit_leaderboard_entry(ctx: Context<SubmitLeaderboardEntry>) -> Result<()> {
+        let season = &mut ctx.accounts.season;
+        let stats = &ctx.accounts.predictor_stats;
+        let clock = Clock::get()?;
 
-        require!(pending > 0, IdlError::NoRewardsToClaim);
+        require!(season.active, IdlError::SeasonNotActive);
+        require!(clock.unix_timestamp <= season.end_time, IdlError::SeasonNotActive);
+
+        let entry = &mut ctx.accounts.leaderboard_entry;
+        if entry.user == Pubkey::default() {
+            entry.season = season.key();
+            entry.user = ctx.accounts.user.key();
+            entry.rank = 0;
+            entry.prize_claimed = false;
+            entry.bump = ctx.bumps.leaderboard_entry;
+            season.entry_count += 1;
+        }
 
-        let state_bump = ctx.accounts.state.bump;
-        let seeds = &[b"state".as_ref(), &[state_bump]];
+        let (season_predictions, season_correct, season_winnings) = if stats.season_start == season.start_time {
+            (stats.season_predictions, stats.season_correct, stats.season_winnings)
+        } else {
+            (0, 0, 0)
+        };
+        entry.accuracy = (season_correct * 100)
+            .checked_div(season_predictions)
+            .unwrap_or(0);
+        entry.winnings = season_winnings;
+        entry.updated_at = clock.unix_timestamp;
+        season.record_entry(entry.user, entry.winnings, entry.accuracy);
+
+        msg!("Leaderboard entry updated: {}% accuracy, {} winnings", entry.accuracy, entry.winnings);
+        Ok(())
+    }
+
+    /// Claim a ranked predictor's share of the season prize pool
+    pub fn claim_leaderboard_prize(ctx: Context<ClaimLeaderboardPrize>) -> Result<()> {
+        let season = &ctx.accounts.season;
+        let entry = &ctx.accounts.leaderboard_entry;
+
+        require!(season.finalized, IdlError::LeaderboardNotFinalized);
+        require!(!entry.prize_claimed, IdlError::AlreadyClaimed);
+        let rank = season.rank_of(&entry.user).ok_or(IdlError::NotPrizeWinner)?;
+
+        let share_bps = season.prize_shares_bps[(rank - 1) as usize] as u64;
+        let prize = (season.prize_pool as u128 * share_bps as u128 / 10000) as u64;
+        require!(prize > 0, IdlError::NotPrizeWinner);
+
+        // Prizes are capped by the prize pool alone; season bonuses draw on total_rewards
+        let new_distributed = season.prize_distributed
+            .checked_add(prize)
+            .ok_or(IdlError::MathOverflow)?;
+        require!(new_distributed <= season.prize_pool, IdlError::InsufficientPoolBalance);
+
+        // Prize money must not come out of staked tokens or staker rewards
+        let min_vault_balance = ctx.accounts.state.total_staked
+            .checked_add(ctx.accounts.state.reward_pool)
+            .ok_or(IdlError::MathOverflow)?;
+        let vault_after = ctx.accounts.vault.amount.saturating_sub(prize);
+        require!(vault_after >= min_vault_balance, IdlError::InsufficientPoolBalance);
+
+        let state_bump = ctx.accounts.state.bump;
+        let seeds = &[b"state".as_ref(), &[state_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds
+            ),
+            prize
+        )?;
+
+        ctx.accounts.leaderboard_entry.prize_claimed = true;
+        ctx.accounts.leaderboard_entry.rank = rank;
+        ctx.accounts.season.prize_distributed = new_distributed;
+
+        emit!(LeaderboardPrizeClaimed {
+            version: EVENT_VERSION,
+            season: ctx.accounts.season.key(),
+            user: ctx.accounts.leaderboard_entry.user,
+            rank: ctx.accounts.leaderboard_entry.rank,
+            prize,
+        });
+        msg!("Rank {} claimed {} from season {} prize pool",
+            ctx.accounts.leaderboard_entry.rank, prize, ctx.accounts.season.season_number);
+        Ok(())
+    }
+
+    /// Initialize creator stats for prediction mining
+    pub fn init_creator_stats(ctx: Context<InitCreatorStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.creator_stats;
+
+        stats.creator = ctx.accounts.creator.key();
+        stats.markets_created = 0;
+        stats.total_volume = 0;
+        stats.total_fees_earned = 0;
+        stats.pending_fees = 0;
+        stats.last_claim = 0;
+        stats.bump = ctx.bumps.creator_stats;
+        stats.last_market_created = 0;
+
+        msg!("Creator stats initialized for {}", stats.creator);
+        Ok(())
+    }
+
+    /// Claim creator fees (prediction mining)
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
+        let stats = &mut ctx.accounts.creator_stats;
+        let pending = stats.pending_fees;
+
+        require!(pending > 0, IdlError::NoRewardsToClaim);
+
+        let state_bump = ctx.accounts.state.bump;
+        let seeds = &[b"state".as_ref(), &[state_bump]];
         let signer_seeds = &[&seeds[..]];
 
         let cpi_accounts = Transfer {
@@ -1524,6 +3218,11 @@ pub mod idl_protocol {
         stats.pending_fees = 0;
         stats.last_claim = Clock::get()?.unix_timestamp;
 
+        emit!(CreatorFeesClaimed {
+            version: EVENT_VERSION,
+            creator: stats.creator,
+            amount: pending,
+        });
         msg!("Claimed {} creator fees", pending);
         Ok(())
     }
@@ -1533,14 +3232,15 @@ pub mod idl_protocol {
         ctx: Context<PlaceConvictionBet>,
         lock_duration: i64,
     ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(
-            lock_duration >= CONVICTION_LOCK_MIN && lock_duration <= CONVICTION_LOCK_MAX,
+            (cfg.conviction_lock_min..=cfg.conviction_lock_max).contains(&lock_duration),
             IdlError::InvalidLockDuration
         );
 
         let clock = Clock::get()?;
         let days_locked = lock_duration / 86400;
-        let bonus_bps = (days_locked as u64) * CONVICTION_BONUS_PER_DAY;
+        let bonus_bps = (days_locked as u64) * cfg.conviction_bonus_per_day;
 
         let conviction = &mut ctx.accounts.conviction_bet;
         conviction.owner = ctx.accounts.user.key();
@@ -1552,6 +3252,14 @@ pub mod idl_protocol {
         conviction.claimed = false;
         conviction.bump = ctx.bumps.conviction_bet;
 
+        emit!(ConvictionBetPlaced {
+            version: EVENT_VERSION,
+            market: conviction.market,
+            bet: conviction.bet,
+            user: conviction.owner,
+            lock_end: conviction.lock_end,
+            bonus_bps,
+        });
         msg!(
             "Conviction bet placed: {} days lock, {}bps bonus",
             days_locked,
@@ -1591,6 +3299,11 @@ pub mod idl_protocol {
             amount
         )?;
 
+        emit!(ReferralFeesClaimed {
+            version: EVENT_VERSION,
+            referrer: referral.referrer,
+            amount,
+        });
         msg!("Claimed {} referral fees", amount);
         Ok(())
     }
@@ -1605,8 +3318,10 @@ pub mod idl_protocol {
         stake_amount: u64,
         bet_yes: bool,
     ) -> Result<()> {
-        require!(stake_amount >= BATTLE_MIN_STAKE, IdlError::BetTooSmall);
+        let cfg = &ctx.accounts.config.params;
+        require!(stake_amount >= cfg.battle_min_stake, IdlError::BetTooSmall);
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(ctx.accounts.market.is_two_sided(), IdlError::WrongMarketType);
 
         let clock = Clock::get()?;
         let battle = &mut ctx.accounts.battle;
@@ -1633,12 +3348,21 @@ pub mod idl_protocol {
         battle.accepted_at = 0;
         battle.bump = ctx.bumps.battle;
 
+        emit!(BattleCreated {
+            version: EVENT_VERSION,
+            battle: battle.key(),
+            market: battle.market,
+            challenger: battle.challenger,
+            stake_amount,
+            challenger_bet_yes: bet_yes,
+        });
         msg!("Battle created: {} IDL stake on {}", stake_amount, if bet_yes { "YES" } else { "NO" });
         Ok(())
     }
 
     /// Accept a battle challenge (opponent takes opposite side)
     pub fn accept_battle(ctx: Context<AcceptBattle>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
         let clock = Clock::get()?;
@@ -1646,7 +3370,7 @@ pub mod idl_protocol {
 
         require!(battle.status == 0, IdlError::InvalidInput); // Must be pending
         require!(
-            clock.unix_timestamp < battle.created_at + BATTLE_TIMEOUT,
+            clock.unix_timestamp < battle.created_at + cfg.battle_timeout,
             IdlError::BettingClosed
         );
 
@@ -1665,29 +3389,46 @@ pub mod idl_protocol {
         battle.status = 1; // Active
         battle.accepted_at = clock.unix_timestamp;
 
+        emit!(BattleAccepted {
+            version: EVENT_VERSION,
+            battle: battle.key(),
+            opponent: battle.opponent,
+        });
         msg!("Battle accepted by {}", ctx.accounts.opponent.key());
         Ok(())
     }
 
     /// Resolve a battle after market resolution
     pub fn resolve_battle(ctx: Context<ResolveBattle>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let battle = &mut ctx.accounts.battle;
         let market = &ctx.accounts.market;
 
         require!(battle.status == 1, IdlError::InvalidInput); // Must be active
         require!(market.resolved, IdlError::MarketNotResolved);
+        require!(market.status == MARKET_STATUS_RESOLVED, IdlError::MarketNotResolved);
 
-        // Determine winner
-        let actual = market.actual_value.unwrap_or(0);
-        let yes_won = actual >= market.target_value;
+        // Determine winner (outcome also covers hedge markets, which don't compare against target)
+        let yes_won = market.outcome.ok_or(IdlError::MarketNotResolved)?;
         let challenger_won = battle.challenger_bet_yes == yes_won;
 
         let winner = if challenger_won { battle.challenger } else { battle.opponent };
-        let loser = if challenger_won { battle.opponent } else { battle.challenger };
+        require!(
+            ctx.accounts.winner_token_account.owner == winner,
+            IdlError::Unauthorized
+        );
 
-        // Calculate payout (total stake minus platform fee)
+        // Calculate payout (total stake minus platform fee, discounted for the winner)
+        let fee_bps = discounted_fee_bps(
+            cfg,
+            cfg.battle_platform_fee_bps,
+            &winner,
+            ctx.accounts.winner_stats.as_ref().map(|s| &***s),
+            ctx.accounts.winner_lootbox.as_ref().map(|l| &***l),
+            Clock::get()?.unix_timestamp
+        );
         let total_stake = battle.stake_amount.saturating_mul(2);
-        let platform_fee = (total_stake * BATTLE_PLATFORM_FEE_BPS) / 10000;
+        let platform_fee = (total_stake as u128 * fee_bps as u128 / 10000) as u64;
         let payout = total_stake.saturating_sub(platform_fee);
 
         // Transfer winnings
@@ -1712,6 +3453,14 @@ pub mod idl_protocol {
         battle.winner = winner;
         battle.status = 2; // Resolved
 
+        emit!(BattleResolved {
+            version: EVENT_VERSION,
+            battle: battle.key(),
+            market: battle.market,
+            winner,
+            payout,
+            platform_fee,
+        });
         msg!("Battle resolved! Winner: {} gets {} IDL", winner, payout);
         Ok(())
     }
@@ -1744,12 +3493,18 @@ pub mod idl_protocol {
 
         battle.status = 3; // Cancelled
 
+        emit!(BattleCancelled {
+            version: EVENT_VERSION,
+            battle: battle.key(),
+            refunded: battle.stake_amount,
+        });
         msg!("Battle cancelled, {} IDL refunded", battle.stake_amount);
         Ok(())
     }
 
     /// Create a guild
     pub fn create_guild(ctx: Context<CreateGuild>, name: String) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(name.len() <= 32, IdlError::InvalidInput);
         require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
@@ -1761,7 +3516,7 @@ pub mod idl_protocol {
         };
         token::transfer(
             CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            GUILD_CREATION_FEE
+            cfg.guild_creation_fee
         )?;
 
         let clock = Clock::get()?;
@@ -1776,18 +3531,31 @@ pub mod idl_protocol {
         guild.created_at = clock.unix_timestamp;
         guild.active = true;
         guild.bump = ctx.bumps.guild;
-
+        guild.amount_at_risk = 0;
+
+        emit!(GuildCreated {
+            version: EVENT_VERSION,
+            guild: guild.key(),
+            leader: guild.leader,
+            name: guild.name.clone(),
+        });
         msg!("Guild '{}' created by {}", name, ctx.accounts.leader.key());
         Ok(())
     }
 
     /// Join a guild
+    /// Contributions buy pool shares at the treasury's value. Joining is closed while the
+    /// guild has bets open, since their outcome would be shared with late joiners.
     pub fn join_guild(ctx: Context<JoinGuild>, contribution: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         require!(contribution > 0, IdlError::InvalidAmount);
 
         let guild = &mut ctx.accounts.guild;
-        require!(guild.active, IdlError::ProtocolPaused);
-        require!(guild.member_count < GUILD_MAX_MEMBERS, IdlError::InvalidInput);
+        require!(guild.active, IdlError::GuildInactive);
+        require!(guild.member_count < cfg.guild_max_members, IdlError::InvalidInput);
+
+        let shares = guild.shares_for(contribution, ctx.accounts.guild_treasury.amount)?;
+        require!(shares > 0, IdlError::InvalidAmount);
 
         // Transfer contribution to guild treasury
         let cpi_accounts = Transfer {
@@ -1805,145 +3573,139 @@ pub mod idl_protocol {
 
         membership.guild = guild.key();
         membership.member = ctx.accounts.member.key();
-        membership.contribution = contribution;
+        membership.contribution = shares;
         membership.winnings_claimed = 0;
         membership.joined_at = clock.unix_timestamp;
         membership.bump = ctx.bumps.guild_member;
 
-        guild.member_count = guild.member_count.saturating_add(1);
-        guild.total_pooled = guild.total_pooled.saturating_add(contribution);
-
-        msg!("Joined guild with {} IDL contribution", contribution);
+        // Leader is already counted as the first member
+        if ctx.accounts.member.key() != guild.leader {
+            guild.member_count = guild.member_count.saturating_add(1);
+        }
+        guild.total_pooled = guild.total_pooled.saturating_add(shares);
+
+        emit!(GuildJoined {
+            version: EVENT_VERSION,
+            guild: guild.key(),
+            member: ctx.accounts.member.key(),
+            contribution,
+            shares,
+            total_shares: guild.total_pooled,
+        });
+        msg!("Joined guild with {} IDL contribution ({} shares)", contribution, shares);
         Ok(())
     }
 
-    /// Buy a loot box
-    pub fn buy_lootbox(ctx: Context<BuyLootbox>, tier: u8) -> Result<()> {
-        require!(tier <= 2, IdlError::InvalidInput); // 0=common, 1=rare, 2=legendary
-
-        let price = match tier {
-            0 => LOOTBOX_COMMON_PRICE,
-            1 => LOOTBOX_RARE_PRICE,
-            2 => LOOTBOX_LEGENDARY_PRICE,
-            _ => return Err(IdlError::InvalidInput.into()),
-        };
-
-        // Pay for lootbox
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.buyer_token_account.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-            authority: ctx.accounts.buyer.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            price
-        )?;
+    /// Guild leader commits a bet from the guild treasury (step 1 of commit-reveal)
+    pub fn guild_commit_bet(ctx: Context<GuildCommitBet>, commitment: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
-        // Generate pseudo-random reward using slot hash
+        let guild = &ctx.accounts.guild;
+        let market = &ctx.accounts.market;
         let clock = Clock::get()?;
-        let slot = clock.slot;
-        let random = (slot % 100) as u8;
-
-        // Determine reward based on tier and random
-        let (reward_type, reward_value, expires_in) = match tier {
-            0 => { // Common: mostly small discounts
-                if random < 70 {
-                    (0u8, 100u64, 604800i64) // 1% fee discount for 7 days
-                } else if random < 90 {
-                    (1u8, 200u64, 604800i64) // 2% stake boost for 7 days
-                } else {
-                    (3u8, LOOTBOX_COMMON_PRICE, 0i64) // 1 IDL back
-                }
-            },
-            1 => { // Rare: better rewards
-                if random < 50 {
-                    (0u8, 300u64, 2592000i64) // 3% fee discount for 30 days
-                } else if random < 80 {
-                    (1u8, 500u64, 2592000i64) // 5% stake boost for 30 days
-                } else {
-                    (3u8, LOOTBOX_RARE_PRICE * 2, 0i64) // 20 IDL back
-                }
-            },
-            _ => { // Legendary: big rewards
-                if random < 30 {
-                    (0u8, 1000u64, 7776000i64) // 10% fee discount for 90 days
-                } else if random < 60 {
-                    (1u8, 1000u64, 7776000i64) // 10% stake boost for 90 days
-                } else if random < 90 {
-                    (2u8, 1u64, 0i64) // VIP tier upgrade
-                } else {
-                    (3u8, LOOTBOX_LEGENDARY_PRICE * 5, 0i64) // 500 IDL jackpot!
-                }
-            },
-        };
 
-        let reward = &mut ctx.accounts.lootbox_reward;
-        reward.owner = ctx.accounts.buyer.key();
-        reward.reward_type = reward_type;
-        reward.reward_value = reward_value;
-        reward.expires_at = if expires_in > 0 { clock.unix_timestamp + expires_in } else { 0 };
-        reward.used = false;
-        reward.tier = tier;
-        reward.bump = ctx.bumps.lootbox_reward;
+        require!(guild.active, IdlError::GuildInactive);
+        require!(guild.member_count >= cfg.guild_min_members, IdlError::GuildTooSmall);
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+        require!(
+            clock.unix_timestamp < market.resolution_timestamp - cfg.betting_close_window,
+            IdlError::BettingClosed
+        );
 
-        msg!("Lootbox opened! Reward type: {}, value: {}", reward_type, reward_value);
+        // Leader posts the bond and gets it back on reveal
+        post_commitment_bond(
+            cfg,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.leader_token_account.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.leader.to_account_info(),
+            ctx.accounts.leader_token_account.amount
+        )?;
+
+        let bet_commitment = &mut ctx.accounts.bet_commitment;
+        bet_commitment.owner = guild.key();
+        bet_commitment.market = market.key();
+        bet_commitment.commitment = commitment;
+        bet_commitment.commit_time = clock.unix_timestamp;
+        bet_commitment.revealed = false;
+        bet_commitment.bump = ctx.bumps.bet_commitment;
+        bet_commitment.bond_amount = cfg.commitment_bond_amount;
+        bet_commitment.bond_payer = ctx.accounts.leader.key();
+
+        emit!(BetCommitted {
+            version: EVENT_VERSION,
+            market: bet_commitment.market,
+            bettor: bet_commitment.owner,
+            bond_amount: bet_commitment.bond_amount,
+            reveal_deadline: bet_commitment.commit_time + cfg.bet_reveal_window,
+        });
+        msg!("Guild bet committed, must reveal within {} seconds", cfg.bet_reveal_window);
         Ok(())
     }
 
-    /// Create a limit order
-    pub fn create_limit_order(
-        ctx: Context<CreateLimitOrder>,
+    /// Guild leader reveals a committed guild bet, moving funds from the guild treasury
+    pub fn guild_reveal_bet(
+        ctx: Context<GuildRevealBet>,
         amount: u64,
         bet_yes: bool,
-        target_odds_bps: u64,
+        nonce: u64,
+        salt: [u8; 32]
     ) -> Result<()> {
-        require!(amount >= MIN_BET_AMOUNT, IdlError::BetTooSmall);
-        require!(target_odds_bps > 0 && target_odds_bps < 10000, IdlError::InvalidInput);
-
-        // Lock funds for the order
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            amount
-        )?;
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(amount >= cfg.min_bet_amount, IdlError::BetTooSmall);
+        require!(amount <= cfg.max_bet_amount, IdlError::BetTooLarge);
 
+        let commitment = &mut ctx.accounts.bet_commitment;
+        let market = &mut ctx.accounts.market;
         let clock = Clock::get()?;
-        let order = &mut ctx.accounts.limit_order;
-
-        order.owner = ctx.accounts.user.key();
-        order.market = ctx.accounts.market.key();
-        order.amount = amount;
-        order.bet_yes = bet_yes;
-        order.target_odds_bps = target_odds_bps;
-        order.expires_at = clock.unix_timestamp + LIMIT_ORDER_EXPIRY;
-        order.filled = false;
-        order.created_at = clock.unix_timestamp;
-        order.bump = ctx.bumps.limit_order;
 
-        msg!("Limit order created: {} IDL at {}bps odds", amount, target_odds_bps);
-        Ok(())
-    }
+        require!(ctx.accounts.guild.active, IdlError::GuildInactive);
+        require!(!commitment.revealed, IdlError::AlreadyRevealed);
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.is_two_sided(), IdlError::WrongMarketType);
+        require!(
+            clock.unix_timestamp <= commitment.commit_time + cfg.commitment_expiry,
+            IdlError::CommitmentExpired
+        );
+        require!(
+            clock.unix_timestamp >= commitment.commit_time + cfg.bet_commit_window,
+            IdlError::RevealTooEarly
+        );
+        require!(
+            clock.unix_timestamp <= commitment.commit_time + cfg.bet_reveal_window,
+            IdlError::RevealTooLate
+        );
+        require!(
+            bet_commitment_hash(amount, bet_yes as u8, nonce, &salt) == commitment.commitment,
+            IdlError::InvalidCommitment
+        );
 
-    /// Cancel a limit order
-    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
-        let order = &mut ctx.accounts.limit_order;
+        commitment.revealed = true;
 
-        require!(!order.filled, IdlError::InvalidInput);
-        require!(order.owner == ctx.accounts.user.key(), IdlError::Unauthorized);
+        // Commitment bond comes back on reveal (the commitment account is closed)
+        let bond_amount = commitment.bond_amount;
+        transfer_from_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.leader_token_account.to_account_info(),
+            &ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.bump,
+            bond_amount
+        )?;
 
-        // Refund locked funds
-        let state_bump = ctx.accounts.state.bump;
-        let seeds = &[b"state".as_ref(), &[state_bump]];
-        let signer_seeds = &[&seeds[..]];
+        let leader = ctx.accounts.guild.leader;
+        let guild_seeds = &[b"guild".as_ref(), leader.as_ref(), &[ctx.accounts.guild.bump]];
+        let signer_seeds = &[&guild_seeds[..]];
 
+        // PREDICTION MINING: Creator's volume fee is taken from the stake
+        let creator_fee = accrue_creator_volume_fee(cfg, &mut ctx.accounts.creator_stats, amount);
         let cpi_accounts = Transfer {
-            from: ctx.accounts.vault.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.state.to_account_info(),
+            from: ctx.accounts.guild_treasury.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.guild.to_account_info(),
         };
         token::transfer(
             CpiContext::new_with_signer(
@@ -1951,906 +3713,2065 @@ pub mod idl_protocol {
                 cpi_accounts,
                 signer_seeds
             ),
-            order.amount
+            creator_fee
         )?;
+        let amount = amount - creator_fee;
 
-        order.filled = true; // Mark as "used" to prevent double cancel
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.guild_treasury.to_account_info(),
+            to: ctx.accounts.market_pool.to_account_info(),
+            authority: ctx.accounts.guild.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds
+            ),
+            amount
+        )?;
 
-        msg!("Limit order cancelled, {} IDL refunded", order.amount);
-        Ok(())
-    }
+        // Guild bets carry no staker bonus
+        market.add_pending_reveal(cfg, bet_yes as u8, amount, amount, clock.unix_timestamp);
 
-    /// Set a stop loss on a bet
-    pub fn set_stop_loss(
-        ctx: Context<SetStopLoss>,
-        threshold_bps: u64,
-    ) -> Result<()> {
-        require!(threshold_bps >= STOP_LOSS_MIN_THRESHOLD, IdlError::InvalidInput);
-        require!(threshold_bps <= 9000, IdlError::InvalidInput); // Max 90% loss
+        let odds = &mut ctx.accounts.dynamic_odds;
+        odds.init_for(cfg, market, ctx.bumps.dynamic_odds, clock.unix_timestamp);
+        odds.record_volume(cfg, market, amount, clock.unix_timestamp);
 
-        let clock = Clock::get()?;
-        let stop_loss = &mut ctx.accounts.stop_loss;
+        let guild_key = ctx.accounts.guild.key();
+        let bet = &mut ctx.accounts.bet;
+        bet.owner = guild_key;
+        bet.market = market.key();
+        bet.amount = amount;
+        bet.effective_amount = amount;
+        bet.bet_yes = bet_yes;
+        bet.outcome_index = bet_yes as u8;
+        bet.timestamp = clock.unix_timestamp;
+        bet.claimed = false;
+        bet.nonce = nonce;
+        bet.bump = ctx.bumps.bet;
 
-        stop_loss.owner = ctx.accounts.user.key();
-        stop_loss.bet = ctx.accounts.bet.key();
-        stop_loss.market = ctx.accounts.bet.market;
-        stop_loss.threshold_bps = threshold_bps;
-        stop_loss.triggered = false;
-        stop_loss.active = true;
-        stop_loss.created_at = clock.unix_timestamp;
-        stop_loss.bump = ctx.bumps.stop_loss;
+        let guild = &mut ctx.accounts.guild;
+        guild.amount_at_risk = guild.amount_at_risk
+            .checked_add(amount)
+            .ok_or(IdlError::MathOverflow)?;
 
-        msg!("Stop loss set at {}bps threshold", threshold_bps);
+        emit!(BetRevealed {
+            version: EVENT_VERSION,
+            market: bet.market,
+            bettor: bet.owner,
+            outcome_index: bet.outcome_index,
+            amount,
+            effective_amount: bet.effective_amount,
+            creator_fee,
+        });
+        msg!("Guild bet revealed: {} on {}", amount, if bet_yes { "YES" } else { "NO" });
         Ok(())
     }
 
-    /// Partial cashout - exit early at current odds
-    pub fn partial_cashout(ctx: Context<PartialCashout>, cashout_amount: u64) -> Result<()> {
-        let bet = &ctx.accounts.bet;
+    /// Settle a guild bet back into the guild treasury (permissionless)
+    /// Winnings pay the usual fees, then the leader takes GUILD_LEADER_SHARE_BPS of the profit.
+    /// Bets on cancelled markets are refunded in full.
+    pub fn claim_guild_winnings(ctx: Context<ClaimGuildWinnings>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let state = &mut ctx.accounts.state;
+        let market = &ctx.accounts.market;
+        let bet = &mut ctx.accounts.bet;
         let clock = Clock::get()?;
 
-        require!(
-            clock.unix_timestamp >= bet.timestamp + CASHOUT_MIN_TIME,
-            IdlError::ClaimTooEarly
-        );
-        require!(cashout_amount <= bet.amount, IdlError::InvalidAmount);
         require!(!bet.claimed, IdlError::AlreadyClaimed);
 
-        // Calculate cashout value based on current odds
-        let market = &ctx.accounts.market;
-        let total_pool = market.total_yes_amount.saturating_add(market.total_no_amount);
-        let bet_side_pool = if bet.bet_yes { market.total_yes_amount } else { market.total_no_amount };
-
-        // Current implied probability for this side
-        let current_odds_bps = if total_pool > 0 {
-            (bet_side_pool * 10000) / total_pool
+        let market_key = market.key();
+        let market_pool_bump = ctx.bumps.market_pool;
+        let market_seeds = &[
+            b"market_pool".as_ref(),
+            market_key.as_ref(),
+            &[market_pool_bump],
+        ];
+        let signer_seeds = &[&market_seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let market_pool = ctx.accounts.market_pool.to_account_info();
+        let guild_treasury = ctx.accounts.guild_treasury.to_account_info();
+
+        let (returned, leader_cut) = if market.status == MARKET_STATUS_CANCELLED {
+            bet.claimed = true;
+            transfer_from_market_pool(&token_program, &market_pool, &guild_treasury, signer_seeds, bet.amount)?;
+            (bet.amount, 0)
         } else {
-            5000 // 50% default
+            require!(market.status == MARKET_STATUS_RESOLVED, IdlError::MarketNotResolved);
+            let resolved_at = market.resolved_at.ok_or(IdlError::MarketNotResolved)?;
+            require!(
+                clock.unix_timestamp >= resolved_at + cfg.claim_delay_after_resolution,
+                IdlError::ClaimTooEarly
+            );
+            require!(
+                dispute_window_closed(cfg, resolved_at, clock.unix_timestamp),
+                IdlError::DisputeWindowOpen
+            );
+
+            bet.claimed = true;
+            let payout = market_payout(bet, market)?;
+
+            if payout == 0 {
+                (0, 0)
+            } else {
+                let gross_winnings = std::cmp::min(payout, ctx.accounts.market_pool.amount);
+                let fees = FeeSplit::new(cfg, (gross_winnings as u128 * market.fee_bps(cfg) as u128 / 10000) as u64);
+                let net_winnings = gross_winnings.saturating_sub(fees.fee);
+
+                let profit = net_winnings.saturating_sub(bet.amount);
+                let leader_cut = (profit as u128 * cfg.guild_leader_share_bps as u128 / 10000) as u64;
+
+                transfer_from_market_pool(
+                    &token_program,
+                    &market_pool,
+                    &ctx.accounts.leader_token_account.to_account_info(),
+                    signer_seeds,
+                    leader_cut
+                )?;
+                transfer_from_market_pool(
+                    &token_program,
+                    &market_pool,
+                    &guild_treasury,
+                    signer_seeds,
+                    net_winnings.saturating_sub(leader_cut)
+                )?;
+
+                let fee_accounts = FeeAccounts {
+                    market_pool: market_pool.clone(),
+                    creator: ctx.accounts.creator_token_account.to_account_info(),
+                    treasury: ctx.accounts.treasury_token_account.to_account_info(),
+                    vault: ctx.accounts.vault.to_account_info(),
+                    burn_vault: ctx.accounts.burn_vault.to_account_info(),
+                    token_program: token_program.clone(),
+                };
+                distribute_fees(&fees, &fee_accounts, signer_seeds, state)?;
+
+                (net_winnings.saturating_sub(leader_cut), leader_cut)
+            }
         };
 
-        // Cashout value = amount * (1 - fee) * (current_odds / 100)
-        let fee = (cashout_amount * CASHOUT_FEE_BPS) / 10000;
-        let after_fee = cashout_amount.saturating_sub(fee);
-        let payout = (after_fee * current_odds_bps) / 10000;
+        let guild = &mut ctx.accounts.guild;
+        guild.amount_at_risk = guild.amount_at_risk.saturating_sub(bet.amount);
+        guild.total_winnings = guild.total_winnings
+            .checked_add(returned.saturating_sub(bet.amount))
+            .ok_or(IdlError::MathOverflow)?;
 
-        // Transfer payout
-        let state_bump = ctx.accounts.state.bump;
-        let seeds = &[b"state".as_ref(), &[state_bump]];
-        let signer_seeds = &[&seeds[..]];
+        emit!(GuildBetSettled {
+            version: EVENT_VERSION,
+            guild: guild.key(),
+            market: market_key,
+            returned,
+            leader_cut,
+        });
+        msg!("Guild bet settled: {} returned to treasury, {} to leader", returned, leader_cut);
+        Ok(())
+    }
+
+    /// Withdraw part of a member's pro-rata share of the guild treasury
+    /// Open to members while the guild has bets open (see Guild::share_value).
+    pub fn withdraw_from_guild(ctx: Context<WithdrawFromGuild>, shares: u64) -> Result<()> {
+        require!(shares > 0, IdlError::InvalidAmount);
+        require!(shares <= ctx.accounts.guild_member.contribution, IdlError::InsufficientStake);
+
+        let payout = pay_guild_share(
+            &ctx.accounts.guild,
+            &ctx.accounts.guild_treasury,
+            ctx.accounts.member_token_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            shares
+        )?;
+
+        let membership = &mut ctx.accounts.guild_member;
+        membership.contribution = membership.contribution.saturating_sub(shares);
+        membership.winnings_claimed = membership.winnings_claimed.saturating_add(payout);
+        let guild = &mut ctx.accounts.guild;
+        guild.total_pooled = guild.total_pooled.saturating_sub(shares);
+
+        emit!(GuildWithdrawn {
+            version: EVENT_VERSION,
+            guild: guild.key(),
+            member: membership.member,
+            shares,
+            payout,
+            left: false,
+        });
+        msg!("Withdrew {} IDL for {} guild shares", payout, shares);
+        Ok(())
+    }
+
+    /// Leave a guild, withdrawing the member's full pro-rata share
+    pub fn leave_guild(ctx: Context<LeaveGuild>) -> Result<()> {
+        let shares = ctx.accounts.guild_member.contribution;
+
+        let payout = if shares > 0 {
+            pay_guild_share(
+                &ctx.accounts.guild,
+                &ctx.accounts.guild_treasury,
+                ctx.accounts.member_token_account.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                shares
+            )?
+        } else {
+            0
+        };
+
+        let guild = &mut ctx.accounts.guild;
+        guild.total_pooled = guild.total_pooled.saturating_sub(shares);
+        if ctx.accounts.member.key() != guild.leader {
+            guild.member_count = guild.member_count.saturating_sub(1);
+        }
+
+        emit!(GuildWithdrawn {
+            version: EVENT_VERSION,
+            guild: guild.key(),
+            member: ctx.accounts.member.key(),
+            shares,
+            payout,
+            left: true,
+        });
+        msg!("Left guild with {} IDL", payout);
+        Ok(())
+    }
+
+    /// Dissolve a guild (leader only) - stops new bets and joins so members can withdraw
+    pub fn dissolve_guild(ctx: Context<DissolveGuild>) -> Result<()> {
+        let guild = &mut ctx.accounts.guild;
+        require!(guild.active, IdlError::GuildInactive);
+        require!(guild.amount_at_risk == 0, IdlError::GuildBetsOutstanding);
+
+        guild.active = false;
+
+        emit!(GuildDissolved {
+            version: EVENT_VERSION,
+            guild: guild.key(),
+        });
+        msg!("Guild '{}' dissolved", guild.name);
+        Ok(())
+    }
+
+    /// Buy a loot box (phase 1 of 2)
+    /// `commitment` = hash(secret); open_lootbox reveals the secret, which is mixed with the hash
+    /// of the slot after this one, so the roll can't be predicted or chosen at purchase time.
+    pub fn buy_lootbox(
+        ctx: Context<BuyLootbox>,
+        tier: u8,
+        nonce: u64,
+        commitment: [u8; 32]
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(tier <= 2, IdlError::InvalidInput); // 0=common, 1=rare, 2=legendary
 
+        let price = match tier {
+            0 => cfg.lootbox_common_price,
+            1 => cfg.lootbox_rare_price,
+            2 => cfg.lootbox_legendary_price,
+            _ => return Err(IdlError::InvalidInput.into()),
+        };
+
+        // Pay for lootbox: LOOTBOX_BURN_PERCENT burned, the rest funds token rewards
+        let burn_amount = price * cfg.lootbox_burn_percent / 100;
         let cpi_accounts = Transfer {
-            from: ctx.accounts.market_pool.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.state.to_account_info(),
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.burn_vault.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
         };
         token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                cpi_accounts,
-                signer_seeds
-            ),
-            payout
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            burn_amount
+        )?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            price - burn_amount
         )?;
 
-        // Record cashout
-        let cashout = &mut ctx.accounts.cashout_position;
-        cashout.owner = ctx.accounts.user.key();
-        cashout.bet = bet.key();
-        cashout.market = market.key();
-        cashout.original_amount = cashout_amount;
-        cashout.cashed_out_amount = cashout_amount;
-        cashout.received_amount = payout;
-        cashout.cashed_out_at = clock.unix_timestamp;
-        cashout.bump = ctx.bumps.cashout_position;
+        let state = &mut ctx.accounts.state;
+        state.total_burned = state.total_burned
+            .checked_add(burn_amount)
+            .ok_or(IdlError::MathOverflow)?;
+        state.lootbox_budget = state.lootbox_budget
+            .checked_add(price - burn_amount)
+            .ok_or(IdlError::MathOverflow)?;
 
-        msg!("Cashed out {} IDL for {} IDL ({}bps odds)", cashout_amount, payout, current_odds_bps);
+        let reward = &mut ctx.accounts.lootbox_reward;
+        reward.owner = ctx.accounts.buyer.key();
+        reward.tier = tier;
+        reward.bump = ctx.bumps.lootbox_reward;
+        reward.commitment = commitment;
+        reward.commit_slot = Clock::get()?.slot;
+        reward.opened = false;
+        reward.used = false;
+
+        emit!(LootboxPurchased {
+            version: EVENT_VERSION,
+            lootbox: reward.key(),
+            owner: reward.owner,
+            tier,
+            price,
+            burned: burn_amount,
+            commit_slot: reward.commit_slot,
+        });
+        msg!("Lootbox {} bought (tier {}), open it after slot {}", nonce, tier, reward.commit_slot + 1);
         Ok(())
     }
 
-    /// Initialize dynamic odds for a market
-    pub fn init_dynamic_odds(ctx: Context<InitDynamicOdds>) -> Result<()> {
+    /// Open a loot box (phase 2 of 2)
+    /// The roll is hash(secret, SlotHashes[commit_slot + 1], lootbox). Boxes opened after that
+    /// slot hash has left SlotHashes (~512 slots) get roll 0, so waiting out a bad roll never helps.
+    pub fn open_lootbox(ctx: Context<OpenLootbox>, secret: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
         let clock = Clock::get()?;
-        let odds = &mut ctx.accounts.dynamic_odds;
+        let lootbox_key = ctx.accounts.lootbox_reward.key();
+        let reward = &mut ctx.accounts.lootbox_reward;
+
+        require!(!reward.opened, IdlError::LootboxAlreadyOpened);
+        require!(
+            anchor_lang::solana_program::hash::hash(&secret).to_bytes() == reward.commitment,
+            IdlError::InvalidCommitment
+        );
+
+        let target_slot = reward.commit_slot + 1;
+        require!(clock.slot > target_slot, IdlError::LootboxNotReady);
 
-        odds.market = ctx.accounts.market.key();
-        odds.yes_odds_bps = ODDS_BASE_YES; // Start at 50%
-        odds.no_odds_bps = 10000 - ODDS_BASE_YES;
-        odds.last_update = clock.unix_timestamp;
-        odds.volume_since_update = 0;
-        odds.bump = ctx.bumps.dynamic_odds;
+        let slot_hash = recent_slot_hash(&ctx.accounts.slot_hashes, target_slot)?;
+        let random = lootbox_roll(&secret, slot_hash, &lootbox_key);
+        let (reward_type, reward_value, expires_in) = lootbox_prize(cfg, reward.tier, random);
 
-        msg!("Dynamic odds initialized at 50/50");
+        reward.reward_type = reward_type;
+        reward.reward_value = reward_value;
+        reward.expires_at = if expires_in > 0 { clock.unix_timestamp + expires_in } else { 0 };
+        reward.opened = true;
+
+        emit!(LootboxOpened {
+            version: EVENT_VERSION,
+            lootbox: lootbox_key,
+            owner: reward.owner,
+            reward_type,
+            reward_value,
+            expires_at: reward.expires_at,
+        });
+        msg!("Lootbox opened! Reward type: {}, value: {}", reward_type, reward_value);
         Ok(())
     }
-}
 
-// ==================== HELPER FUNCTIONS ====================
+    /// Redeem a one-shot lootbox reward: IDL tokens (paid from the lootbox budget) or a VIP tier
+    /// Fee discounts and stake boosts apply automatically until they expire.
+    pub fn redeem_lootbox(ctx: Context<RedeemLootbox>) -> Result<()> {
+        let reward = &mut ctx.accounts.lootbox_reward;
+        reward.require_redeemable()?;
+
+        match reward.reward_type {
+            2 => {
+                let stats = ctx.accounts.predictor_stats
+                    .as_mut()
+                    .ok_or(IdlError::InvalidInput)?;
+                stats.vip_bonus_tiers = stats.vip_bonus_tiers.saturating_add(reward.reward_value as u8);
+                stats.vip_tier = (stats.vip_tier.saturating_add(reward.reward_value as u8)).min(4);
+                msg!("Lootbox VIP upgrade redeemed, tier now {}", stats.vip_tier);
+            }
+            3 => {
+                let amount = reward.reward_value;
+                let state = &mut ctx.accounts.state;
+                require!(state.lootbox_budget >= amount, IdlError::InsufficientPoolBalance);
+                state.lootbox_budget -= amount;
+
+                transfer_from_vault(
+                    &ctx.accounts.token_program.to_account_info(),
+                    &ctx.accounts.vault.to_account_info(),
+                    &ctx.accounts.owner_token_account.to_account_info(),
+                    &ctx.accounts.state.to_account_info(),
+                    ctx.accounts.state.bump,
+                    amount
+                )?;
+                msg!("Lootbox redeemed for {} IDL", amount);
+            }
+            _ => return Err(IdlError::InvalidInput.into()),
+        }
 
-/// Calculate earned rewards for a staker using checkpoint system
-fn calculate_earned(staker: &StakerAccount, state: &ProtocolState) -> u64 {
-    if staker.staked_amount == 0 {
-        return 0;
+        reward.used = true;
+        emit!(LootboxRedeemed {
+            version: EVENT_VERSION,
+            lootbox: reward.key(),
+            owner: reward.owner,
+            reward_type: reward.reward_type,
+            reward_value: reward.reward_value,
+        });
+        Ok(())
     }
 
-    let reward_delta = state.reward_per_token_stored
-        .saturating_sub(staker.reward_per_token_paid);
+    /// Create a limit order
+    /// `order_id` lets a user keep several orders per market; it becomes the bet nonce on fill
+    pub fn create_limit_order(
+        ctx: Context<CreateLimitOrder>,
+        amount: u64,
+        bet_yes: bool,
+        target_odds_bps: u64,
+        order_id: u64,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
+        require!(amount >= cfg.min_bet_amount, IdlError::BetTooSmall);
+        require!(amount <= cfg.max_bet_amount, IdlError::BetTooLarge);
+        require!(target_odds_bps > 0 && target_odds_bps < 10000, IdlError::InvalidInput);
 
-    // Scale down from 1e18 precision
-    ((staker.staked_amount as u128)
-        .saturating_mul(reward_delta)
-        / 1_000_000_000_000_000_000u128) as u64
-}
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
+        let betting_close = market.resolution_timestamp - cfg.betting_close_window;
+        require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+        require!(market.is_two_sided(), IdlError::WrongMarketType);
+        require!(clock.unix_timestamp < betting_close, IdlError::BettingClosed);
 
-/// Update reward_per_token when new rewards are added
-fn update_reward_per_token(state: &mut ProtocolState, new_rewards: u64) {
-    if state.total_staked > 0 {
-        // Scale up by 1e18 for precision
-        let reward_increase = (new_rewards as u128)
-            .saturating_mul(1_000_000_000_000_000_000u128)
-            / (state.total_staked as u128);
-        state.reward_per_token_stored = state.reward_per_token_stored
-            .saturating_add(reward_increase);
-    }
-}
+        // Lock funds for the order
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount
+        )?;
 
-/// RICK FIX: Get total voting power for a user (veIDL from lock + badge)
-/// This accounts for veIDL decay over time
-pub fn get_voting_power(
-    ve_position: Option<&VePosition>,
-    badge: Option<&VolumeBadge>,
-    current_time: i64
-) -> u64 {
-    let ve_power = ve_position
-        .map(|vp| vp.current_ve_amount(current_time))
-        .unwrap_or(0);
+        let order = &mut ctx.accounts.limit_order;
 
-    let badge_power = badge
-        .map(|b| b.ve_amount)
-        .unwrap_or(0);
+        order.owner = ctx.accounts.user.key();
+        order.market = ctx.accounts.market.key();
+        order.amount = amount;
+        order.bet_yes = bet_yes;
+        order.target_odds_bps = target_odds_bps;
+        // Orders can't outlive betting on their market
+        order.expires_at = std::cmp::min(clock.unix_timestamp + cfg.limit_order_expiry, betting_close);
+        order.filled = false;
+        order.created_at = clock.unix_timestamp;
+        order.bump = ctx.bumps.limit_order;
+        order.order_id = order_id;
+
+        emit!(LimitOrderCreated {
+            version: EVENT_VERSION,
+            order: order.key(),
+            market: order.market,
+            owner: order.owner,
+            amount,
+            bet_yes,
+            target_odds_bps,
+            expires_at: order.expires_at,
+        });
+        msg!("Limit order created: {} IDL at {}bps odds", amount, target_odds_bps);
+        Ok(())
+    }
 
-    ve_power.saturating_add(badge_power)
-}
+    /// Cancel a limit order
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        let order = &mut ctx.accounts.limit_order;
 
-// ==================== ACCOUNTS ====================
+        require!(!order.filled, IdlError::OrderInactive);
+        require!(order.owner == ctx.accounts.user.key(), IdlError::Unauthorized);
 
-/// Step 1: Create protocol state only
-#[derive(Accounts)]
-pub struct CreateState<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + ProtocolState::INIT_SPACE,
-        seeds = [b"state"],
-        bump
-    )]
-    pub state: Box<Account<'info, ProtocolState>>,
+        // Refund locked funds
+        let state_bump = ctx.accounts.state.bump;
+        let seeds = &[b"state".as_ref(), &[state_bump]];
+        let signer_seeds = &[&seeds[..]];
 
-    pub idl_mint: Box<Account<'info, Mint>>,
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds
+            ),
+            order.amount
+        )?;
 
-    /// CHECK: Treasury account
-    pub treasury: UncheckedAccount<'info>,
+        order.filled = true; // Mark as "used" to prevent double cancel
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        emit!(LimitOrderClosed {
+            version: EVENT_VERSION,
+            order: order.key(),
+            owner: order.owner,
+            refunded: order.amount,
+            expired: false,
+        });
+        msg!("Limit order cancelled, {} IDL refunded", order.amount);
+        Ok(())
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Fill a limit order once the market's implied odds reach its target (permissionless keeper crank)
+    /// The locked funds become a regular Bet; the keeper earns LIMIT_ORDER_FEE_BPS and the
+    /// market creator its volume fee, both taken from the order.
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
-/// Step 2: Initialize vault
-#[derive(Accounts)]
-pub struct InitVault<'info> {
-    #[account(
-        mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
-    )]
-    pub state: Box<Account<'info, ProtocolState>>,
+        let order = &ctx.accounts.limit_order;
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
 
-    #[account(
-        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
-    )]
-    pub idl_mint: Box<Account<'info, Mint>>,
+        require!(!market.resolved, IdlError::MarketResolved);
+        require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+        require!(
+            clock.unix_timestamp < market.resolution_timestamp - cfg.betting_close_window,
+            IdlError::BettingClosed
+        );
 
-    #[account(
-        init,
-        payer = authority,
-        seeds = [b"vault"],
-        bump,
-        token::mint = idl_mint,
-        token::authority = state,
-    )]
-    pub vault: Box<Account<'info, TokenAccount>>,
+        ctx.accounts.dynamic_odds.init_for(cfg, market, ctx.bumps.dynamic_odds, clock.unix_timestamp);
+        let odds_bps = ctx.accounts.dynamic_odds.odds_bps(order.bet_yes);
+        order.check_fill(odds_bps, clock.unix_timestamp)?;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        let keeper_fee = (order.amount as u128 * cfg.limit_order_fee_bps as u128 / 10000) as u64;
+        // PREDICTION MINING: Creator's volume fee stays in the vault as it does on reveal
+        let creator_fee = accrue_creator_volume_fee(cfg, &mut ctx.accounts.creator_stats, order.amount);
+        let bet_amount = order.amount
+            .saturating_sub(keeper_fee)
+            .saturating_sub(creator_fee);
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        let state_bump = ctx.accounts.state.bump;
+        let seeds = &[b"state".as_ref(), &[state_bump]];
+        let signer_seeds = &[&seeds[..]];
 
-/// Step 3: Initialize burn vault and activate
-#[derive(Accounts)]
-pub struct InitBurnVault<'info> {
-    #[account(
-        mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
-    )]
-    pub state: Box<Account<'info, ProtocolState>>,
-
-    #[account(
-        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
-    )]
-    pub idl_mint: Box<Account<'info, Mint>>,
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.market_pool.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds
+            ),
+            bet_amount
+        )?;
 
-    #[account(
-        init,
-        payer = authority,
-        seeds = [b"burn_vault"],
-        bump,
-        token::mint = idl_mint,
-        token::authority = state,
-    )]
-    pub burn_vault: Box<Account<'info, TokenAccount>>,
+        if keeper_fee > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.keeper_token_account.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds
+                ),
+                keeper_fee
+            )?;
+        }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        let staked_amount = ctx.accounts.staker_account
+            .as_ref()
+            .map(|s| s.staked_amount)
+            .unwrap_or(0);
+        let effective_amount = with_stake_bonus(cfg, bet_amount, staked_amount);
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        let order = &mut ctx.accounts.limit_order;
+        let market = &mut ctx.accounts.market;
+        // COMMITMENT_SNIPE: Fills join the pending batch like revealed bets
+        market.add_pending_reveal(cfg, order.bet_yes as u8, bet_amount, effective_amount, clock.unix_timestamp);
+        ctx.accounts.dynamic_odds.record_volume(cfg, market, bet_amount, clock.unix_timestamp);
 
-#[derive(Accounts)]
-pub struct Stake<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+        let bet = &mut ctx.accounts.bet;
+        bet.owner = order.owner;
+        bet.market = market.key();
+        bet.amount = bet_amount;
+        bet.effective_amount = effective_amount;
+        bet.bet_yes = order.bet_yes;
+        bet.outcome_index = order.bet_yes as u8;
+        bet.timestamp = clock.unix_timestamp;
+        bet.claimed = false;
+        bet.nonce = order.order_id;
+        bet.bump = ctx.bumps.bet;
 
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + StakerAccount::INIT_SPACE,
-        seeds = [b"staker", user.key().as_ref()],
-        bump
-    )]
-    pub staker_account: Account<'info, StakerAccount>,
+        order.filled = true;
 
-    #[account(
-        mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
-        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
+        if let Some(ref mut user_volume) = ctx.accounts.user_volume {
+            user_volume.total_volume_usd = user_volume.total_volume_usd
+                .checked_add(bet_amount)
+                .ok_or(IdlError::MathOverflow)?;
+            user_volume.last_updated = clock.unix_timestamp;
+        }
 
-    #[account(
-        mut,
-        seeds = [b"vault"],
-        bump = state.vault_bump
-    )]
-    pub vault: Account<'info, TokenAccount>,
+        emit!(LimitOrderFilled {
+            version: EVENT_VERSION,
+            order: order.key(),
+            market: bet.market,
+            owner: bet.owner,
+            bet_amount,
+            effective_amount,
+            odds_bps,
+            keeper: ctx.accounts.keeper_token_account.owner,
+            keeper_fee,
+            creator_fee,
+        });
+        msg!("Limit order filled: {} IDL on {} at {}bps (keeper fee: {})",
+            bet_amount, if bet.bet_yes { "YES" } else { "NO" }, odds_bps, keeper_fee);
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Refund an expired, unfilled limit order to its owner (permissionless)
+    pub fn expire_limit_order(ctx: Context<ExpireLimitOrder>) -> Result<()> {
+        let order = &ctx.accounts.limit_order;
+        let clock = Clock::get()?;
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        require!(!order.filled, IdlError::OrderInactive);
+        require!(clock.unix_timestamp >= order.expires_at, IdlError::LimitOrderNotExpired);
 
-#[derive(Accounts)]
-pub struct Unstake<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+        let state_bump = ctx.accounts.state.bump;
+        let seeds = &[b"state".as_ref(), &[state_bump]];
+        let signer_seeds = &[&seeds[..]];
 
-    #[account(
-        mut,
-        seeds = [b"staker", user.key().as_ref()],
-        bump = staker_account.bump,
-        constraint = staker_account.owner == user.key() @ IdlError::Unauthorized
-    )]
-    pub staker_account: Account<'info, StakerAccount>,
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.owner_token_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds
+            ),
+            order.amount
+        )?;
 
-    #[account(
-        seeds = [b"ve_position", user.key().as_ref()],
-        bump
-    )]
-    pub ve_position: Option<Account<'info, VePosition>>,
+        let order = &mut ctx.accounts.limit_order;
+        order.filled = true; // Consumed, same as cancel
+
+        emit!(LimitOrderClosed {
+            version: EVENT_VERSION,
+            order: order.key(),
+            owner: order.owner,
+            refunded: order.amount,
+            expired: true,
+        });
+        msg!("Expired limit order refunded: {} IDL", order.amount);
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
-        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    /// Set a stop loss on a bet
+    pub fn set_stop_loss(
+        ctx: Context<SetStopLoss>,
+        threshold_bps: u64,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(threshold_bps >= cfg.stop_loss_min_threshold, IdlError::InvalidInput);
+        require!(threshold_bps <= 9000, IdlError::InvalidInput); // Max 90% loss
 
-    #[account(
-        mut,
-        seeds = [b"vault"],
-        bump = state.vault_bump
-    )]
-    pub vault: Account<'info, TokenAccount>,
+        let clock = Clock::get()?;
+        let stop_loss = &mut ctx.accounts.stop_loss;
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+        stop_loss.owner = ctx.accounts.user.key();
+        stop_loss.bet = ctx.accounts.bet.key();
+        stop_loss.market = ctx.accounts.bet.market;
+        stop_loss.threshold_bps = threshold_bps;
+        stop_loss.entry_odds_bps = ctx.accounts.dynamic_odds.odds_bps(ctx.accounts.bet.bet_yes);
+        stop_loss.triggered = false;
+        stop_loss.active = true;
+        stop_loss.created_at = clock.unix_timestamp;
+        stop_loss.bump = ctx.bumps.stop_loss;
 
-    pub token_program: Program<'info, Token>,
-}
+        emit!(StopLossSet {
+            version: EVENT_VERSION,
+            bet: stop_loss.bet,
+            owner: stop_loss.owner,
+            threshold_bps,
+        });
+        msg!("Stop loss set at {}bps threshold", threshold_bps);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct LockForVe<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+    /// Partial cashout - exit early at current odds
+    pub fn partial_cashout(ctx: Context<PartialCashout>, cashout_amount: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let clock = Clock::get()?;
+        let original_amount = ctx.accounts.bet.amount;
 
-    #[account(
-        seeds = [b"staker", user.key().as_ref()],
-        bump = staker_account.bump,
-        constraint = staker_account.owner == user.key()
-    )]
-    pub staker_account: Account<'info, StakerAccount>,
+        // Calculate cashout value based on current odds
+        let current_odds_bps = ctx.accounts.dynamic_odds.odds_bps(ctx.accounts.bet.bet_yes);
+        let fee_bps = discounted_fee_bps(
+            cfg,
+            cfg.cashout_fee_bps,
+            &ctx.accounts.user.key(),
+            ctx.accounts.predictor_stats.as_ref().map(|s| &***s),
+            ctx.accounts.lootbox_reward.as_ref().map(|l| &***l),
+            clock.unix_timestamp
+        );
+        let (_, payout) = cash_out_bet(
+            cfg,
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.bet,
+            cashout_amount,
+            current_odds_bps,
+            fee_bps,
+            clock.unix_timestamp
+        )?;
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + VePosition::INIT_SPACE,
-        seeds = [b"ve_position", user.key().as_ref()],
-        bump
-    )]
-    pub ve_position: Account<'info, VePosition>,
+        // Transfer payout
+        let market_key = ctx.accounts.market.key();
+        let market_seeds = &[
+            b"market_pool".as_ref(),
+            market_key.as_ref(),
+            &[ctx.bumps.market_pool],
+        ];
+        transfer_from_market_pool(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.market_pool.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &[&market_seeds[..]],
+            payout
+        )?;
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+        // Record cashout
+        let cashout = &mut ctx.accounts.cashout_position;
+        cashout.owner = ctx.accounts.user.key();
+        cashout.bet = ctx.accounts.bet.key();
+        cashout.market = market_key;
+        cashout.original_amount = original_amount;
+        cashout.cashed_out_amount = cashout_amount;
+        cashout.received_amount = payout;
+        cashout.cashed_out_at = clock.unix_timestamp;
+        cashout.bump = ctx.bumps.cashout_position;
 
-    pub system_program: Program<'info, System>,
-}
+        emit!(BetCashedOut {
+            version: EVENT_VERSION,
+            market: market_key,
+            bet: cashout.bet,
+            owner: cashout.owner,
+            cashed_out_amount: cashout_amount,
+            payout,
+            odds_bps: current_odds_bps,
+            keeper: None,
+            keeper_reward: 0,
+        });
+        msg!("Cashed out {} IDL for {} IDL ({}bps odds)", cashout_amount, payout, current_odds_bps);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct UnlockVe<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+    /// Trigger a stop loss once the bet's side has lost `threshold_bps` of its entry odds
+    /// Permissionless keeper crank: cashes out the whole remaining position at current odds,
+    /// with the owner's fee discounts, and pays the keeper STOP_LOSS_KEEPER_REWARD_BPS of the payout.
+    pub fn trigger_stop_loss(ctx: Context<TriggerStopLoss>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        require!(!ctx.accounts.state.paused, IdlError::ProtocolPaused);
 
-    #[account(
-        mut,
-        close = user,
-        seeds = [b"ve_position", user.key().as_ref()],
-        bump = ve_position.bump,
-        constraint = ve_position.owner == user.key()
-    )]
-    pub ve_position: Account<'info, VePosition>,
+        let stop_loss = &ctx.accounts.stop_loss;
+        let clock = Clock::get()?;
 
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        require!(stop_loss.active && !stop_loss.triggered, IdlError::OrderInactive);
+        require!(
+            clock.unix_timestamp >= stop_loss.created_at + cfg.stop_loss_check_interval,
+            IdlError::StopLossTooRecent
+        );
 
-/// RICK FIX: ExtendLock accounts
-#[derive(Accounts)]
-pub struct ExtendLock<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+        let current_odds_bps = ctx.accounts.dynamic_odds.odds_bps(ctx.accounts.bet.bet_yes);
+        require!(stop_loss.is_reached(current_odds_bps), IdlError::StopLossNotReached);
+
+        let position = ctx.accounts.bet.amount;
+        let fee_bps = discounted_fee_bps(
+            cfg,
+            cfg.cashout_fee_bps,
+            &ctx.accounts.bet.owner,
+            ctx.accounts.predictor_stats.as_ref().map(|s| &***s),
+            ctx.accounts.lootbox_reward.as_ref().map(|l| &***l),
+            clock.unix_timestamp
+        );
+        let (_, payout) = cash_out_bet(
+            cfg,
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.bet,
+            position,
+            current_odds_bps,
+            fee_bps,
+            clock.unix_timestamp
+        )?;
+        let keeper_reward = (payout as u128 * cfg.stop_loss_keeper_reward_bps as u128 / 10000) as u64;
 
-    #[account(
-        mut,
-        seeds = [b"ve_position", user.key().as_ref()],
-        bump = ve_position.bump,
-        constraint = ve_position.owner == user.key() @ IdlError::Unauthorized
-    )]
-    pub ve_position: Account<'info, VePosition>,
+        let market_key = ctx.accounts.market.key();
+        let market_seeds = &[
+            b"market_pool".as_ref(),
+            market_key.as_ref(),
+            &[ctx.bumps.market_pool],
+        ];
+        let signer_seeds = &[&market_seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let market_pool = ctx.accounts.market_pool.to_account_info();
+
+        transfer_from_market_pool(
+            &token_program,
+            &market_pool,
+            &ctx.accounts.owner_token_account.to_account_info(),
+            signer_seeds,
+            payout.saturating_sub(keeper_reward)
+        )?;
+        transfer_from_market_pool(
+            &token_program,
+            &market_pool,
+            &ctx.accounts.keeper_token_account.to_account_info(),
+            signer_seeds,
+            keeper_reward
+        )?;
 
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        let stop_loss = &mut ctx.accounts.stop_loss;
+        stop_loss.triggered = true;
+        stop_loss.active = false;
+
+        emit!(BetCashedOut {
+            version: EVENT_VERSION,
+            market: market_key,
+            bet: stop_loss.bet,
+            owner: stop_loss.owner,
+            cashed_out_amount: position,
+            payout,
+            odds_bps: current_odds_bps,
+            keeper: Some(ctx.accounts.keeper_token_account.owner),
+            keeper_reward,
+        });
+        msg!("Stop loss triggered at {}bps: {} IDL cashed out for {} (keeper: {})",
+            current_odds_bps, position, payout, keeper_reward);
+        Ok(())
+    }
 
-// ==================== 10/10 ACCOUNT STRUCTS ====================
+    /// Initialize dynamic odds for a market
+    pub fn init_dynamic_odds(ctx: Context<InitDynamicOdds>) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let clock = Clock::get()?;
+        let odds = &mut ctx.accounts.dynamic_odds;
 
-#[derive(Accounts)]
-pub struct CommitBet<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+        // Starts at 50/50, or at spot odds if the market already has volume
+        odds.init_for(cfg, &ctx.accounts.market, ctx.bumps.dynamic_odds, clock.unix_timestamp);
 
-    pub market: Account<'info, PredictionMarket>,
+        msg!("Dynamic odds initialized at {}bps YES", odds.yes_odds_bps);
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + BetCommitment::INIT_SPACE,
-        seeds = [b"bet_commit", market.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub bet_commitment: Account<'info, BetCommitment>,
+    // ═══════════════════════════════════════════════════════════════════════════════
+    // GOVERNANCE - veIDL proposals
+    // ═══════════════════════════════════════════════════════════════════════════════
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Open a proposal; the proposer needs proposal_min_voting_power at the snapshot.
+    /// `config_params` carries the new parameters for UpdateConfig proposals;
+    /// `description_hash` commits to the off-chain proposal text.
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        action: GovernanceAction,
+        config_params: Option<ConfigParams>,
+        description_hash: [u8; 32]
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config.params;
+        let state = &mut ctx.accounts.state;
+        let clock = Clock::get()?;
 
-    pub system_program: Program<'info, System>,
-}
+        action.validate(config_params.as_ref())?;
 
-#[derive(Accounts)]
-#[instruction(amount: u64, bet_yes: bool, nonce: u64, salt: [u8; 32])]
-pub struct RevealBet<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Box<Account<'info, ProtocolState>>,
+        // VE_DECAY_ARBITRAGE FIX: Same snapshot rule as dispute votes
+        let snapshot_time = clock.unix_timestamp - cfg.vote_snapshot_delay;
+        let power = snapshot_voting_power(
+            ctx.accounts.ve_position.as_ref().map(|vp| &***vp),
+            ctx.accounts.badge.as_ref().map(|b| &***b),
+            snapshot_time
+        );
+        require!(power >= cfg.proposal_min_voting_power, IdlError::InsufficientVotingPower);
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = state.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.config_params = config_params;
+        proposal.description_hash = description_hash;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.snapshot_time = snapshot_time;
+        proposal.voting_ends_at = clock.unix_timestamp + cfg.proposal_voting_period;
+        proposal.executable_at = proposal.voting_ends_at + cfg.proposal_execution_delay;
+        // total_ve_supply tracks undecayed amounts, so quorum errs on the high side
+        proposal.quorum = (state.total_ve_supply as u128 * cfg.proposal_quorum_bps as u128 / 10000) as u64;
+        proposal.pass_threshold_bps = cfg.proposal_pass_threshold_bps;
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        state.proposal_count = state.proposal_count.saturating_add(1);
+
+        emit!(ProposalCreated {
+            version: EVENT_VERSION,
+            proposal: proposal.key(),
+            id: proposal.id,
+            proposer: proposal.proposer,
+            action: proposal.action,
+            config_params: proposal.config_params,
+            description_hash,
+            snapshot_time,
+            voting_ends_at: proposal.voting_ends_at,
+            executable_at: proposal.executable_at,
+            quorum: proposal.quorum,
+        });
+        msg!("Proposal {} created, voting open until {}", proposal.id, proposal.voting_ends_at);
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub market: Box<Account<'info, PredictionMarket>>,
+    /// Vote on a proposal with veIDL + badge power at the proposal's snapshot
+    pub fn vote_on_proposal(ctx: Context<VoteOnProposal>, support: bool) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
 
-    #[account(
-        mut,
-        seeds = [b"bet_commit", market.key().as_ref(), user.key().as_ref()],
-        bump = bet_commitment.bump,
-        constraint = bet_commitment.owner == user.key() @ IdlError::Unauthorized
-    )]
-    pub bet_commitment: Box<Account<'info, BetCommitment>>,
+        require!(clock.unix_timestamp < proposal.voting_ends_at, IdlError::ProposalVotingClosed);
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + Bet::INIT_SPACE,
-        seeds = [b"bet", market.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub bet: Box<Account<'info, Bet>>,
+        let weight = snapshot_voting_power(
+            ctx.accounts.ve_position.as_ref().map(|vp| &***vp),
+            ctx.accounts.badge.as_ref().map(|b| &***b),
+            proposal.snapshot_time
+        );
+        require!(weight > 0, IdlError::VoteSnapshotNotReady);
 
-    #[account(
-        seeds = [b"staker", user.key().as_ref()],
-        bump
-    )]
-    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
+        if support {
+            proposal.votes_for = proposal.votes_for.saturating_add(weight);
+        } else {
+            proposal.votes_against = proposal.votes_against.saturating_add(weight);
+        }
 
-    // AUDIT FIX: Add user_volume to track volume for badges
-    // SELF-REVIEW FIX: Use "volume" seed to match IssueBadge (was "user_volume")
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + UserVolume::INIT_SPACE,
-        seeds = [b"volume", user.key().as_ref()],
-        bump
-    )]
-    pub user_volume: Box<Account<'info, UserVolume>>,
+        let vote = &mut ctx.accounts.proposal_vote;
+        vote.proposal = proposal.key();
+        vote.voter = ctx.accounts.voter.key();
+        vote.support = support;
+        vote.weight = weight;
+        vote.bump = ctx.bumps.proposal_vote;
+
+        emit!(ProposalVoteCast {
+            version: EVENT_VERSION,
+            proposal: vote.proposal,
+            voter: vote.voter,
+            support,
+            weight,
+        });
+        msg!("Proposal {} vote: {} with {} power", proposal.id, support, weight);
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
-    )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    /// Execute a passed proposal once its timelock has expired (permissionless)
+    /// SpendInsurance proposals need `recipient_token_account`.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.require_executable(clock.unix_timestamp)?;
 
-    #[account(
-        mut,
-        seeds = [b"market_pool", market.key().as_ref()],
-        bump
-    )]
-    pub market_pool: Box<Account<'info, TokenAccount>>,
+        let state = &mut ctx.accounts.state;
+        match proposal.action {
+            GovernanceAction::UpdateConfig => {
+                let params = proposal.config_params.ok_or(IdlError::InvalidConfig)?;
+                params.validate()?;
+                ctx.accounts.config.params = params;
+            }
+            GovernanceAction::SetPaused(paused) => {
+                state.paused = paused;
+            }
+            GovernanceAction::SetTvlCap(new_cap) => {
+                require!(!state.tvl_raise_active(clock.unix_timestamp), IdlError::TvlRaiseQueueActive);
+                if new_cap > state.tvl_cap {
+                    // TVL_CAP_RACE FIX: New headroom still goes through the pro-rata queue
+                    let queue_window = ctx.accounts.config.params.tvl_raise_queue_window;
+                    state.open_tvl_raise(new_cap, queue_window, clock.unix_timestamp);
+                } else {
+                    state.tvl_cap = new_cap;
+                }
+            }
+            GovernanceAction::SpendInsurance { recipient, amount } => {
+                let recipient_token_account = ctx.accounts.recipient_token_account
+                    .as_ref()
+                    .ok_or(IdlError::InvalidInput)?;
+                require!(recipient_token_account.key() == recipient, IdlError::InvalidInput);
+                require!(recipient_token_account.mint == state.idl_mint, IdlError::InvalidMint);
+                require!(amount <= state.insurance_fund, IdlError::InsufficientInsuranceFund);
+
+                // AUDIT FIX: Same reserve check as withdraw_insurance
+                let min_vault_balance = state.total_staked
+                    .checked_add(state.reward_pool)
+                    .ok_or(IdlError::MathOverflow)?;
+                require!(
+                    ctx.accounts.vault.amount.saturating_sub(amount) >= min_vault_balance,
+                    IdlError::InsufficientPoolBalance
+                );
+
+                transfer_from_vault(
+                    &ctx.accounts.token_program.to_account_info(),
+                    &ctx.accounts.vault.to_account_info(),
+                    &recipient_token_account.to_account_info(),
+                    &state.to_account_info(),
+                    state.bump,
+                    amount
+                )?;
+                state.insurance_fund = state.insurance_fund.saturating_sub(amount);
+            }
+        }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+        proposal.executed = true;
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+        emit!(ProposalExecuted {
+            version: EVENT_VERSION,
+            proposal: proposal.key(),
+            id: proposal.id,
+            action: proposal.action,
+        });
+        msg!("Proposal {} executed", proposal.id);
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct DepositOracleBond<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+// ==================== HELPER FUNCTIONS ====================
 
-    #[account(
-        init,
-        payer = oracle,
-        space = 8 + OracleBond::INIT_SPACE,
-        seeds = [b"oracle_bond", oracle.key().as_ref()],
-        bump
-    )]
-    pub oracle_bond: Account<'info, OracleBond>,
+/// Calculate earned rewards for a staker using checkpoint system
+fn calculate_earned(staker: &StakerAccount, state: &ProtocolState) -> u64 {
+    if staker.staked_amount == 0 {
+        return 0;
+    }
 
-    #[account(
-        mut,
-        constraint = oracle_token_account.mint == state.idl_mint @ IdlError::InvalidMint
-    )]
-    pub oracle_token_account: Account<'info, TokenAccount>,
+    let reward_delta = state.reward_per_token_stored
+        .saturating_sub(staker.reward_per_token_paid);
 
-    #[account(
-        mut,
-        seeds = [b"vault"],
-        bump = state.vault_bump
-    )]
-    pub vault: Account<'info, TokenAccount>,
+    // Scale down from 1e18 precision
+    ((staker.staked_amount as u128)
+        .saturating_mul(reward_delta)
+        / 1_000_000_000_000_000_000u128) as u64
+}
 
-    #[account(mut)]
-    pub oracle: Signer<'info>,
+/// Update reward_per_token when new rewards are added
+fn update_reward_per_token(state: &mut ProtocolState, new_rewards: u64) {
+    if state.total_staked > 0 {
+        // Scale up by 1e18 for precision
+        let reward_increase = (new_rewards as u128)
+            .saturating_mul(1_000_000_000_000_000_000u128)
+            / (state.total_staked as u128);
+        state.reward_per_token_stored = state.reward_per_token_stored
+            .saturating_add(reward_increase);
+    }
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+/// Most common outcome among revealed oracle votes, how many agree on it, and whether that
+/// meets both the minimum oracle count and the consensus threshold
+fn tally_oracle_votes(cfg: &ConfigParams, keys: &[Option<u64>]) -> (u64, usize, bool) {
+    let revealed = keys.iter().flatten().count();
+    let (winning_key, agreeing) = keys
+        .iter()
+        .flatten()
+        .map(|key| (*key, keys.iter().flatten().filter(|k| *k == key).count()))
+        .max_by_key(|(_, count)| *count)
+        .unwrap_or((0, 0));
+    let consensus = agreeing >= cfg.min_oracle_consensus as usize
+        && agreeing * 100 >= cfg.oracle_consensus_threshold as usize * revealed;
+    (winning_key, agreeing, consensus)
 }
 
-#[derive(Accounts)]
-pub struct CommitResolution<'info> {
-    pub market: Account<'info, PredictionMarket>,
+/// An oracle is slashed for voting against consensus, or for committing and never revealing,
+/// which would otherwise let it withhold a losing vote at no cost
+fn oracle_slashable(key: Option<u64>, withheld: bool, consensus_key: Option<u64>) -> bool {
+    match key {
+        Some(key) => consensus_key.is_some_and(|winning| winning != key),
+        None => withheld,
+    }
+}
 
-    // SELF-REVIEW FIX: Make mutable to track active_resolution
-    #[account(
-        mut,
-        seeds = [b"oracle_bond", oracle.key().as_ref()],
-        bump = oracle_bond.bump,
-        constraint = oracle_bond.oracle == oracle.key() @ IdlError::Unauthorized
-    )]
-    pub oracle_bond: Account<'info, OracleBond>,
+/// Load (resolution_commitment, oracle_bond) pairs for every oracle registered on a market
+/// Each commitment must be the market's PDA for its oracle, with no oracle repeated.
+fn load_market_oracles<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    market: &PredictionMarket,
+    market_key: Pubkey,
+    program_id: &Pubkey
+) -> Result<Vec<(Account<'info, ResolutionCommitment>, Account<'info, OracleBond>)>> {
+    require!(
+        remaining_accounts.len() == 2 * market.oracle_count as usize,
+        IdlError::InvalidOracleAccounts
+    );
+
+    let mut oracles: Vec<(Account<'info, ResolutionCommitment>, Account<'info, OracleBond>)> =
+        Vec::with_capacity(remaining_accounts.len() / 2);
+    for pair in remaining_accounts.chunks(2) {
+        require!(pair[0].is_writable && pair[1].is_writable, IdlError::InvalidOracleAccounts);
+        let res_commit = Account::<ResolutionCommitment>::try_from(&pair[0])?;
+        let (expected_key, _) = Pubkey::find_program_address(
+            &[b"res_commit", market_key.as_ref(), res_commit.oracle.as_ref()],
+            program_id
+        );
+        require!(pair[0].key() == expected_key, IdlError::InvalidOracleAccounts);
+        require!(
+            !oracles.iter().any(|(existing, _)| existing.oracle == res_commit.oracle),
+            IdlError::InvalidOracleAccounts
+        );
 
-    #[account(
-        init,
-        payer = oracle,
-        space = 8 + ResolutionCommitment::INIT_SPACE,
-        seeds = [b"res_commit", market.key().as_ref()],
-        bump
-    )]
-    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+        let bond = Account::<OracleBond>::try_from(&pair[1])?;
+        require!(bond.oracle == res_commit.oracle, IdlError::InvalidOracleAccounts);
+        oracles.push((res_commit, bond));
+    }
+    Ok(oracles)
+}
 
-    #[account(mut)]
-    pub oracle: Signer<'info>,
+/// Settle pending rewards at the current checkpoint, then add `amount` to the staker's position
+fn credit_stake(
+    state: &mut ProtocolState,
+    staker: &mut StakerAccount,
+    owner: Pubkey,
+    bump: u8,
+    amount: u64,
+    now: i64
+) -> Result<()> {
+    // Initialize staker if new
+    if staker.owner == Pubkey::default() {
+        staker.owner = owner;
+        staker.bump = bump;
+        staker.reward_per_token_paid = state.reward_per_token_stored;
+    } else {
+        checkpoint_staker(staker, state)?;
+    }
 
-    pub system_program: Program<'info, System>,
+    // SECURITY FIX: Use checked arithmetic
+    staker.staked_amount = staker.staked_amount
+        .checked_add(amount)
+        .ok_or(IdlError::MathOverflow)?;
+    staker.last_stake_timestamp = now;
+    state.total_staked = state.total_staked
+        .checked_add(amount)
+        .ok_or(IdlError::MathOverflow)?;
+
+    emit!(Staked {
+        version: EVENT_VERSION,
+        user: owner,
+        amount,
+        staked_amount: staker.staked_amount,
+        total_staked: state.total_staked,
+    });
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct RevealResolution<'info> {
-    #[account(mut)]
-    pub market: Account<'info, PredictionMarket>,
+/// SECURITY FIX: Move earned rewards into pending before the stake changes
+fn checkpoint_staker(staker: &mut StakerAccount, state: &ProtocolState) -> Result<()> {
+    let earned = calculate_earned(staker, state);
+    staker.pending_rewards = staker.pending_rewards
+        .checked_add(earned)
+        .ok_or(IdlError::MathOverflow)?;
+    staker.reward_per_token_paid = state.reward_per_token_stored;
+    Ok(())
+}
 
-    #[account(
-        mut,
-        seeds = [b"res_commit", market.key().as_ref()],
-        bump = resolution_commitment.bump,
-        constraint = resolution_commitment.oracle == oracle.key() @ IdlError::Unauthorized
-    )]
-    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+/// AUTO-COMPOUND: Restake up to `amount` (the caller moves it into the vault)
+/// Only what fits under the TVL cap is restaked, and nothing while a TVL raise queue is open.
+/// Doesn't reset the unstake timer: compounding is not a fresh deposit.
+/// No AUTO_COMPOUND_BONUS_BPS is added: the vault holds no unallocated funds to back it.
+/// Returns the amount restaked.
+fn compound_into_stake(
+    state: &mut ProtocolState,
+    staker: &mut StakerAccount,
+    amount: u64,
+    now: i64
+) -> Result<u64> {
+    if state.tvl_raise_active(now) {
+        return Ok(0);
+    }
+    let restaked = amount.min(state.tvl_cap.saturating_sub(state.total_staked));
+    if restaked == 0 {
+        return Ok(0);
+    }
 
-    pub oracle: Signer<'info>,
+    checkpoint_staker(staker, state)?;
+    staker.staked_amount = staker.staked_amount
+        .checked_add(restaked)
+        .ok_or(IdlError::MathOverflow)?;
+    state.total_staked = state.total_staked
+        .checked_add(restaked)
+        .ok_or(IdlError::MathOverflow)?;
+    Ok(restaked)
 }
 
-#[derive(Accounts)]
-pub struct DisputeResolution<'info> {
-    // SELF-REVIEW FIX: Make state mutable to update insurance_fund
-    #[account(
-        mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
-    )]
-    pub state: Account<'info, ProtocolState>,
+/// Commitment hash for a stake: hash(amount, salt)
+fn stake_commitment_hash(amount: u64, salt: &[u8; 32]) -> [u8; 32] {
+    let mut hasher_input = Vec::new();
+    hasher_input.extend_from_slice(&amount.to_le_bytes());
+    hasher_input.extend_from_slice(salt);
 
-    #[account(
-        mut,
-        seeds = [b"res_commit", market.key().as_ref()],
-        bump = resolution_commitment.bump
-    )]
-    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+    anchor_lang::solana_program::hash::hash(&hasher_input).to_bytes()
+}
 
-    #[account(
-        mut,
-        seeds = [b"oracle_bond", resolution_commitment.oracle.as_ref()],
-        bump = oracle_bond.bump
-    )]
-    pub oracle_bond: Account<'info, OracleBond>,
+/// Commitment hash for a bet: hash(amount, side, nonce, salt)
+/// `side` is 1/0 for YES/NO, or the outcome index on categorical markets.
+fn bet_commitment_hash(amount: u64, side: u8, nonce: u64, salt: &[u8; 32]) -> [u8; 32] {
+    let mut hasher_input = Vec::new();
+    hasher_input.extend_from_slice(&amount.to_le_bytes());
+    hasher_input.push(side);
+    hasher_input.extend_from_slice(&nonce.to_le_bytes());
+    hasher_input.extend_from_slice(salt);
 
-    // AUDIT FIX: Make market mutable so we can cancel it
-    #[account(mut)]
-    pub market: Account<'info, PredictionMarket>,
+    anchor_lang::solana_program::hash::hash(&hasher_input).to_bytes()
+}
 
-    pub authority: Signer<'info>,
+/// Fee rate after the payer's VIP tier and any active lootbox fee discount
+/// Never below PROTOCOL_FEE_FLOOR_BPS (or the base rate, if that is already lower).
+fn discounted_fee_bps(
+    cfg: &ConfigParams,
+    base_bps: u64,
+    payer: &Pubkey,
+    stats: Option<&PredictorStats>,
+    lootbox: Option<&LootboxReward>,
+    now: i64
+) -> u64 {
+    let vip_discount = stats
+        .filter(|s| s.owner == *payer)
+        .map(|s| s.vip_fee_discount_bps(cfg))
+        .unwrap_or(0);
+    let lootbox_discount = lootbox
+        .filter(|l| l.owner == *payer && l.is_active_fee_discount(now))
+        .map(|l| l.reward_value)
+        .unwrap_or(0);
+    base_bps
+        .saturating_sub(vip_discount.saturating_add(lootbox_discount))
+        .max(cfg.protocol_fee_floor_bps.min(base_bps))
 }
 
-// AUDIT FIX: Allow oracle to withdraw bond after successful resolution
-#[derive(Accounts)]
-pub struct WithdrawOracleBond<'info> {
-    #[account(
-        seeds = [b"state"],
-        bump = state.bump
-    )]
-    pub state: Account<'info, ProtocolState>,
+/// Hash of the oldest SlotHashes entry at or after `slot`, or None if it has aged out
+/// SlotHashes data: u64 count, then (u64 slot, [u8; 32] hash) entries, newest first.
+fn recent_slot_hash(slot_hashes: &AccountInfo, slot: u64) -> Result<Option<[u8; 32]>> {
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8, IdlError::InvalidInput);
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+
+    let mut found = None;
+    for entry in data[8..].chunks_exact(40).take(count) {
+        let entry_slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+        if entry_slot < slot {
+            return Ok(found);
+        }
+        found = Some(entry[8..].try_into().unwrap());
+        if entry_slot == slot {
+            return Ok(found);
+        }
+    }
+    // Ran out of entries before reaching `slot`: it has aged out
+    Ok(None)
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault"],
-        bump = state.vault_bump
-    )]
-    pub vault: Account<'info, TokenAccount>,
+/// Lootbox roll in 0..100 from hash(secret, slot hash, lootbox); 0 once the slot hash has aged out
+fn lootbox_roll(secret: &[u8; 32], slot_hash: Option<[u8; 32]>, lootbox: &Pubkey) -> u8 {
+    let Some(slot_hash) = slot_hash else {
+        return 0;
+    };
+    let mut input = Vec::with_capacity(96);
+    input.extend_from_slice(secret);
+    input.extend_from_slice(&slot_hash);
+    input.extend_from_slice(lootbox.as_ref());
+    let roll = anchor_lang::solana_program::hash::hash(&input).to_bytes();
+    (u64::from_le_bytes(roll[..8].try_into().unwrap()) % 100) as u8
+}
 
-    #[account(
-        mut,
-        seeds = [b"oracle_bond", oracle.key().as_ref()],
-        bump = oracle_bond.bump,
-        constraint = oracle_bond.oracle == oracle.key() @ IdlError::Unauthorized
-    )]
-    pub oracle_bond: Account<'info, OracleBond>,
+/// (reward_type, reward_value, expires_in) for a lootbox tier and roll; low rolls are the smallest rewards
+fn lootbox_prize(cfg: &ConfigParams, tier: u8, random: u8) -> (u8, u64, i64) {
+    match tier {
+        0 => { // Common: mostly small discounts
+            if random < 70 {
+                (0u8, 100u64, 604800i64) // 1% fee discount for 7 days
+            } else if random < 90 {
+                (1u8, 200u64, 604800i64) // 2% stake boost for 7 days
+            } else {
+                (3u8, cfg.lootbox_common_price, 0i64) // 1 IDL back
+            }
+        },
+        1 => { // Rare: better rewards
+            if random < 50 {
+                (0u8, 300u64, 2592000i64) // 3% fee discount for 30 days
+            } else if random < 80 {
+                (1u8, 500u64, 2592000i64) // 5% stake boost for 30 days
+            } else {
+                (3u8, cfg.lootbox_rare_price * 2, 0i64) // 20 IDL back
+            }
+        },
+        _ => { // Legendary: big rewards
+            if random < 30 {
+                (0u8, 1000u64, 7776000i64) // 10% fee discount for 90 days
+            } else if random < 60 {
+                (1u8, 1000u64, 7776000i64) // 10% stake boost for 90 days
+            } else if random < 90 {
+                (2u8, 1u64, 0i64) // VIP tier upgrade
+            } else {
+                (3u8, cfg.lootbox_legendary_price * 5, 0i64) // 500 IDL jackpot!
+            }
+        },
+    }
+}
 
-    #[account(
-        seeds = [b"res_commit", market.key().as_ref()],
-        bump = resolution_commitment.bump,
-        constraint = resolution_commitment.oracle == oracle.key() @ IdlError::Unauthorized
-    )]
-    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+/// Early-bird bonus on a bet committed within EARLY_BIRD_WINDOW of market creation
+/// Uses the commit time so a late reveal keeps the bonus and a late commit can't claim it.
+fn early_bird_bonus(cfg: &ConfigParams, market: &PredictionMarket, commit_time: i64, amount: u64) -> u64 {
+    if commit_time > market.created_at + cfg.early_bird_window {
+        return 0;
+    }
+    (amount as u128 * cfg.early_bird_bonus_bps as u128 / 10000) as u64
+}
 
-    pub market: Account<'info, PredictionMarket>,
+/// Effective bet amount including the staker bonus (1% per million staked, capped)
+fn with_stake_bonus(cfg: &ConfigParams, amount: u64, staked_amount: u64) -> u64 {
+    let stake_millions = staked_amount / 1_000_000;
+    let stake_bonus = std::cmp::min(
+        stake_millions.saturating_mul(cfg.stake_bonus_per_million),
+        cfg.max_stake_bonus_bps
+    );
+    let multiplier = 10000u64.saturating_add(stake_bonus);
+    ((amount as u128).saturating_mul(multiplier as u128) / 10000) as u64
+}
 
-    #[account(mut)]
-    pub oracle_token_account: Account<'info, TokenAccount>,
+/// Spot YES probability (bps) from the market's effective pools, 50% for an empty market
+fn spot_yes_odds_bps(cfg: &ConfigParams, market: &PredictionMarket) -> u64 {
+    let total_pool = market.total_yes_amount.saturating_add(market.total_no_amount);
+    ((market.total_yes_amount as u128 * 10000)
+        .checked_div(total_pool as u128)
+        .unwrap_or(cfg.odds_base_yes as u128)) as u64
+}
 
-    pub oracle: Signer<'info>,
+/// Cash out part of an open bet at the given odds, returning (fee, payout)
+/// Cashout value = amount * (1 - fee) * odds. The bet and its side of the market shrink
+/// by the cashed-out share; the fee and odds haircut stay in the pool.
+fn cash_out_bet(
+    cfg: &ConfigParams,
+    market: &mut PredictionMarket,
+    bet: &mut Bet,
+    cashout_amount: u64,
+    odds_bps: u64,
+    fee_bps: u64,
+    now: i64
+) -> Result<(u64, u64)> {
+    require!(!bet.claimed, IdlError::AlreadyClaimed);
+    require!(cashout_amount > 0 && cashout_amount <= bet.amount, IdlError::InvalidAmount);
+    require!(now >= bet.timestamp + cfg.cashout_min_time, IdlError::ClaimTooEarly);
+    // The bet's reveal batch has unveiled by now (CASHOUT_MIN_TIME > BATCH_REVEAL_DELAY)
+    market.unveil_reveal_batch(cfg, now);
+    // No exits once betting has closed or the market is settled
+    require!(!market.resolved, IdlError::MarketResolved);
+    require!(market.status == MARKET_STATUS_ACTIVE, IdlError::MarketResolved);
+    require!(market.is_two_sided(), IdlError::WrongMarketType);
+    require!(
+        now < market.resolution_timestamp - cfg.betting_close_window,
+        IdlError::BettingClosed
+    );
+
+    let fee = (cashout_amount as u128 * fee_bps as u128 / 10000) as u64;
+    let after_fee = cashout_amount.saturating_sub(fee);
+    let payout = (after_fee as u128 * odds_bps as u128 / 10000) as u64;
+
+    let effective_out = (bet.effective_amount as u128)
+        .checked_mul(cashout_amount as u128)
+        .and_then(|v| v.checked_div(bet.amount as u128))
+        .and_then(|v| u64::try_from(v).ok())
+        .ok_or(IdlError::MathOverflow)?;
+
+    // The whole cashed-out stake leaves its side; what isn't paid out stays in the pot
+    if bet.bet_yes {
+        market.total_yes_actual = market.total_yes_actual.saturating_sub(cashout_amount);
+        market.total_yes_amount = market.total_yes_amount.saturating_sub(effective_out);
+    } else {
+        market.total_no_actual = market.total_no_actual.saturating_sub(cashout_amount);
+        market.total_no_amount = market.total_no_amount.saturating_sub(effective_out);
+    }
+    market.cashout_retained = market.cashout_retained
+        .checked_add(cashout_amount.saturating_sub(payout))
+        .ok_or(IdlError::MathOverflow)?;
 
-    pub token_program: Program<'info, Token>,
+    let early_bird_out = (bet.early_bird_bonus as u128 * cashout_amount as u128
+        / bet.amount as u128) as u64;
+
+    bet.amount = bet.amount.saturating_sub(cashout_amount);
+    bet.effective_amount = bet.effective_amount.saturating_sub(effective_out);
+    bet.early_bird_bonus = bet.early_bird_bonus.saturating_sub(early_bird_out);
+    if bet.amount == 0 {
+        bet.claimed = true;
+    }
+
+    Ok((fee, payout))
 }
 
-#[derive(Accounts)]
-#[instruction(protocol_id: String, metric_type: MetricType, target_value: u64, resolution_timestamp: i64)]
-pub struct CreateMarket<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Box<Account<'info, ProtocolState>>,
+/// Add a revealed bet to the market's actual and effective pools
+fn add_to_market_pools(market: &mut PredictionMarket, bet_yes: bool, amount: u64, effective_amount: u64) {
+    if bet_yes {
+        market.total_yes_actual = market.total_yes_actual.saturating_add(amount);
+        market.total_yes_amount = market.total_yes_amount.saturating_add(effective_amount);
+    } else {
+        market.total_no_actual = market.total_no_actual.saturating_add(amount);
+        market.total_no_amount = market.total_no_amount.saturating_add(effective_amount);
+    }
+}
 
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + PredictionMarket::INIT_SPACE,
-        seeds = [b"market", protocol_id.as_bytes(), &resolution_timestamp.to_le_bytes()],
-        bump
-    )]
-    pub market: Box<Account<'info, PredictionMarket>>,
+/// Gross payout (stake plus winnings) owed to a bet on a resolved market, 0 if it lost
+fn market_payout(bet: &Bet, market: &PredictionMarket) -> Result<u64> {
+    if market.market_type == MARKET_TYPE_CATEGORICAL {
+        let winner = market.winning_outcome.ok_or(IdlError::MarketNotResolved)?;
+        if bet.outcome_index != winner {
+            return Ok(0);
+        }
+        let index = winner as usize;
+        let total_actual = market.outcome_pools
+            .iter()
+            .try_fold(0u64, |acc, &pool| acc.checked_add(pool))
+            .ok_or(IdlError::MathOverflow)?;
+        return parimutuel_winnings(
+            bet,
+            market.outcome_pools_effective[index],
+            total_actual.saturating_sub(market.outcome_pools[index])
+        );
+    }
 
-    #[account(
-        init,
-        payer = creator,
-        seeds = [b"market_pool", market.key().as_ref()],
-        bump,
-        token::mint = idl_mint,
-        token::authority = market_pool,
-    )]
-    pub market_pool: Box<Account<'info, TokenAccount>>,
+    if market.market_type == MARKET_TYPE_SCALAR {
+        return scalar_payout(bet, market);
+    }
 
-    pub idl_mint: Box<Account<'info, Mint>>,
+    let outcome = market.outcome.ok_or(IdlError::MarketNotResolved)?;
+    if bet.bet_yes != outcome {
+        return Ok(0);
+    }
+    // Stake left behind by cashouts is shared by the winners like the losing pool
+    if outcome {
+        parimutuel_winnings(bet, market.total_yes_amount, market.total_no_actual.saturating_add(market.cashout_retained))
+    } else {
+        parimutuel_winnings(bet, market.total_no_amount, market.total_yes_actual.saturating_add(market.cashout_retained))
+    }
+}
 
-    #[account(mut)]
-    pub creator: Signer<'info>,
+/// Scalar payout: longs share pot * position, shorts share pot * (1 - position),
+/// where position is the clamped value's place in the range. One-sided markets refund stakes.
+fn scalar_payout(bet: &Bet, market: &PredictionMarket) -> Result<u64> {
+    let actual_value = market.actual_value.ok_or(IdlError::MarketNotResolved)?;
+    if market.total_yes_amount == 0 || market.total_no_amount == 0 {
+        return Ok(bet.amount);
+    }
 
-    /// CHECK: Oracle authorized to resolve
-    pub oracle: UncheckedAccount<'info>,
+    let clamped = actual_value.clamp(market.range_low, market.range_high);
+    let long_bps = (clamped - market.range_low) as u128 * 10000
+        / (market.range_high - market.range_low) as u128;
+    let pot = market.total_yes_actual as u128
+        + market.total_no_actual as u128
+        + market.cashout_retained as u128;
+
+    let (side_bps, side_pool_effective) = if bet.bet_yes {
+        (long_bps, market.total_yes_amount)
+    } else {
+        (10000 - long_bps, market.total_no_amount)
+    };
+    let side_pot = pot * side_bps / 10000;
+
+    (bet.effective_amount as u128)
+        .checked_mul(side_pot)
+        .and_then(|v| v.checked_div(side_pool_effective as u128))
+        .and_then(|v| u64::try_from(v).ok())
+        .ok_or(IdlError::MathOverflow.into())
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+/// Stake plus parimutuel share of the losing pool for a winning bet
+/// SECURITY FIX: Use effective_amount for share calculation, actual pool for funds
+fn parimutuel_winnings(bet: &Bet, winning_pool_effective: u64, losing_pool_actual: u64) -> Result<u64> {
+    // Calculate winnings share based on effective amounts (includes staker bonus)
+    let winnings_share = (bet.effective_amount as u128)
+        .checked_mul(losing_pool_actual as u128)
+        .and_then(|v| v.checked_div(winning_pool_effective as u128))
+        .and_then(|v| u64::try_from(v).ok())
+        .unwrap_or(0);
+
+    Ok(bet.amount
+        .checked_add(winnings_share)
+        .ok_or(IdlError::MathOverflow)?)
 }
 
-#[derive(Accounts)]
-#[instruction(amount: u64, bet_yes: bool, nonce: u64)]
-pub struct PlaceBet<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Box<Account<'info, ProtocolState>>,
+/// Fee breakdown for a winning claim
+/// TIER 3: Insurance fund takes 1% of total fee first, the rest is split by share
+pub struct FeeSplit {
+    pub fee: u64,
+    pub insurance: u64,
+    pub staker: u64,
+    pub creator: u64,
+    pub treasury: u64,
+    pub burn: u64,
+}
 
-    #[account(mut)]
-    pub market: Box<Account<'info, PredictionMarket>>,
+impl FeeSplit {
+    pub fn new(cfg: &ConfigParams, fee: u64) -> Self {
+        let insurance = (fee as u128 * cfg.insurance_fee_bps as u128 / 10000) as u64;
+        let distributable = fee.saturating_sub(insurance) as u128;
+
+        Self {
+            fee,
+            insurance,
+            staker: (distributable * cfg.staker_fee_share_bps as u128 / 10000) as u64,
+            creator: (distributable * cfg.creator_fee_share_bps as u128 / 10000) as u64,
+            treasury: (distributable * cfg.treasury_fee_share_bps as u128 / 10000) as u64,
+            burn: (distributable * cfg.burn_fee_share_bps as u128 / 10000) as u64,
+        }
+    }
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + Bet::INIT_SPACE,
-        seeds = [b"bet", market.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub bet: Box<Account<'info, Bet>>,
+/// Fee destinations for a claim paid out of a market pool
+pub struct FeeAccounts<'info> {
+    pub market_pool: AccountInfo<'info>,
+    pub creator: AccountInfo<'info>,
+    pub treasury: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub burn_vault: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
 
-    /// Staker account - optional, if missing user gets no bonus
-    /// (To save stack space, we don't init_if_needed here)
-    #[account(
-        seeds = [b"staker", user.key().as_ref()],
-        bump
-    )]
-    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
+/// Transfer out of a market pool (the pool PDA is its own authority)
+fn transfer_from_market_pool<'info>(
+    token_program: &AccountInfo<'info>,
+    market_pool: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let cpi_accounts = Transfer {
+        from: market_pool.clone(),
+        to: to.clone(),
+        authority: market_pool.clone(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds),
+        amount
+    )
+}
 
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + UserVolume::INIT_SPACE,
-        seeds = [b"volume", user.key().as_ref()],
-        bump
-    )]
-    pub user_volume: Box<Account<'info, UserVolume>>,
+/// Current size of an account type that has gained fields since it was first deployed
+/// New fields are only ever appended, so an older account grows in place by realloc.
+fn migrated_space(discriminator: &[u8; 8]) -> Option<usize> {
+    let space = match *discriminator {
+        // Seasons: running season window, per-season predictor stats and the leaderboard
+        ProtocolState::DISCRIMINATOR => ProtocolState::INIT_SPACE,
+        PredictorStats::DISCRIMINATOR => PredictorStats::INIT_SPACE,
+        Season::DISCRIMINATOR => Season::INIT_SPACE,
+        LeaderboardEntry::DISCRIMINATOR => LeaderboardEntry::INIT_SPACE,
+        // Guild bets: no guild could bet before, so nothing is at risk
+        Guild::DISCRIMINATOR => Guild::INIT_SPACE,
+        // Limit orders: an order placed before order ids reads id 0, which doesn't match its
+        // PDA, so keepers can't fill or expire it; the owner can still cancel it for a refund
+        LimitOrder::DISCRIMINATOR => LimitOrder::INIT_SPACE,
+        // Markets: appended fields read as a plain binary market with no parent, no pending
+        // reveal batch, no creation stake to refund and no cashout haircut retained
+        PredictionMarket::DISCRIMINATOR => PredictionMarket::INIT_SPACE,
+        // Stop losses: one set before entry odds were recorded reads entry odds 0 and never triggers
+        StopLoss::DISCRIMINATOR => StopLoss::INIT_SPACE,
+        // Bets: outcome_index only matters on categorical markets, which older bets never joined
+        Bet::DISCRIMINATOR => Bet::INIT_SPACE,
+        // Oracle commitments: older reveals resolved their market on the spot, so the recorded
+        // votes of a migrated commitment are never tallied
+        ResolutionCommitment::DISCRIMINATOR => ResolutionCommitment::INIT_SPACE,
+        // Bet commitments: made before bonds, so no bond; see migrate_legacy_fields for the payer
+        BetCommitment::DISCRIMINATOR => BetCommitment::INIT_SPACE,
+        // Creator stats: no cooldown runs from before it was introduced
+        CreatorStats::DISCRIMINATOR => CreatorStats::INIT_SPACE,
+        // Loot boxes: see migrate_legacy_fields
+        LootboxReward::DISCRIMINATOR => LootboxReward::INIT_SPACE,
+        // Vote snapshots: with no recorded change, a lock or badge counts at its current terms
+        // from lock_start / issued_at on; an earlier extension or upgrade wasn't recorded
+        VePosition::DISCRIMINATOR => VePosition::INIT_SPACE,
+        VolumeBadge::DISCRIMINATOR => VolumeBadge::INIT_SPACE,
+        _ => return None,
+    };
+    Some(8 + space)
+}
 
-    #[account(
-        mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
-        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
-    )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+/// Set appended fields whose zero value would be wrong for an account written by an earlier version
+fn migrate_legacy_fields(account: &AccountInfo, discriminator: &[u8; 8]) -> Result<()> {
+    match *discriminator {
+        // The owner made the commitment, without a bond; lets them close it once it expires
+        BetCommitment::DISCRIMINATOR => {
+            rewrite_account(account, |c: &mut BetCommitment| c.bond_payer = c.owner)
+        }
+        // Loot boxes used to roll at purchase: the reward is already set
+        LootboxReward::DISCRIMINATOR => {
+            rewrite_account(account, |r: &mut LootboxReward| r.opened = true)
+        }
+        _ => Ok(()),
+    }
+}
 
-    #[account(
-        mut,
-        seeds = [b"market_pool", market.key().as_ref()],
-        bump
-    )]
-    pub market_pool: Box<Account<'info, TokenAccount>>,
+/// Deserialize a program account, apply `update` and write it back in place
+fn rewrite_account<T: AccountSerialize + AccountDeserialize>(
+    account: &AccountInfo,
+    update: impl FnOnce(&mut T)
+) -> Result<()> {
+    let mut value = T::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    update(&mut value);
+    let mut data = account.try_borrow_mut_data()?;
+    value.try_serialize(&mut &mut data[..])
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+/// Transfer out of the protocol vault, signed by the state PDA
+fn transfer_from_vault<'info>(
+    token_program: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    state: &AccountInfo<'info>,
+    state_bump: u8,
+    amount: u64
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let seeds = &[b"state".as_ref(), &[state_bump]];
+    let cpi_accounts = Transfer {
+        from: vault.clone(),
+        to: to.clone(),
+        authority: state.clone(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program.clone(), cpi_accounts, &[&seeds[..]]),
+        amount
+    )
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+/// MARKET_SPAM FIX: Enforce the per-creator cooldown and lock the refundable creation stake in the vault
+fn lock_creation_stake<'info>(
+    cfg: &ConfigParams,
+    stats: &mut CreatorStats,
+    token_program: &AccountInfo<'info>,
+    from: &Account<'info, TokenAccount>,
+    vault: &AccountInfo<'info>,
+    creator: &AccountInfo<'info>,
+    now: i64
+) -> Result<()> {
+    stats.record_market_created(cfg, now)?;
+    require!(from.amount >= cfg.market_creation_stake, IdlError::MarketCreationStakeRequired);
+
+    let cpi_accounts = Transfer {
+        from: from.to_account_info(),
+        to: vault.clone(),
+        authority: creator.clone(),
+    };
+    token::transfer(
+        CpiContext::new(token_program.clone(), cpi_accounts),
+        cfg.market_creation_stake
+    )
 }
 
-#[derive(Accounts)]
-pub struct ResolveMarket<'info> {
-    #[account(mut)]
-    pub market: Account<'info, PredictionMarket>,
+/// PREDICTION MINING: Credit a revealed bet's volume to the market creator, returning the fee owed
+fn accrue_creator_volume_fee(cfg: &ConfigParams, stats: &mut CreatorStats, amount: u64) -> u64 {
+    let fee = (amount as u128 * cfg.creator_volume_fee_bps as u128 / 10000) as u64;
+    stats.total_volume = stats.total_volume.saturating_add(amount);
+    stats.pending_fees = stats.pending_fees.saturating_add(fee);
+    fee
+}
 
-    // AUDIT FIX: Require oracle bond to be present
-    #[account(
-        seeds = [b"oracle_bond", oracle.key().as_ref()],
-        bump = oracle_bond.bump,
-        constraint = oracle_bond.oracle == oracle.key() @ IdlError::Unauthorized
-    )]
-    pub oracle_bond: Account<'info, OracleBond>,
+/// COMMITMENT_GRIEF FIX: Take the commitment bond from the committer into the vault
+fn post_commitment_bond<'info>(
+    cfg: &ConfigParams,
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    balance: u64
+) -> Result<()> {
+    require!(balance >= cfg.commitment_bond_amount, IdlError::CommitmentBondRequired);
+    let cpi_accounts = Transfer {
+        from: from.clone(),
+        to: vault.clone(),
+        authority: authority.clone(),
+    };
+    token::transfer(
+        CpiContext::new(token_program.clone(), cpi_accounts),
+        cfg.commitment_bond_amount
+    )
+}
 
-    pub oracle: Signer<'info>,
+/// Pay out a claim's fee split and record it in protocol state
+fn distribute_fees<'info>(
+    fees: &FeeSplit,
+    accounts: &FeeAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    state: &mut ProtocolState
+) -> Result<()> {
+    let pool = &accounts.market_pool;
+    let program = &accounts.token_program;
+
+    transfer_from_market_pool(program, pool, &accounts.creator, signer_seeds, fees.creator)?;
+    transfer_from_market_pool(program, pool, &accounts.treasury, signer_seeds, fees.treasury)?;
+    // Staker rewards and insurance both sit in the vault (insurance tracked separately in state)
+    transfer_from_market_pool(program, pool, &accounts.vault, signer_seeds, fees.staker)?;
+    transfer_from_market_pool(program, pool, &accounts.vault, signer_seeds, fees.insurance)?;
+    // RICK FIX: Send "burn" to burn_vault instead of actual burn
+    // (Actual SPL burn requires mint authority which market_pool doesn't have)
+    transfer_from_market_pool(program, pool, &accounts.burn_vault, signer_seeds, fees.burn)?;
+
+    // SECURITY FIX: Update reward checkpoint before adding to pool
+    update_reward_per_token(state, fees.staker);
+
+    state.reward_pool = state.reward_pool
+        .checked_add(fees.staker)
+        .ok_or(IdlError::MathOverflow)?;
+    state.total_burned = state.total_burned
+        .checked_add(fees.burn)
+        .ok_or(IdlError::MathOverflow)?;
+    state.total_fees_collected = state.total_fees_collected
+        .checked_add(fees.fee)
+        .ok_or(IdlError::MathOverflow)?;
+    state.insurance_fund = state.insurance_fund
+        .checked_add(fees.insurance)
+        .ok_or(IdlError::MathOverflow)?;
+    Ok(())
+}
+
+/// An oracle bond locked to `market` frees up once the market is settled, no dispute is open,
+/// and (if it resolved) its dispute window has closed
+fn require_oracle_bond_unlocked(cfg: &ConfigParams, market: &PredictionMarket, now: i64) -> Result<()> {
+    require!(market.status != MARKET_STATUS_ACTIVE, IdlError::MarketNotResolved);
+    require!(market.status != MARKET_STATUS_DISPUTED, IdlError::ResolutionDisputed);
+    if let Some(resolved_at) = market.resolved_at {
+        require!(dispute_window_closed(cfg, resolved_at, now), IdlError::DisputeWindowOpen);
+    }
+    Ok(())
+}
+
+/// Whether a resolution at `resolved_at` can no longer be disputed
+/// Settlement opens at the same instant disputes close, so the two never overlap.
+fn dispute_window_closed(cfg: &ConfigParams, resolved_at: i64, now: i64) -> bool {
+    now >= resolved_at + cfg.oracle_dispute_window
+}
+
+/// Pay out the treasury value of `shares` guild shares, signed by the guild PDA
+fn pay_guild_share<'info>(
+    guild: &Account<'info, Guild>,
+    guild_treasury: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    shares: u64
+) -> Result<u64> {
+    let payout = guild.share_value(shares, guild_treasury.amount)?;
+
+    let guild_seeds = &[b"guild".as_ref(), guild.leader.as_ref(), &[guild.bump]];
+    let signer_seeds = &[&guild_seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: guild_treasury.to_account_info(),
+        to,
+        authority: guild.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds),
+        payout
+    )?;
+
+    Ok(payout)
+}
+
+fn emit_market_created(market: &Account<PredictionMarket>) {
+    emit!(MarketCreated {
+        version: EVENT_VERSION,
+        market: market.key(),
+        creator: market.creator,
+        market_type: market.market_type,
+        protocol_id: market.protocol_id.clone(),
+        resolution_timestamp: market.resolution_timestamp,
+        creation_stake: market.creation_stake,
+        parent_market: market.parent_market,
+    });
+}
+
+fn emit_market_resolved(market: &Account<PredictionMarket>) {
+    emit!(MarketResolved {
+        version: EVENT_VERSION,
+        market: market.key(),
+        outcome: market.outcome,
+        winning_outcome: market.winning_outcome,
+        actual_value: market.actual_value,
+        resolved_at: market.resolved_at.unwrap_or_default(),
+    });
+}
+
+/// RICK FIX: Get total voting power for a user (veIDL from lock + badge)
+/// This accounts for veIDL decay over time
+pub fn get_voting_power(
+    ve_position: Option<&VePosition>,
+    badge: Option<&VolumeBadge>,
+    current_time: i64
+) -> u64 {
+    let ve_power = ve_position
+        .map(|vp| vp.current_ve_amount(current_time))
+        .unwrap_or(0);
+
+    let badge_power = badge
+        .map(|b| b.ve_amount)
+        .unwrap_or(0);
+
+    ve_power.saturating_add(badge_power)
+}
+
+/// VE_DECAY_ARBITRAGE FIX: Voting power at a snapshot, ignoring locks and badges created after it
+/// and lock extensions or badge upgrades made after it
+pub fn snapshot_voting_power(
+    ve_position: Option<&VePosition>,
+    badge: Option<&VolumeBadge>,
+    snapshot: i64
+) -> u64 {
+    let ve_power = ve_position
+        .map(|vp| vp.snapshot_ve_amount(snapshot))
+        .unwrap_or(0);
+
+    let badge_power = badge
+        .map(|b| b.snapshot_ve_amount(snapshot))
+        .unwrap_or(0);
+
+    ve_power.saturating_add(badge_power)
 }
 
+// ==================== ACCOUNTS ====================
+
+/// Step 1: Create protocol state only
 #[derive(Accounts)]
-pub struct CancelMarket<'info> {
+pub struct CreateState<'info> {
     #[account(
+        init,
+        payer = authority,
+        space = 8 + ProtocolState::INIT_SPACE,
         seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+        bump
     )]
-    pub state: Account<'info, ProtocolState>,
+    pub state: Box<Account<'info, ProtocolState>>,
 
-    #[account(mut)]
-    pub market: Account<'info, PredictionMarket>,
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Treasury account
+    pub treasury: UncheckedAccount<'info>,
 
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
+/// Step 2: Initialize vault
 #[derive(Accounts)]
-pub struct ClaimRefund<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Box<Account<'info, ProtocolState>>,
-
-    #[account(
-        seeds = [b"market", market.protocol_id.as_bytes(), &market.resolution_timestamp.to_le_bytes()],
-        bump = market.bump
-    )]
-    pub market: Box<Account<'info, PredictionMarket>>,
-
+pub struct InitVault<'info> {
     #[account(
         mut,
-        seeds = [b"bet", market.key().as_ref(), bet.owner.as_ref(), &bet.nonce.to_le_bytes()],
-        bump = bet.bump,
-        constraint = bet.owner == user.key() @ IdlError::Unauthorized
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
     )]
-    pub bet: Box<Account<'info, Bet>>,
+    pub state: Box<Account<'info, ProtocolState>>,
 
     #[account(
-        mut,
-        seeds = [b"market_pool", market.key().as_ref()],
-        bump
+        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
     )]
-    pub market_pool: Box<Account<'info, TokenAccount>>,
+    pub idl_mint: Box<Account<'info, Mint>>,
 
     #[account(
-        mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+        init,
+        payer = authority,
+        seeds = [b"vault"],
+        bump,
+        token::mint = idl_mint,
+        token::authority = state,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub vault: Box<Account<'info, TokenAccount>>,
 
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
+/// Step 3: Initialize protocol config
 #[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
-    #[account(mut, seeds = [b"state"], bump = state.bump)]
+pub struct InitConfig<'info> {
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
     pub state: Box<Account<'info, ProtocolState>>,
 
     #[account(
-        seeds = [b"market", market.protocol_id.as_bytes(), &market.resolution_timestamp.to_le_bytes()],
-        bump = market.bump
+        init,
+        payer = authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
     )]
-    pub market: Box<Account<'info, PredictionMarket>>,
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Step 4: Initialize burn vault and activate
+#[derive(Accounts)]
+pub struct InitBurnVault<'info> {
     #[account(
         mut,
-        seeds = [b"bet", market.key().as_ref(), bet.owner.as_ref(), &bet.nonce.to_le_bytes()],
-        bump = bet.bump,
-        constraint = bet.owner == user.key() @ IdlError::Unauthorized
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
     )]
-    pub bet: Box<Account<'info, Bet>>,
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    // Protocol can't activate before its parameters exist
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
     #[account(
-        mut,
-        seeds = [b"market_pool", market.key().as_ref()],
-        bump
+        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
     )]
-    pub market_pool: Box<Account<'info, TokenAccount>>,
+    pub idl_mint: Box<Account<'info, Mint>>,
 
     #[account(
-        mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+        init,
+        payer = authority,
+        seeds = [b"burn_vault"],
+        bump,
+        token::mint = idl_mint,
+        token::authority = state,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub burn_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
-    /// SECURITY FIX: Validate creator token account belongs to market creator
     #[account(
-        mut,
-        constraint = creator_token_account.owner == market.creator @ IdlError::InvalidCreatorAccount,
-        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+        init_if_needed,
+        payer = user,
+        space = 8 + StakerAccount::INIT_SPACE,
+        seeds = [b"staker", user.key().as_ref()],
+        bump
     )]
-    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+    pub staker_account: Account<'info, StakerAccount>,
 
-    /// SECURITY FIX: Validate treasury token account matches state treasury
     #[account(
         mut,
-        constraint = treasury_token_account.owner == state.treasury @ IdlError::InvalidTreasuryAccount,
-        constraint = treasury_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
+        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
     )]
-    pub treasury_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_token_account: Account<'info, TokenAccount>,
 
     #[account(
         mut,
         seeds = [b"vault"],
         bump = state.vault_bump
     )]
-    pub vault: Box<Account<'info, TokenAccount>>,
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitStake<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
     #[account(
-        mut,
-        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
+        init_if_needed,
+        payer = user,
+        space = 8 + StakeCommitment::INIT_SPACE,
+        seeds = [b"stake_commit", user.key().as_ref()],
+        bump
     )]
-    pub idl_mint: Box<Account<'info, Mint>>,
+    pub stake_commitment: Account<'info, StakeCommitment>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealStake<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
-    /// RICK FIX: Burn vault to hold "burned" tokens (since we can't actually burn without mint authority)
     #[account(
         mut,
-        seeds = [b"burn_vault"],
+        close = user,
+        seeds = [b"stake_commit", user.key().as_ref()],
+        bump = stake_commitment.bump,
+        constraint = stake_commitment.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub stake_commitment: Account<'info, StakeCommitment>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakerAccount::INIT_SPACE,
+        seeds = [b"staker", user.key().as_ref()],
         bump
     )]
-    pub burn_vault: Box<Account<'info, TokenAccount>>,
+    pub staker_account: Account<'info, StakerAccount>,
 
-    /// CONVICTION FIX: Optional conviction bet for bonus winnings
-    /// If provided, applies bonus_bps to winnings (if lock has expired)
     #[account(
         mut,
-        seeds = [b"conviction", bet.key().as_ref()],
-        bump = conviction_bet.bump,
-        constraint = conviction_bet.owner == user.key() @ IdlError::Unauthorized,
-        constraint = conviction_bet.bet == bet.key() @ IdlError::Unauthorized
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
+        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
     )]
-    pub conviction_bet: Option<Account<'info, ConvictionBet>>,
+    pub user_token_account: Account<'info, TokenAccount>,
 
-    /// REFERRAL FIX: Optional referral account to credit fees
-    /// If user has a referrer, 5% of fees goes to referrer's pending_fees
     #[account(
         mut,
-        seeds = [b"referral", user.key().as_ref()],
-        bump = user_referral.bump
+        seeds = [b"vault"],
+        bump = state.vault_bump
     )]
-    pub user_referral: Option<Account<'info, ReferralAccount>>,
+    pub vault: Account<'info, TokenAccount>,
 
     #[account(mut)]
     pub user: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimStakingRewards<'info> {
+pub struct JoinTvlRaise<'info> {
     #[account(mut, seeds = [b"state"], bump = state.bump)]
     pub state: Account<'info, ProtocolState>,
 
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
     #[account(
-        mut,  // SECURITY FIX: Now mutable to track claimed amounts
-        seeds = [b"staker", user.key().as_ref()],
-        bump = staker_account.bump,
-        constraint = staker_account.owner == user.key() @ IdlError::Unauthorized
+        init,
+        payer = user,
+        space = 8 + TvlRaiseEntry::INIT_SPACE,
+        seeds = [b"tvl_raise_entry".as_ref(), &state.tvl_raise_id.to_le_bytes(), user.key().as_ref()],
+        bump
     )]
-    pub staker_account: Account<'info, StakerAccount>,
+    pub entry: Account<'info, TvlRaiseEntry>,
 
     #[account(
         mut,
-        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
+        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
     )]
     pub user_token_account: Account<'info, TokenAccount>,
 
@@ -2865,87 +5786,84 @@ pub struct ClaimStakingRewards<'info> {
     pub user: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct IssueBadge<'info> {
+pub struct SettleTvlRaiseEntry<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
     #[account(
         mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+        close = owner,
+        seeds = [b"tvl_raise_entry".as_ref(), &state.tvl_raise_id.to_le_bytes(), owner.key().as_ref()],
+        bump = entry.bump
     )]
-    pub state: Account<'info, ProtocolState>,
+    pub entry: Account<'info, TvlRaiseEntry>,
 
     #[account(
         init_if_needed,
-        payer = authority,
-        space = 8 + VolumeBadge::INIT_SPACE,
-        seeds = [b"badge", recipient.key().as_ref()],
+        payer = payer,
+        space = 8 + StakerAccount::INIT_SPACE,
+        seeds = [b"staker", owner.key().as_ref()],
         bump
     )]
-    pub badge: Account<'info, VolumeBadge>,
+    pub staker_account: Account<'info, StakerAccount>,
 
     #[account(
-        seeds = [b"volume", recipient.key().as_ref()],
-        bump = user_volume.bump
+        mut,
+        constraint = owner_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
+        constraint = owner_token_account.owner == owner.key() @ IdlError::Unauthorized
     )]
-    pub user_volume: Account<'info, UserVolume>,
+    pub owner_token_account: Account<'info, TokenAccount>,
 
-    /// CHECK: Badge recipient
-    pub recipient: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
 
+    /// CHECK: Entry owner, receives the refund and the entry's rent
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RevokeBadge<'info> {
-    #[account(
-        mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
-    )]
+pub struct Unstake<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
     pub state: Account<'info, ProtocolState>,
 
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
     #[account(
         mut,
-        close = authority,
-        seeds = [b"badge", badge.owner.as_ref()],
-        bump = badge.bump
+        seeds = [b"staker", user.key().as_ref()],
+        bump = staker_account.bump,
+        constraint = staker_account.owner == user.key() @ IdlError::Unauthorized
     )]
-    pub badge: Account<'info, VolumeBadge>,
-
-    #[account(mut)]
-    pub authority: Signer<'info>,
-}
+    pub staker_account: Account<'info, StakerAccount>,
 
-#[derive(Accounts)]
-pub struct AdminOnly<'info> {
     #[account(
-        mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+        seeds = [b"ve_position", user.key().as_ref()],
+        bump
     )]
-    pub state: Account<'info, ProtocolState>,
-
-    pub authority: Signer<'info>,
-}
+    pub ve_position: Option<Account<'info, VePosition>>,
 
-// TIER 3: Withdraw from insurance fund
-#[derive(Accounts)]
-pub struct WithdrawInsurance<'info> {
     #[account(
         mut,
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
+        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
     )]
-    pub state: Account<'info, ProtocolState>,
+    pub user_token_account: Account<'info, TokenAccount>,
 
     #[account(
         mut,
@@ -2954,49 +5872,35 @@ pub struct WithdrawInsurance<'info> {
     )]
     pub vault: Account<'info, TokenAccount>,
 
-    /// Recipient token account for insurance withdrawal
     #[account(mut)]
-    pub recipient: Account<'info, TokenAccount>,
-
-    pub authority: Signer<'info>,
+    pub user: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
 }
 
-// ═══════════════════════════════════════════════════════════════════════════════
-// PUMP MECHANICS ACCOUNTS
-// ═══════════════════════════════════════════════════════════════════════════════
-
 #[derive(Accounts)]
-pub struct RegisterReferral<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + ReferralAccount::INIT_SPACE,
-        seeds = [b"referral", user.key().as_ref()],
-        bump
-    )]
-    pub referral_account: Account<'info, ReferralAccount>,
-
-    /// CHECK: Referrer just needs to be a valid pubkey
-    pub referrer: AccountInfo<'info>,
+pub struct LockForVe<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
-    pub system_program: Program<'info, System>,
-}
+    #[account(
+        seeds = [b"staker", user.key().as_ref()],
+        bump = staker_account.bump,
+        constraint = staker_account.owner == user.key()
+    )]
+    pub staker_account: Account<'info, StakerAccount>,
 
-#[derive(Accounts)]
-pub struct InitPredictorStats<'info> {
     #[account(
         init,
         payer = user,
-        space = 8 + PredictorStats::INIT_SPACE,
-        seeds = [b"predictor_stats", user.key().as_ref()],
+        space = 8 + VePosition::INIT_SPACE,
+        seeds = [b"ve_position", user.key().as_ref()],
         bump
     )]
-    pub predictor_stats: Account<'info, PredictorStats>,
+    pub ve_position: Account<'info, VePosition>,
 
     #[account(mut)]
     pub user: Signer<'info>,
@@ -3005,985 +5909,5098 @@ pub struct InitPredictorStats<'info> {
 }
 
 #[derive(Accounts)]
-pub struct SetAutoCompound<'info> {
+pub struct UnlockVe<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
     #[account(
         mut,
-        seeds = [b"predictor_stats", user.key().as_ref()],
-        bump = predictor_stats.bump,
-        constraint = predictor_stats.owner == user.key() @ IdlError::Unauthorized
+        close = user,
+        seeds = [b"ve_position", user.key().as_ref()],
+        bump = ve_position.bump,
+        constraint = ve_position.owner == user.key()
     )]
-    pub predictor_stats: Account<'info, PredictorStats>,
+    pub ve_position: Account<'info, VePosition>,
 
+    #[account(mut)]
     pub user: Signer<'info>,
 }
 
+/// RICK FIX: ExtendLock accounts
 #[derive(Accounts)]
-pub struct UpdateVipTier<'info> {
-    #[account(
-        seeds = [b"staker", user.key().as_ref()],
-        bump = staker_account.bump
-    )]
-    pub staker_account: Account<'info, StakerAccount>,
+pub struct ExtendLock<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 
     #[account(
         mut,
-        seeds = [b"predictor_stats", user.key().as_ref()],
-        bump = predictor_stats.bump,
-        constraint = predictor_stats.owner == user.key() @ IdlError::Unauthorized
+        seeds = [b"ve_position", user.key().as_ref()],
+        bump = ve_position.bump,
+        constraint = ve_position.owner == user.key() @ IdlError::Unauthorized
     )]
-    pub predictor_stats: Account<'info, PredictorStats>,
+    pub ve_position: Account<'info, VePosition>,
 
+    #[account(mut)]
     pub user: Signer<'info>,
 }
 
+// ==================== 10/10 ACCOUNT STRUCTS ====================
+
 #[derive(Accounts)]
-#[instruction(season_number: u64)]
-pub struct CreateSeason<'info> {
-    #[account(
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
-    )]
+pub struct CommitBet<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
     pub state: Account<'info, ProtocolState>,
 
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub market: Account<'info, PredictionMarket>,
+
     #[account(
         init,
-        payer = authority,
-        space = 8 + Season::INIT_SPACE,
-        seeds = [b"season", season_number.to_le_bytes().as_ref()],
+        payer = user,
+        space = 8 + BetCommitment::INIT_SPACE,
+        seeds = [b"bet_commit", market.key().as_ref(), user.key().as_ref()],
         bump
     )]
-    pub season: Account<'info, Season>,
+    pub bet_commitment: Account<'info, BetCommitment>,
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub authority_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
 
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub user: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct EndSeason<'info> {
+pub struct ReclaimCommitmentBond<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub market: Account<'info, PredictionMarket>,
+
     #[account(
-        seeds = [b"state"],
-        bump = state.bump,
-        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+        mut,
+        close = payer,
+        seeds = [b"bet_commit", market.key().as_ref(), bet_commitment.owner.as_ref()],
+        bump = bet_commitment.bump,
+        constraint = bet_commitment.bond_payer == payer.key() @ IdlError::Unauthorized
     )]
-    pub state: Account<'info, ProtocolState>,
+    pub bet_commitment: Account<'info, BetCommitment>,
+
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ IdlError::Unauthorized,
+        constraint = payer_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
 
     #[account(mut)]
-    pub season: Account<'info, Season>,
+    pub payer: Signer<'info>,
 
-    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct InitCreatorStats<'info> {
+#[instruction(amount: u64, bet_yes: bool, nonce: u64, salt: [u8; 32])]
+pub struct RevealBet<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"bet_commit", market.key().as_ref(), user.key().as_ref()],
+        bump = bet_commitment.bump,
+        constraint = bet_commitment.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub bet_commitment: Box<Account<'info, BetCommitment>>,
+
     #[account(
         init,
-        payer = creator,
-        space = 8 + CreatorStats::INIT_SPACE,
-        seeds = [b"creator_stats", creator.key().as_ref()],
+        payer = user,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", market.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
         bump
     )]
-    pub creator_stats: Account<'info, CreatorStats>,
+    pub bet: Box<Account<'info, Bet>>,
 
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + DynamicOdds::INIT_SPACE,
+        seeds = [b"dynamic_odds", market.key().as_ref()],
+        bump
+    )]
+    pub dynamic_odds: Box<Account<'info, DynamicOdds>>,
 
-    pub system_program: Program<'info, System>,
-}
+    #[account(
+        seeds = [b"staker", user.key().as_ref()],
+        bump
+    )]
+    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
 
-#[derive(Accounts)]
-pub struct ClaimCreatorFees<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+    // AUDIT FIX: Add user_volume to track volume for badges
+    // SELF-REVIEW FIX: Use "volume" seed to match IssueBadge (was "user_volume")
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserVolume::INIT_SPACE,
+        seeds = [b"volume", user.key().as_ref()],
+        bump
+    )]
+    pub user_volume: Box<Account<'info, UserVolume>>,
 
     #[account(
         mut,
-        seeds = [b"creator_stats", creator.key().as_ref()],
-        bump = creator_stats.bump,
-        constraint = creator_stats.creator == creator.key() @ IdlError::Unauthorized
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
     )]
-    pub creator_stats: Account<'info, CreatorStats>,
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut)]
-    pub creator_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
 
-    pub creator: Signer<'info>,
+    /// PREDICTION MINING: Market creator accrues the volume fee
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    /// Optional lootbox stake boost
+    #[account(constraint = lootbox_reward.owner == user.key() @ IdlError::Unauthorized)]
+    pub lootbox_reward: Option<Box<Account<'info, LootboxReward>>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct PlaceConvictionBet<'info> {
+#[instruction(amount: u64, outcome_index: u8, nonce: u64, salt: [u8; 32])]
+pub struct RevealCategoricalBet<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
     #[account(
-        seeds = [b"bet", bet.market.as_ref(), user.key().as_ref(), &bet.nonce.to_le_bytes()],
-        bump = bet.bump
+        mut,
+        close = user,
+        seeds = [b"bet_commit", market.key().as_ref(), user.key().as_ref()],
+        bump = bet_commitment.bump,
+        constraint = bet_commitment.owner == user.key() @ IdlError::Unauthorized
     )]
-    pub bet: Account<'info, Bet>,
+    pub bet_commitment: Box<Account<'info, BetCommitment>>,
 
     #[account(
         init,
         payer = user,
-        space = 8 + ConvictionBet::INIT_SPACE,
-        seeds = [b"conviction", bet.key().as_ref()],
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", market.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
         bump
     )]
-    pub conviction_bet: Account<'info, ConvictionBet>,
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        seeds = [b"staker", user.key().as_ref()],
+        bump
+    )]
+    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserVolume::INIT_SPACE,
+        seeds = [b"volume", user.key().as_ref()],
+        bump
+    )]
+    pub user_volume: Box<Account<'info, UserVolume>>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// PREDICTION MINING: Market creator accrues the volume fee
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    /// Optional lootbox stake boost
+    #[account(constraint = lootbox_reward.owner == user.key() @ IdlError::Unauthorized)]
+    pub lootbox_reward: Option<Box<Account<'info, LootboxReward>>>,
 
     #[account(mut)]
     pub user: Signer<'info>,
 
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimReferralFees<'info> {
+pub struct DepositOracleBond<'info> {
     #[account(seeds = [b"state"], bump = state.bump)]
     pub state: Account<'info, ProtocolState>,
 
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
     #[account(
-        mut,
-        seeds = [b"referral", referral_account.user.as_ref()],
-        bump = referral_account.bump,
-        constraint = referral_account.referrer == referrer.key() @ IdlError::Unauthorized
+        init,
+        payer = oracle,
+        space = 8 + OracleBond::INIT_SPACE,
+        seeds = [b"oracle_bond", oracle.key().as_ref()],
+        bump
     )]
-    pub referral_account: Account<'info, ReferralAccount>,
-
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+    pub oracle_bond: Account<'info, OracleBond>,
+
+    #[account(
+        mut,
+        constraint = oracle_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub oracle_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
 
     #[account(mut)]
-    pub referrer_token_account: Account<'info, TokenAccount>,
+    pub oracle: Signer<'info>,
 
-    pub referrer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitResolution<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    // SELF-REVIEW FIX: Make mutable to track active_resolution
+    #[account(
+        mut,
+        seeds = [b"oracle_bond", oracle.key().as_ref()],
+        bump = oracle_bond.bump,
+        constraint = oracle_bond.oracle == oracle.key() @ IdlError::Unauthorized
+    )]
+    pub oracle_bond: Account<'info, OracleBond>,
+
+    #[account(
+        mut,
+        seeds = [b"res_commit", market.key().as_ref(), oracle.key().as_ref()],
+        bump = resolution_commitment.bump
+    )]
+    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterMarketOracle<'info> {
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        seeds = [b"oracle_bond", oracle_bond.oracle.as_ref()],
+        bump = oracle_bond.bump
+    )]
+    pub oracle_bond: Account<'info, OracleBond>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ResolutionCommitment::INIT_SPACE,
+        seeds = [b"res_commit", market.key().as_ref(), oracle_bond.oracle.as_ref()],
+        bump
+    )]
+    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeResolution<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+}
+
+#[derive(Accounts)]
+pub struct RevealResolution<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        mut,
+        seeds = [b"res_commit", market.key().as_ref(), oracle.key().as_ref()],
+        bump = resolution_commitment.bump,
+        constraint = resolution_commitment.oracle == oracle.key() @ IdlError::Unauthorized
+    )]
+    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeResolution<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        init,
+        payer = disputer,
+        space = 8 + Dispute::INIT_SPACE,
+        seeds = [b"dispute", market.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        mut,
+        constraint = disputer_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub disputer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub disputer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteOnDispute<'info> {
+    #[account(constraint = market.key() == dispute.market @ IdlError::InvalidMarket)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        mut,
+        seeds = [b"dispute", market.key().as_ref()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + DisputeVote::INIT_SPACE,
+        seeds = [b"dispute_vote", dispute.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub dispute_vote: Account<'info, DisputeVote>,
+
+    #[account(
+        seeds = [b"ve_position", voter.key().as_ref()],
+        bump = ve_position.bump
+    )]
+    pub ve_position: Option<Box<Account<'info, VePosition>>>,
+
+    #[account(
+        seeds = [b"badge", voter.key().as_ref()],
+        bump = badge.bump
+    )]
+    pub badge: Option<Box<Account<'info, VolumeBadge>>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeDispute<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        seeds = [b"dispute", market.key().as_ref()],
+        bump = dispute.bump
+    )]
+    pub dispute: Box<Account<'info, Dispute>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = disputer_token_account.owner == dispute.disputer @ IdlError::Unauthorized,
+        constraint = disputer_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub disputer_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// AUDIT FIX: Allow oracle to withdraw bond after successful resolution
+#[derive(Accounts)]
+pub struct WithdrawOracleBond<'info> {
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    // A locked bond can only be withdrawn against the market it is locked to
+    #[account(
+        mut,
+        seeds = [b"oracle_bond", oracle.key().as_ref()],
+        bump = oracle_bond.bump,
+        constraint = oracle_bond.oracle == oracle.key() @ IdlError::Unauthorized,
+        constraint = oracle_bond.active_resolution.is_none()
+            || oracle_bond.active_resolution == Some(market.key()) @ IdlError::OracleHasPendingResolution
+    )]
+    pub oracle_bond: Account<'info, OracleBond>,
+
+    #[account(
+        seeds = [b"res_commit", market.key().as_ref(), oracle.key().as_ref()],
+        bump = resolution_commitment.bump,
+        constraint = resolution_commitment.oracle == oracle.key() @ IdlError::Unauthorized
+    )]
+    pub resolution_commitment: Account<'info, ResolutionCommitment>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(mut)]
+    pub oracle_token_account: Account<'info, TokenAccount>,
+
+    pub oracle: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseOracleBond<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"oracle_bond", oracle_bond.oracle.as_ref()],
+        bump = oracle_bond.bump,
+        constraint = oracle_bond.active_resolution == Some(market.key()) @ IdlError::OracleHasPendingResolution
+    )]
+    pub oracle_bond: Account<'info, OracleBond>,
+
+    pub market: Account<'info, PredictionMarket>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_id: String, metric_type: MetricType, target_value: u64, resolution_timestamp: i64)]
+pub struct CreateMarket<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PredictionMarket::INIT_SPACE,
+        seeds = [b"market", protocol_id.as_bytes(), &resolution_timestamp.to_le_bytes()],
+        bump
+    )]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump,
+        token::mint = idl_mint,
+        token::authority = market_pool,
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// MARKET_SPAM FIX: Cooldown is tracked per creator
+    #[account(
+        mut,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// CHECK: Oracle authorized to resolve
+    pub oracle: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_id: String, metric_type: MetricType, outcome_count: u8, resolution_timestamp: i64)]
+pub struct CreateCategoricalMarket<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PredictionMarket::INIT_SPACE,
+        seeds = [b"categorical_market", protocol_id.as_bytes(), &resolution_timestamp.to_le_bytes()],
+        bump
+    )]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump,
+        token::mint = idl_mint,
+        token::authority = market_pool,
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint)]
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// MARKET_SPAM FIX: Cooldown is tracked per creator
+    #[account(
+        mut,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// CHECK: Oracle authorized to resolve
+    pub oracle: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_id: String, metric_type: MetricType, range_low: u64, range_high: u64, resolution_timestamp: i64)]
+pub struct CreateScalarMarket<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PredictionMarket::INIT_SPACE,
+        seeds = [b"scalar_market", protocol_id.as_bytes(), &resolution_timestamp.to_le_bytes()],
+        bump
+    )]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump,
+        token::mint = idl_mint,
+        token::authority = market_pool,
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint)]
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// MARKET_SPAM FIX: Cooldown is tracked per creator
+    #[account(
+        mut,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// CHECK: Oracle authorized to resolve
+    pub oracle: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_a: String, protocol_b: String, metric_type: MetricType, resolution_timestamp: i64)]
+pub struct CreateComparisonMarket<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PredictionMarket::INIT_SPACE,
+        seeds = [
+            b"comparison_market",
+            protocol_a.as_bytes(),
+            protocol_b.as_bytes(),
+            &resolution_timestamp.to_le_bytes()
+        ],
+        bump
+    )]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump,
+        token::mint = idl_mint,
+        token::authority = market_pool,
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint)]
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// MARKET_SPAM FIX: Cooldown is tracked per creator
+    #[account(
+        mut,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// CHECK: Oracle authorized to resolve
+    pub oracle: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(inverse: bool)]
+pub struct CreateHedgeMarket<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub parent_market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PredictionMarket::INIT_SPACE,
+        seeds = [b"hedge_market", parent_market.key().as_ref(), &[inverse as u8]],
+        bump
+    )]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump,
+        token::mint = idl_mint,
+        token::authority = market_pool,
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint)]
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// MARKET_SPAM FIX: Cooldown is tracked per creator
+    #[account(
+        mut,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveHedgeMarket<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        constraint = market.parent_market == Some(parent_market.key()) @ IdlError::InvalidHedgeParent
+    )]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    pub parent_market: Box<Account<'info, PredictionMarket>>,
+}
+
+#[derive(Accounts)]
+pub struct UnveilRevealBatch<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, bet_yes: bool, nonce: u64)]
+pub struct PlaceBet<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", market.key().as_ref(), user.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    /// Staker account - optional, if missing user gets no bonus
+    /// (To save stack space, we don't init_if_needed here)
+    #[account(
+        seeds = [b"staker", user.key().as_ref()],
+        bump
+    )]
+    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserVolume::INIT_SPACE,
+        seeds = [b"volume", user.key().as_ref()],
+        bump
+    )]
+    pub user_volume: Box<Account<'info, UserVolume>>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint,
+        constraint = user_token_account.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+
+    // AUDIT FIX: Require oracle bond to be present
+    #[account(
+        seeds = [b"oracle_bond", oracle.key().as_ref()],
+        bump = oracle_bond.bump,
+        constraint = oracle_bond.oracle == oracle.key() @ IdlError::Unauthorized
+    )]
+    pub oracle_bond: Account<'info, OracleBond>,
+
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimCreationStake<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        constraint = market.creator == creator.key() @ IdlError::Unauthorized
+    )]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key() @ IdlError::Unauthorized,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub creator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(constraint = market.address() == Some(market.key()) @ IdlError::InvalidMarket)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), bet.owner.as_ref(), &bet.nonce.to_le_bytes()],
+        bump = bet.bump,
+        constraint = bet.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(constraint = market.address() == Some(market.key()) @ IdlError::InvalidMarket)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), bet.owner.as_ref(), &bet.nonce.to_le_bytes()],
+        bump = bet.bump,
+        constraint = bet.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// SECURITY FIX: Validate creator token account belongs to market creator
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == market.creator @ IdlError::InvalidCreatorAccount,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// SECURITY FIX: Validate treasury token account matches state treasury
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == state.treasury @ IdlError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub treasury_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub idl_mint: Box<Account<'info, Mint>>,
+
+    /// RICK FIX: Burn vault to hold "burned" tokens (since we can't actually burn without mint authority)
+    #[account(
+        mut,
+        seeds = [b"burn_vault"],
+        bump
+    )]
+    pub burn_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CONVICTION FIX: Optional conviction bet for bonus winnings
+    /// If provided, applies bonus_bps to winnings (if lock has expired)
+    #[account(
+        mut,
+        seeds = [b"conviction", bet.key().as_ref()],
+        bump = conviction_bet.bump,
+        constraint = conviction_bet.owner == user.key() @ IdlError::Unauthorized,
+        constraint = conviction_bet.bet == bet.key() @ IdlError::Unauthorized
+    )]
+    pub conviction_bet: Option<Account<'info, ConvictionBet>>,
+
+    /// REFERRAL FIX: Optional referral account to credit fees
+    /// If user has a referrer, 5% of fees goes to referrer's pending_fees
+    #[account(
+        mut,
+        seeds = [b"referral", user.key().as_ref()],
+        bump = user_referral.bump
+    )]
+    pub user_referral: Option<Account<'info, ReferralAccount>>,
+
+    /// Accuracy and streak record, updated on every settled bet
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + PredictorStats::INIT_SPACE,
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump
+    )]
+    pub predictor_stats: Box<Account<'info, PredictorStats>>,
+
+    /// Optional lootbox fee discount
+    #[account(constraint = lootbox_reward.owner == user.key() @ IdlError::Unauthorized)]
+    pub lootbox_reward: Option<Box<Account<'info, LootboxReward>>>,
+
+    /// AUTO-COMPOUND: Staker account that receives restaked winnings
+    #[account(
+        mut,
+        seeds = [b"staker", user.key().as_ref()],
+        bump = staker_account.bump
+    )]
+    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
+
+    /// SEASON FIX: Optional season whose bonus applies to these winnings
+    #[account(
+        mut,
+        seeds = [b"season", season.season_number.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStakingRewards<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,  // SECURITY FIX: Now mutable to track claimed amounts
+        seeds = [b"staker", user.key().as_ref()],
+        bump = staker_account.bump,
+        constraint = staker_account.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub staker_account: Account<'info, StakerAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// AUTO-COMPOUND: Restakes the rewards if the user opted in
+    #[account(
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump = predictor_stats.bump
+    )]
+    pub predictor_stats: Option<Account<'info, PredictorStats>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundStakingRewards<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"staker", staker_account.owner.as_ref()],
+        bump = staker_account.bump
+    )]
+    pub staker_account: Account<'info, StakerAccount>,
+
+    #[account(
+        seeds = [b"predictor_stats", staker_account.owner.as_ref()],
+        bump = predictor_stats.bump,
+        constraint = predictor_stats.auto_compound @ IdlError::AutoCompoundDisabled
+    )]
+    pub predictor_stats: Account<'info, PredictorStats>,
+
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueBadge<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VolumeBadge::INIT_SPACE,
+        seeds = [b"badge", recipient.key().as_ref()],
+        bump
+    )]
+    pub badge: Account<'info, VolumeBadge>,
+
+    #[account(
+        seeds = [b"volume", recipient.key().as_ref()],
+        bump = user_volume.bump
+    )]
+    pub user_volume: Account<'info, UserVolume>,
+
+    /// CHECK: Badge recipient
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeBadge<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"badge", badge.owner.as_ref()],
+        bump = badge.bump
+    )]
+    pub badge: Account<'info, VolumeBadge>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Any account owned by this program; its discriminator picks the layout (see migrated_space)
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminOnly<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RaiseTvlCap<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigAdmin<'info> {
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub authority: Signer<'info>,
+}
+
+// TIER 3: Withdraw from insurance fund
+#[derive(Accounts)]
+pub struct WithdrawInsurance<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Recipient token account for insurance withdrawal
+    #[account(mut)]
+    pub recipient: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// PUMP MECHANICS ACCOUNTS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Accounts)]
+pub struct RegisterReferral<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ReferralAccount::INIT_SPACE,
+        seeds = [b"referral", user.key().as_ref()],
+        bump
+    )]
+    pub referral_account: Account<'info, ReferralAccount>,
+
+    /// CHECK: Referrer just needs to be a valid pubkey
+    pub referrer: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitPredictorStats<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PredictorStats::INIT_SPACE,
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump
+    )]
+    pub predictor_stats: Account<'info, PredictorStats>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoCompound<'info> {
+    #[account(
+        mut,
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump = predictor_stats.bump,
+        constraint = predictor_stats.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub predictor_stats: Account<'info, PredictorStats>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundPredictorBonuses<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVipTier<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        seeds = [b"staker", user.key().as_ref()],
+        bump = staker_account.bump
+    )]
+    pub staker_account: Account<'info, StakerAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump = predictor_stats.bump,
+        constraint = predictor_stats.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub predictor_stats: Account<'info, PredictorStats>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_number: u64)]
+pub struct CreateSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Season::INIT_SPACE,
+        seeds = [b"season", season_number.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundSeason<'info> {
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.authority == authority.key() @ IdlError::Unauthorized
+    )]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [b"season", season.season_number.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EndSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"season", season.season_number.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitLeaderboardEntry<'info> {
+    #[account(
+        mut,
+        seeds = [b"season", season.season_number.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+
+    #[account(
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump = predictor_stats.bump,
+        constraint = predictor_stats.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub predictor_stats: Account<'info, PredictorStats>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + LeaderboardEntry::INIT_SPACE,
+        seeds = [b"leaderboard", season.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub leaderboard_entry: Account<'info, LeaderboardEntry>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLeaderboardPrize<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [b"season", season.season_number.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+
+    #[account(
+        mut,
+        seeds = [b"leaderboard", season.key().as_ref(), user.key().as_ref()],
+        bump = leaderboard_entry.bump,
+        constraint = leaderboard_entry.user == user.key() @ IdlError::Unauthorized
+    )]
+    pub leaderboard_entry: Account<'info, LeaderboardEntry>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitCreatorStats<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + CreatorStats::INIT_SPACE,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_stats: Account<'info, CreatorStats>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump = creator_stats.bump,
+        constraint = creator_stats.creator == creator.key() @ IdlError::Unauthorized
+    )]
+    pub creator_stats: Account<'info, CreatorStats>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    pub creator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceConvictionBet<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        seeds = [b"bet", bet.market.as_ref(), user.key().as_ref(), &bet.nonce.to_le_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Account<'info, Bet>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ConvictionBet::INIT_SPACE,
+        seeds = [b"conviction", bet.key().as_ref()],
+        bump
+    )]
+    pub conviction_bet: Account<'info, ConvictionBet>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralFees<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [b"referral", referral_account.user.as_ref()],
+        bump = referral_account.bump,
+        constraint = referral_account.referrer == referrer.key() @ IdlError::Unauthorized
+    )]
+    pub referral_account: Account<'info, ReferralAccount>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub referrer_token_account: Account<'info, TokenAccount>,
+
+    pub referrer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// ADVANCED MECHANICS ACCOUNTS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Accounts)]
+pub struct CreateBattle<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        init,
+        payer = challenger,
+        space = 8 + PredictionBattle::INIT_SPACE,
+        seeds = [b"battle", market.key().as_ref(), challenger.key().as_ref()],
+        bump
+    )]
+    pub battle: Account<'info, PredictionBattle>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub challenger_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptBattle<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub battle: Account<'info, PredictionBattle>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub opponent_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub opponent: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveBattle<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub battle: Account<'info, PredictionBattle>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub winner_token_account: Account<'info, TokenAccount>,
+
+    /// Optional fee discounts; ignored unless they belong to the winner
+    pub winner_stats: Option<Box<Account<'info, PredictorStats>>>,
+    pub winner_lootbox: Option<Box<Account<'info, LootboxReward>>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBattle<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(mut)]
+    pub battle: Account<'info, PredictionBattle>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub challenger_token_account: Account<'info, TokenAccount>,
+
+    pub challenger: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGuild<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = leader,
+        space = 8 + Guild::INIT_SPACE,
+        seeds = [b"guild", leader.key().as_ref()],
+        bump
+    )]
+    pub guild: Account<'info, Guild>,
+
+    /// Guild-owned treasury so pooled funds can only move through guild instructions
+    #[account(
+        init,
+        payer = leader,
+        seeds = [b"guild_treasury", guild.key().as_ref()],
+        bump,
+        token::mint = idl_mint,
+        token::authority = guild,
+    )]
+    pub guild_treasury: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = idl_mint.key() == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub idl_mint: Account<'info, Mint>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub leader_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGuild<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        init,
+        payer = member,
+        space = 8 + GuildMember::INIT_SPACE,
+        seeds = [b"guild_member", guild.key().as_ref(), member.key().as_ref()],
+        bump
+    )]
+    pub guild_member: Account<'info, GuildMember>,
+
+    #[account(mut, address = guild.treasury @ IdlError::InvalidGuildTreasury)]
+    pub guild_treasury: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub member_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GuildCommitBet<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        seeds = [b"guild", leader.key().as_ref()],
+        bump = guild.bump,
+        constraint = guild.leader == leader.key() @ IdlError::Unauthorized
+    )]
+    pub guild: Account<'info, Guild>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        init,
+        payer = leader,
+        space = 8 + BetCommitment::INIT_SPACE,
+        seeds = [b"bet_commit", market.key().as_ref(), guild.key().as_ref()],
+        bump
+    )]
+    pub bet_commitment: Account<'info, BetCommitment>,
+
+    #[account(
+        mut,
+        constraint = leader_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub leader_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, bet_yes: bool, nonce: u64, salt: [u8; 32])]
+pub struct GuildRevealBet<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"guild", leader.key().as_ref()],
+        bump = guild.bump,
+        constraint = guild.leader == leader.key() @ IdlError::Unauthorized
+    )]
+    pub guild: Box<Account<'info, Guild>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        close = leader,
+        seeds = [b"bet_commit", market.key().as_ref(), guild.key().as_ref()],
+        bump = bet_commitment.bump,
+        constraint = bet_commitment.owner == guild.key() @ IdlError::Unauthorized
+    )]
+    pub bet_commitment: Box<Account<'info, BetCommitment>>,
+
+    #[account(
+        init,
+        payer = leader,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", market.key().as_ref(), guild.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        init_if_needed,
+        payer = leader,
+        space = 8 + DynamicOdds::INIT_SPACE,
+        seeds = [b"dynamic_odds", market.key().as_ref()],
+        bump
+    )]
+    pub dynamic_odds: Box<Account<'info, DynamicOdds>>,
+
+    #[account(mut, address = guild.treasury @ IdlError::InvalidGuildTreasury)]
+    pub guild_treasury: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// PREDICTION MINING: Market creator accrues the volume fee
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    /// Receives the commitment bond refund
+    #[account(
+        mut,
+        constraint = leader_token_account.owner == leader.key() @ IdlError::Unauthorized,
+        constraint = leader_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub leader_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimGuildWinnings<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"guild", guild.leader.as_ref()],
+        bump = guild.bump
+    )]
+    pub guild: Box<Account<'info, Guild>>,
+
+    #[account(constraint = market.address() == Some(market.key()) @ IdlError::InvalidMarket)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), guild.key().as_ref(), &bet.nonce.to_le_bytes()],
+        bump = bet.bump,
+        constraint = bet.owner == guild.key() @ IdlError::Unauthorized
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = guild.treasury @ IdlError::InvalidGuildTreasury)]
+    pub guild_treasury: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = leader_token_account.owner == guild.leader @ IdlError::Unauthorized,
+        constraint = leader_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub leader_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == market.creator @ IdlError::InvalidCreatorAccount,
+        constraint = creator_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub creator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == state.treasury @ IdlError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub treasury_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"burn_vault"], bump = state.burn_vault_bump)]
+    pub burn_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromGuild<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild", guild.leader.as_ref()],
+        bump = guild.bump
+    )]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        mut,
+        seeds = [b"guild_member", guild.key().as_ref(), member.key().as_ref()],
+        bump = guild_member.bump,
+        constraint = guild_member.member == member.key() @ IdlError::Unauthorized
+    )]
+    pub guild_member: Account<'info, GuildMember>,
+
+    #[account(mut, address = guild.treasury @ IdlError::InvalidGuildTreasury)]
+    pub guild_treasury: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = member_token_account.mint == guild_treasury.mint @ IdlError::InvalidMint
+    )]
+    pub member_token_account: Account<'info, TokenAccount>,
+
+    pub member: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveGuild<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild", guild.leader.as_ref()],
+        bump = guild.bump
+    )]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        mut,
+        close = member,
+        seeds = [b"guild_member", guild.key().as_ref(), member.key().as_ref()],
+        bump = guild_member.bump,
+        constraint = guild_member.member == member.key() @ IdlError::Unauthorized
+    )]
+    pub guild_member: Account<'info, GuildMember>,
+
+    #[account(mut, address = guild.treasury @ IdlError::InvalidGuildTreasury)]
+    pub guild_treasury: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = member_token_account.mint == guild_treasury.mint @ IdlError::InvalidMint
+    )]
+    pub member_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DissolveGuild<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild", leader.key().as_ref()],
+        bump = guild.bump,
+        constraint = guild.leader == leader.key() @ IdlError::Unauthorized
+    )]
+    pub guild: Account<'info, Guild>,
+
+    pub leader: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8, nonce: u64)]
+pub struct BuyLootbox<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + LootboxReward::INIT_SPACE,
+        seeds = [b"lootbox", buyer.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub lootbox_reward: Account<'info, LootboxReward>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"burn_vault"], bump = state.burn_vault_bump)]
+    pub burn_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = buyer_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLootbox<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        constraint = lootbox_reward.owner == owner.key() @ IdlError::Unauthorized
+    )]
+    pub lootbox_reward: Account<'info, LootboxReward>,
+
+    /// CHECK: SlotHashes sysvar, read raw (too large to deserialize)
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemLootbox<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        constraint = lootbox_reward.owner == owner.key() @ IdlError::Unauthorized
+    )]
+    pub lootbox_reward: Account<'info, LootboxReward>,
+
+    /// Required for VIP upgrades
+    #[account(
+        mut,
+        seeds = [b"predictor_stats", owner.key().as_ref()],
+        bump = predictor_stats.bump
+    )]
+    pub predictor_stats: Option<Account<'info, PredictorStats>>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key() @ IdlError::Unauthorized,
+        constraint = owner_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, bet_yes: bool, target_odds_bps: u64, order_id: u64)]
+pub struct CreateLimitOrder<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + LimitOrder::INIT_SPACE,
+        seeds = [b"limit_order", market.key().as_ref(), user.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(mut)]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"limit_order",
+            market.key().as_ref(),
+            limit_order.owner.as_ref(),
+            &limit_order.order_id.to_le_bytes()
+        ],
+        bump = limit_order.bump
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    #[account(
+        init,
+        payer = keeper,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [
+            b"bet",
+            market.key().as_ref(),
+            limit_order.owner.as_ref(),
+            &limit_order.order_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = 8 + DynamicOdds::INIT_SPACE,
+        seeds = [b"dynamic_odds", market.key().as_ref()],
+        bump
+    )]
+    pub dynamic_odds: Box<Account<'info, DynamicOdds>>,
+
+    #[account(
+        seeds = [b"staker", limit_order.owner.as_ref()],
+        bump
+    )]
+    pub staker_account: Option<Box<Account<'info, StakerAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"volume", limit_order.owner.as_ref()],
+        bump = user_volume.bump
+    )]
+    pub user_volume: Option<Box<Account<'info, UserVolume>>>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// PREDICTION MINING: Market creator accrues the volume fee
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump = creator_stats.bump
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = keeper_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub keeper_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireLimitOrder<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"limit_order",
+            limit_order.market.as_ref(),
+            limit_order.owner.as_ref(),
+            &limit_order.order_id.to_le_bytes()
+        ],
+        bump = limit_order.bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == limit_order.owner @ IdlError::Unauthorized,
+        constraint = owner_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetStopLoss<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(constraint = bet.owner == user.key() @ IdlError::Unauthorized)]
+    pub bet: Account<'info, Bet>,
+
+    #[account(
+        seeds = [b"dynamic_odds", bet.market.as_ref()],
+        bump = dynamic_odds.bump
+    )]
+    pub dynamic_odds: Box<Account<'info, DynamicOdds>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + StopLoss::INIT_SPACE,
+        seeds = [b"stop_loss", bet.key().as_ref()],
+        bump
+    )]
+    pub stop_loss: Account<'info, StopLoss>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PartialCashout<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, ProtocolState>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), user.key().as_ref(), &bet.nonce.to_le_bytes()],
+        bump = bet.bump,
+        constraint = bet.owner == user.key() @ IdlError::Unauthorized
+    )]
+    pub bet: Account<'info, Bet>,
+
+    #[account(mut)]
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        seeds = [b"dynamic_odds", market.key().as_ref()],
+        bump = dynamic_odds.bump
+    )]
+    pub dynamic_odds: Box<Account<'info, DynamicOdds>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CashoutPosition::INIT_SPACE,
+        seeds = [b"cashout", bet.key().as_ref()],
+        bump
+    )]
+    pub cashout_position: Account<'info, CashoutPosition>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// Optional VIP tier fee discount
+    #[account(
+        seeds = [b"predictor_stats", user.key().as_ref()],
+        bump = predictor_stats.bump
+    )]
+    pub predictor_stats: Option<Box<Account<'info, PredictorStats>>>,
+
+    /// Optional lootbox fee discount
+    #[account(constraint = lootbox_reward.owner == user.key() @ IdlError::Unauthorized)]
+    pub lootbox_reward: Option<Box<Account<'info, LootboxReward>>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TriggerStopLoss<'info> {
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, PredictionMarket>>,
+
+    #[account(
+        mut,
+        seeds = [b"bet", market.key().as_ref(), bet.owner.as_ref(), &bet.nonce.to_le_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Box<Account<'info, Bet>>,
+
+    #[account(
+        mut,
+        seeds = [b"stop_loss", bet.key().as_ref()],
+        bump = stop_loss.bump,
+        constraint = stop_loss.owner == bet.owner @ IdlError::Unauthorized
+    )]
+    pub stop_loss: Box<Account<'info, StopLoss>>,
+
+    #[account(
+        seeds = [b"dynamic_odds", market.key().as_ref()],
+        bump = dynamic_odds.bump
+    )]
+    pub dynamic_odds: Box<Account<'info, DynamicOdds>>,
+
+    #[account(
+        mut,
+        seeds = [b"market_pool", market.key().as_ref()],
+        bump
+    )]
+    pub market_pool: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == bet.owner @ IdlError::Unauthorized,
+        constraint = owner_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = keeper_token_account.mint == state.idl_mint @ IdlError::InvalidMint
+    )]
+    pub keeper_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Optional VIP tier fee discount of the bet owner
+    #[account(
+        seeds = [b"predictor_stats", bet.owner.as_ref()],
+        bump = predictor_stats.bump
+    )]
+    pub predictor_stats: Option<Box<Account<'info, PredictorStats>>>,
+
+    /// Optional lootbox fee discount of the bet owner
+    #[account(constraint = lootbox_reward.owner == bet.owner @ IdlError::Unauthorized)]
+    pub lootbox_reward: Option<Box<Account<'info, LootboxReward>>>,
+
+    pub keeper: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitDynamicOdds<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub market: Account<'info, PredictionMarket>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DynamicOdds::INIT_SPACE,
+        seeds = [b"dynamic_odds", market.key().as_ref()],
+        bump
+    )]
+    pub dynamic_odds: Account<'info, DynamicOdds>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// GOVERNANCE ACCOUNTS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", state.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        seeds = [b"ve_position", proposer.key().as_ref()],
+        bump = ve_position.bump
+    )]
+    pub ve_position: Option<Box<Account<'info, VePosition>>>,
+
+    #[account(
+        seeds = [b"badge", proposer.key().as_ref()],
+        bump = badge.bump
+    )]
+    pub badge: Option<Box<Account<'info, VolumeBadge>>>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteOnProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + ProposalVote::INIT_SPACE,
+        seeds = [b"proposal_vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub proposal_vote: Account<'info, ProposalVote>,
+
+    #[account(
+        seeds = [b"ve_position", voter.key().as_ref()],
+        bump = ve_position.bump
+    )]
+    pub ve_position: Option<Box<Account<'info, VePosition>>>,
+
+    #[account(
+        seeds = [b"badge", voter.key().as_ref()],
+        bump = badge.bump
+    )]
+    pub badge: Option<Box<Account<'info, VolumeBadge>>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Box<Account<'info, ProtocolState>>,
+
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = state.vault_bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// Recipient for SpendInsurance proposals
+    #[account(mut)]
+    pub recipient_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// ==================== STATE ====================
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ProtocolState {
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub idl_mint: Pubkey,
+    pub vault: Pubkey,
+    pub burn_vault: Pubkey,  // Added for multi-step init
+    pub total_staked: u64,
+    pub total_ve_supply: u64,
+    pub reward_pool: u64,
+    pub total_fees_collected: u64,
+    pub total_burned: u64,
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub burn_vault_bump: u8,  // Added for multi-step init
+    pub paused: bool,
+    // SECURITY FIX: Reward checkpoint for proper distribution
+    pub reward_per_token_stored: u128,  // Scaled by 1e18 for precision
+    pub last_reward_update: i64,
+    // RICK FIX: Authority timelock
+    pub pending_authority: Option<Pubkey>,
+    pub authority_transfer_time: Option<i64>,
+    // TIER 3: TVL cap (gradual rollout)
+    pub tvl_cap: u64,
+    // TIER 3: Insurance fund
+    pub insurance_fund: u64,
+    // Lootbox revenue (after burn) available for IDL-token rewards
+    pub lootbox_budget: u64,
+    // TVL_CAP_RACE: Current raise queue
+    pub tvl_raise_id: u64,
+    pub tvl_raise_end: i64,         // Queue closes at this time
+    pub tvl_raise_headroom: u64,    // New capacity when the raise opened
+    pub tvl_raise_requested: u64,   // Total queued deposits
+    pub tvl_raise_pending: u64,     // Queued deposits not yet settled
+    // GOVERNANCE: Next proposal id
+    pub proposal_count: u64,
+    // SEASONS: Running season window; settlements of markets resolved inside it count toward it
+    pub season_start: i64,
+    pub season_end: i64,
+    // Vault funds set aside for accuracy and streak bonuses on winning claims
+    pub predictor_bonus_pool: u64,
+}
+
+impl ProtocolState {
+    /// Direct deposits wait until the raise queue has closed and every entry is settled
+    pub fn tvl_raise_active(&self, now: i64) -> bool {
+        now < self.tvl_raise_end || self.tvl_raise_pending > 0
+    }
+
+    /// Raise the cap and open a pro-rata queue for the new headroom
+    pub fn open_tvl_raise(&mut self, new_cap: u64, queue_window: i64, now: i64) {
+        self.tvl_cap = new_cap;
+        self.tvl_raise_id = self.tvl_raise_id.saturating_add(1);
+        self.tvl_raise_end = now + queue_window;
+        self.tvl_raise_headroom = new_cap.saturating_sub(self.total_staked);
+        self.tvl_raise_requested = 0;
+    }
+
+    /// Share of the headroom staked for a queued deposit: all of it if the raise is
+    /// undersubscribed, otherwise pro-rata to the total requested (rounded down)
+    pub fn tvl_raise_allocation(&self, amount: u64) -> u64 {
+        if self.tvl_raise_requested <= self.tvl_raise_headroom {
+            return amount;
+        }
+        (amount as u128 * self.tvl_raise_headroom as u128 / self.tvl_raise_requested as u128) as u64
+    }
+
+    /// Accuracy and streak bonus of `bonus_bps` on a win's profit, paid out of the
+    /// predictor bonus pool while it lasts so bonuses never come out of a market's pot
+    pub fn take_predictor_bonus(&mut self, profit: u64, bonus_bps: u64) -> u64 {
+        let bonus = ((profit as u128 * bonus_bps as u128 / 10000) as u64).min(self.predictor_bonus_pool);
+        self.predictor_bonus_pool -= bonus;
+        bonus
+    }
+
+    /// Start of the running season if `t` falls inside it
+    pub fn season_start_at(&self, t: i64) -> Option<i64> {
+        (self.season_end > 0 && t >= self.season_start && t <= self.season_end)
+            .then_some(self.season_start)
+    }
+}
+
+/// Tunable economic parameters. Updates go through the same AUTHORITY_TIMELOCK as authority transfers.
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    pub params: ConfigParams,
+    pub pending_params: Option<ConfigParams>,
+    pub update_time: Option<i64>,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct StakerAccount {
+    pub owner: Pubkey,
+    pub staked_amount: u64,
+    pub last_stake_timestamp: i64,
+    pub rewards_claimed: u64,               // Track total rewards claimed
+    pub reward_per_token_paid: u128,        // SECURITY FIX: Checkpoint per staker
+    pub pending_rewards: u64,               // Unclaimed rewards
+    pub last_reward_claim: i64,             // RICK FIX: Cooldown timestamp
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct VePosition {
+    pub owner: Pubkey,
+    pub locked_stake: u64,
+    pub initial_ve_amount: u64,  // RICK FIX: Renamed - this is veIDL at lock time
+    pub lock_start: i64,
+    pub lock_end: i64,
+    pub lock_duration: i64,      // RICK FIX: Store original duration for decay calc
+    pub bump: u8,
+    /// When the lock was last created or extended, and the terms it replaced (in force since
+    /// prev_modified), so a vote snapshot taken before an extension sees the old terms
+    pub last_modified: i64,
+    pub prev_modified: i64,
+    pub prev_initial_ve_amount: u64,
+    pub prev_lock_end: i64,
+    pub prev_lock_duration: i64,
+}
+
+impl VePosition {
+    /// RICK FIX: Calculate current veIDL with linear decay
+    /// Whitepaper: "Current veIDL = Initial veIDL * (Time Remaining / Lock Duration)"
+    pub fn current_ve_amount(&self, current_time: i64) -> u64 {
+        if current_time >= self.lock_end {
+            return 0;
+        }
+        if current_time <= self.lock_start {
+            return self.initial_ve_amount;
+        }
+
+        let time_remaining = self.lock_end.saturating_sub(current_time);
+
+        // current = initial * (remaining / duration)
+        (self.initial_ve_amount as u128)
+            .saturating_mul(time_remaining as u128)
+            .checked_div(self.lock_duration as u128)
+            .map(|v| v as u64)
+            .unwrap_or(0)
+    }
+
+    /// Replace the lock's terms at `now`, keeping the old ones for vote snapshots taken before it
+    pub fn extend_terms(&mut self, initial_ve_amount: u64, lock_end: i64, lock_duration: i64, now: i64) {
+        self.prev_modified = self.last_modified;
+        self.prev_initial_ve_amount = self.initial_ve_amount;
+        self.prev_lock_end = self.lock_end;
+        self.prev_lock_duration = self.lock_duration;
+        self.last_modified = now;
+
+        self.initial_ve_amount = initial_ve_amount;
+        self.lock_end = lock_end;
+        self.lock_duration = lock_duration;
+    }
+
+    /// veIDL under the terms in force at `snapshot`
+    /// Zero if the lock was taken after it, or extended twice since (the terms at the snapshot are gone).
+    pub fn snapshot_ve_amount(&self, snapshot: i64) -> u64 {
+        if snapshot < self.lock_start {
+            return 0;
+        }
+        if snapshot >= self.last_modified {
+            return self.current_ve_amount(snapshot);
+        }
+        if snapshot < self.prev_modified {
+            return 0;
+        }
+        VePosition {
+            initial_ve_amount: self.prev_initial_ve_amount,
+            lock_end: self.prev_lock_end,
+            lock_duration: self.prev_lock_duration,
+            ..self.clone()
+        }
+        .current_ve_amount(snapshot)
+    }
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct PredictionMarket {
+    pub creator: Pubkey,
+    #[max_len(32)]
+    pub protocol_id: String,
+    pub metric_type: MetricType,
+    pub target_value: u64,
+    pub resolution_timestamp: i64,
+    #[max_len(200)]
+    pub description: String,
+    // SECURITY FIX: Track ACTUAL tokens deposited (not effective amounts)
+    pub total_yes_actual: u64,      // Real tokens deposited on YES
+    pub total_no_actual: u64,       // Real tokens deposited on NO
+    // Effective amounts for payout calculation only
+    pub total_yes_amount: u64,      // Weighted YES (with staker bonus)
+    pub total_no_amount: u64,       // Weighted NO (with staker bonus)
+    pub resolved: bool,
+    pub resolved_at: Option<i64>,
+    pub outcome: Option<bool>,
+    pub actual_value: Option<u64>,
+    pub oracle: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+    // SECURITY FIX: Market status for cancellation
+    pub status: u8,                 // 0=active, 1=resolved, 2=cancelled, 3=disputed
+    // ATTACK FIX: Multi-oracle consensus support
+    pub oracle_count: u8,           // Number of registered oracles
+    pub oracle_votes_yes: u8,       // Oracles that voted YES
+    pub oracle_votes_no: u8,        // Oracles that voted NO
+    // Hedge markets settle from their parent
+    pub parent_market: Option<Pubkey>,
+    pub hedge_inverse: bool,
+    pub market_type: u8,            // 0=binary, 1=categorical, 2=scalar, 3=comparison
+    // Categorical markets: per-outcome pools and the winning index
+    pub outcome_count: u8,
+    pub outcome_pools: [u64; MAX_MARKET_OUTCOMES],           // Real tokens per outcome
+    pub outcome_pools_effective: [u64; MAX_MARKET_OUTCOMES], // Weighted (with staker bonus)
+    pub winning_outcome: Option<u8>,
+    // Scalar markets: payout range (values outside are clamped)
+    pub range_low: u64,
+    pub range_high: u64,
+    // Comparison markets: protocol B (protocol_id is A) and its resolved value
+    #[max_len(32)]
+    pub comparison_protocol_id: String,
+    pub comparison_value: Option<u64>,
+    // COMMITMENT_SNIPE: Revealed stakes per side/outcome, hidden until the batch unveils
+    pub pending_actual: [u64; MAX_MARKET_OUTCOMES],
+    pub pending_effective: [u64; MAX_MARKET_OUTCOMES],
+    pub pending_batch_start: i64,   // 0 = no pending batch
+    // MARKET_SPAM: Refundable creation stake held in the vault (0 once refunded or slashed)
+    pub creation_stake: u64,
+    // CASHOUT: Stake left behind by early exits (fee + odds discount), paid to the winning side
+    pub cashout_retained: u64,
+}
+
+impl PredictionMarket {
+    /// PDA this market should live at, derived from its stored bump
+    pub fn address(&self) -> Option<Pubkey> {
+        let bump = [self.bump];
+        let timestamp = self.resolution_timestamp.to_le_bytes();
+        if let Some(parent) = self.parent_market {
+            let inverse = [self.hedge_inverse as u8];
+            return Pubkey::create_program_address(
+                &[b"hedge_market", parent.as_ref(), &inverse, &bump],
+                &crate::ID
+            ).ok();
+        }
+        if self.market_type == MARKET_TYPE_COMPARISON {
+            return Pubkey::create_program_address(
+                &[
+                    b"comparison_market",
+                    self.protocol_id.as_bytes(),
+                    self.comparison_protocol_id.as_bytes(),
+                    &timestamp,
+                    &bump
+                ],
+                &crate::ID
+            ).ok();
+        }
+        let prefix: &[u8] = match self.market_type {
+            MARKET_TYPE_CATEGORICAL => b"categorical_market",
+            MARKET_TYPE_SCALAR => b"scalar_market",
+            _ => b"market",
+        };
+        Pubkey::create_program_address(
+            &[prefix, self.protocol_id.as_bytes(), &timestamp, &bump],
+            &crate::ID
+        ).ok()
+    }
+
+    /// Whether bets are placed on YES/NO sides (everything except categorical)
+    pub fn is_two_sided(&self) -> bool {
+        self.market_type != MARKET_TYPE_CATEGORICAL
+    }
+
+    /// Add a revealed stake to the pending batch (side is the outcome index, 1 = YES on two-sided
+    /// markets), unveiling the previous batch first if its delay has passed
+    pub fn add_pending_reveal(&mut self, cfg: &ConfigParams, side: u8, amount: u64, effective_amount: u64, now: i64) {
+        self.unveil_reveal_batch(cfg, now);
+        if self.pending_batch_start == 0 {
+            self.pending_batch_start = now;
+        }
+        let index = side as usize;
+        self.pending_actual[index] = self.pending_actual[index].saturating_add(amount);
+        self.pending_effective[index] = self.pending_effective[index].saturating_add(effective_amount);
+    }
+
+    /// Fold the pending batch into the public pools once BATCH_REVEAL_DELAY has passed
+    pub fn unveil_reveal_batch(&mut self, cfg: &ConfigParams, now: i64) -> bool {
+        if self.pending_batch_start == 0 || now < self.pending_batch_start + cfg.batch_reveal_delay {
+            return false;
+        }
+        self.flush_pending_reveals();
+        true
+    }
+
+    /// Fold the pending batch into the public pools regardless of the delay (betting is over)
+    pub fn flush_pending_reveals(&mut self) {
+        let actual = std::mem::take(&mut self.pending_actual);
+        let effective = std::mem::take(&mut self.pending_effective);
+        self.pending_batch_start = 0;
+
+        for (index, (&amount, &effective_amount)) in actual.iter().zip(effective.iter()).enumerate() {
+            if self.is_two_sided() {
+                if index < 2 {
+                    add_to_market_pools(self, index == 1, amount, effective_amount);
+                }
+            } else {
+                self.outcome_pools[index] = self.outcome_pools[index].saturating_add(amount);
+                self.outcome_pools_effective[index] = self.outcome_pools_effective[index]
+                    .saturating_add(effective_amount);
+            }
+        }
+    }
+
+    /// Record the agreed oracle value(s) and derive the outcome for this market type
+    pub fn apply_resolution(&mut self, actual_value: u64, value_b: u64) -> Result<()> {
+        match self.market_type {
+            MARKET_TYPE_CATEGORICAL => {
+                require!(actual_value < self.outcome_count as u64, IdlError::InvalidOutcome);
+                self.winning_outcome = Some(actual_value as u8);
+            }
+            MARKET_TYPE_COMPARISON => {
+                self.outcome = Some(actual_value > value_b);
+                self.comparison_value = Some(value_b);
+            }
+            _ => self.outcome = Some(actual_value >= self.target_value),
+        }
+        self.actual_value = Some(actual_value);
+        Ok(())
+    }
+
+    /// What oracles must agree on: the YES/NO outcome for binary markets, the winner
+    /// (0 = B, 1 = A, 2 = tie) for comparisons, and the exact value otherwise
+    pub fn consensus_key(&self, value: u64, value_b: u64) -> u64 {
+        match self.market_type {
+            MARKET_TYPE_COMPARISON => match value.cmp(&value_b) {
+                std::cmp::Ordering::Less => 0,
+                std::cmp::Ordering::Greater => 1,
+                std::cmp::Ordering::Equal => 2,
+            },
+            MARKET_TYPE_BINARY => (value >= self.target_value) as u64,
+            _ => value,
+        }
+    }
+
+    /// Fee charged on winnings (bps)
+    pub fn fee_bps(&self, cfg: &ConfigParams) -> u64 {
+        if self.parent_market.is_some() {
+            cfg.bet_fee_bps + cfg.hedge_market_fee_bps
+        } else {
+            cfg.bet_fee_bps
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Bet {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+    pub effective_amount: u64,
+    pub bet_yes: bool,
+    pub timestamp: i64,
+    pub claimed: bool,
+    pub nonce: u64,
+    pub bump: u8,
+    /// Outcome index for categorical markets (1 = YES, 0 = NO on two-sided markets)
+    pub outcome_index: u8,
+    /// Early-bird bonus included in effective_amount
+    pub early_bird_bonus: u64,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct VolumeBadge {
+    pub owner: Pubkey,
+    pub tier: BadgeTier,
+    pub volume_usd: u64,
+    pub ve_amount: u64,
+    pub issued_at: i64,
+    pub bump: u8,
+    /// When the badge was last issued or upgraded, and the veIDL it replaced (held since prev_modified)
+    pub last_modified: i64,
+    pub prev_modified: i64,
+    pub prev_ve_amount: u64,
+}
+
+impl VolumeBadge {
+    /// Replace the badge's grant at `now`, keeping the old one for vote snapshots taken before it
+    pub fn upgrade_grant(&mut self, ve_amount: u64, now: i64) {
+        self.prev_modified = self.last_modified.max(self.issued_at);
+        self.prev_ve_amount = self.ve_amount;
+        self.last_modified = now;
+        self.ve_amount = ve_amount;
+    }
+
+    /// veIDL granted by the badge at `snapshot`; zero if it was upgraded twice since
+    pub fn snapshot_ve_amount(&self, snapshot: i64) -> u64 {
+        if snapshot < self.issued_at {
+            0
+        } else if snapshot >= self.last_modified {
+            self.ve_amount
+        } else if snapshot >= self.prev_modified {
+            self.prev_ve_amount
+        } else {
+            0
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserVolume {
+    pub user: Pubkey,
+    pub total_volume_usd: u64,
+    pub last_updated: i64,
+    pub bump: u8,
+}
+
+// 10/10 FIX: Commit-reveal bet to prevent front-running
+#[account]
+#[derive(InitSpace, Default)]
+pub struct BetCommitment {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub commitment: [u8; 32],  // hash(amount, bet_yes, nonce, salt)
+    pub commit_time: i64,
+    pub revealed: bool,
+    pub bump: u8,
+    pub bond_amount: u64,     // COMMITMENT_GRIEF: refunded on reveal
+    pub bond_payer: Pubkey,   // Who posted the bond (user, or guild leader)
+}
+
+impl BetCommitment {
+    /// Unrevealed and past COMMITMENT_EXPIRY; returns whether the bond is forfeited, i.e. the
+    /// market was still taking reveals when the reveal window ended
+    pub fn check_expired(&self, cfg: &ConfigParams, market: &PredictionMarket, now: i64) -> Result<bool> {
+        require!(!self.revealed, IdlError::AlreadyRevealed);
+        require!(now > self.commit_time + cfg.commitment_expiry, IdlError::CommitmentNotExpired);
+
+        let reveal_deadline = self.commit_time + cfg.bet_reveal_window;
+        Ok(market.status != MARKET_STATUS_CANCELLED && reveal_deadline < market.resolution_timestamp)
+    }
+}
+
+// TVL_CAP_RACE FIX: A deposit queued for a TVL raise
+#[account]
+#[derive(InitSpace)]
+pub struct TvlRaiseEntry {
+    pub owner: Pubkey,
+    pub raise_id: u64,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+// STAKE_FRONT_RUN FIX: Commit-reveal for stakes above LARGE_STAKE_THRESHOLD
+#[account]
+#[derive(InitSpace, Default)]
+pub struct StakeCommitment {
+    pub owner: Pubkey,
+    pub commitment: [u8; 32],  // hash(amount, salt)
+    pub commit_time: i64,
+    pub bump: u8,
+}
+
+impl StakeCommitment {
+    /// Revealed between STAKE_COMMIT_WINDOW and COMMITMENT_EXPIRY, matching the committed amount
+    pub fn check_reveal(&self, cfg: &ConfigParams, amount: u64, salt: &[u8; 32], now: i64) -> Result<()> {
+        require!(now <= self.commit_time + cfg.commitment_expiry, IdlError::CommitmentExpired);
+        require!(now >= self.commit_time + cfg.stake_commit_window, IdlError::RevealTooEarly);
+        require!(stake_commitment_hash(amount, salt) == self.commitment, IdlError::InvalidCommitment);
+        Ok(())
+    }
+}
+
+// 10/10 FIX: Oracle bond for accountability
+#[account]
+#[derive(InitSpace)]
+pub struct OracleBond {
+    pub oracle: Pubkey,
+    pub bond_amount: u64,
+    pub bonded_at: i64,
+    pub slashed: bool,
+    pub bump: u8,
+    // SELF-REVIEW FIX: Track active resolutions to prevent multi-market exploit
+    // Oracle can only have ONE pending resolution at a time
+    pub active_resolution: Option<Pubkey>,  // Market key until its resolution can no longer be disputed
+}
+
+// 10/10 FIX: Resolution commitment for oracle commit-reveal
+#[account]
+#[derive(InitSpace)]
+pub struct ResolutionCommitment {
+    pub market: Pubkey,
+    pub oracle: Pubkey,
+    pub commitment: [u8; 32],  // hash(actual_value, nonce)
+    pub commit_time: i64,
+    pub revealed: bool,
+    pub disputed: bool,
+    pub bump: u8,
+    // ATTACK FIX: Revealed vote, tallied in finalize_resolution
+    pub value: u64,
+    pub value_b: u64,              // Protocol B's value on comparison markets
+}
+
+/// DISPUTE_GRIEF FIX: Bonded dispute of a market resolution, settled by veIDL vote
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Dispute {
+    pub market: Pubkey,
+    pub disputer: Pubkey,
+    pub bond_amount: u64,
+    pub opened_at: i64,
+    /// Voting power is measured at this time (VOTE_SNAPSHOT_DELAY before opening)
+    pub snapshot_time: i64,
+    pub voting_ends_at: i64,
+    pub votes_uphold: u64,
+    pub votes_overturn: u64,
+    pub votes_cancel: u64,
+    pub settled: bool,
+    /// DISPUTE_CHOICE_* the vote settled on
+    pub result: u8,
+    pub bump: u8,
+}
+
+impl Dispute {
+    pub fn record_vote(&mut self, choice: u8, weight: u64) {
+        match choice {
+            DISPUTE_CHOICE_UPHOLD => self.votes_uphold = self.votes_uphold.saturating_add(weight),
+            DISPUTE_CHOICE_OVERTURN => self.votes_overturn = self.votes_overturn.saturating_add(weight),
+            _ => self.votes_cancel = self.votes_cancel.saturating_add(weight),
+        }
+    }
+
+    /// DISPUTE_CHOICE_* the votes settle on; the resolution stands unless beaten outright
+    pub fn outcome(&self) -> u8 {
+        if self.votes_overturn > self.votes_uphold && self.votes_overturn >= self.votes_cancel {
+            DISPUTE_CHOICE_OVERTURN
+        } else if self.votes_cancel > self.votes_uphold && self.votes_cancel > self.votes_overturn {
+            DISPUTE_CHOICE_CANCEL
+        } else {
+            DISPUTE_CHOICE_UPHOLD
+        }
+    }
+}
+
+/// One voter's ballot on a dispute
+#[account]
+#[derive(InitSpace)]
+pub struct DisputeVote {
+    pub dispute: Pubkey,
+    pub voter: Pubkey,
+    pub choice: u8,
+    pub weight: u64,
+    pub bump: u8,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// PUMP MECHANICS STATE - New tokenomics structures
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Referral relationship - who referred who
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralAccount {
+    /// The user who was referred
+    pub user: Pubkey,
+    /// The referrer who gets fees
+    pub referrer: Pubkey,
+    /// Total fees earned by referrer from this user (historical)
+    pub total_fees_earned: u64,
+    /// SECURITY FIX: Pending fees available to claim
+    pub pending_fees: u64,
+    /// Timestamp of referral registration
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+/// User prediction statistics for accuracy tracking
+#[account]
+#[derive(InitSpace, Default)]
+pub struct PredictorStats {
+    pub owner: Pubkey,
+    /// Total predictions made
+    pub total_predictions: u64,
+    /// Correct predictions
+    pub correct_predictions: u64,
+    /// Current win streak
+    pub current_streak: u64,
+    /// Best ever streak
+    pub best_streak: u64,
+    /// Total winnings
+    pub total_winnings: u64,
+    /// Total losses
+    pub total_losses: u64,
+    /// Last prediction timestamp
+    pub last_prediction: i64,
+    /// Auto-compound enabled
+    pub auto_compound: bool,
+    /// VIP tier based on stake
+    pub vip_tier: u8,
+    pub bump: u8,
+    /// Extra VIP tiers redeemed from lootboxes
+    pub vip_bonus_tiers: u8,
+    /// Season the counters below belong to (its start time)
+    pub season_start: i64,
+    /// Predictions, correct predictions and winnings settled in that season
+    pub season_predictions: u64,
+    pub season_correct: u64,
+    pub season_winnings: u64,
+}
+
+impl PredictorStats {
+    /// Calculate accuracy percentage (0-100)
+    pub fn accuracy(&self) -> u64 {
+        if self.total_predictions == 0 {
+            return 0;
+        }
+        (self.correct_predictions * 100) / self.total_predictions
+    }
+
+    /// Calculate streak bonus in basis points
+    pub fn streak_bonus_bps(&self, cfg: &ConfigParams) -> u64 {
+        std::cmp::min(
+            self.current_streak * cfg.streak_bonus_per_win,
+            cfg.max_streak_bonus_bps
+        )
+    }
+
+    /// Calculate VIP tier from stake amount
+    pub fn calculate_vip_tier(cfg: &ConfigParams, stake: u64) -> u8 {
+        if stake >= cfg.vip_tier_4_stake {
+            4
+        } else if stake >= cfg.vip_tier_3_stake {
+            3
+        } else if stake >= cfg.vip_tier_2_stake {
+            2
+        } else if stake >= cfg.vip_tier_1_stake {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Accuracy bonus (above ACCURACY_BONUS_THRESHOLD) plus streak bonus, in basis points
+    pub fn record_bonus_bps(&self, cfg: &ConfigParams) -> u64 {
+        let accuracy_bonus = if self.accuracy() >= cfg.accuracy_bonus_threshold {
+            cfg.accuracy_bonus_bps
+        } else {
+            0
+        };
+        accuracy_bonus + self.streak_bonus_bps(cfg)
+    }
+
+    /// Record a settled prediction; `amount` is the net payout for a win or the stake for a loss
+    /// `season_start` identifies the season the market resolved in, if any.
+    pub fn record_settlement(&mut self, won: bool, amount: u64, now: i64, season_start: Option<i64>) {
+        self.total_predictions = self.total_predictions.saturating_add(1);
+        if won {
+            self.correct_predictions = self.correct_predictions.saturating_add(1);
+            self.current_streak = self.current_streak.saturating_add(1);
+            self.best_streak = self.best_streak.max(self.current_streak);
+            self.total_winnings = self.total_winnings.saturating_add(amount);
+        } else {
+            self.current_streak = 0;
+            self.total_losses = self.total_losses.saturating_add(amount);
+        }
+        self.last_prediction = now;
+
+        if let Some(start) = season_start {
+            if self.season_start != start {
+                self.season_start = start;
+                self.season_predictions = 0;
+                self.season_correct = 0;
+                self.season_winnings = 0;
+            }
+            self.season_predictions = self.season_predictions.saturating_add(1);
+            if won {
+                self.season_correct = self.season_correct.saturating_add(1);
+                self.season_winnings = self.season_winnings.saturating_add(amount);
+            }
+        }
+    }
+
+    /// Calculate fee discount based on VIP tier
+    pub fn vip_fee_discount_bps(&self, cfg: &ConfigParams) -> u64 {
+        (self.vip_tier as u64) * cfg.vip_fee_discount_bps
+    }
+}
+
+/// Season state for time-limited reward boosts
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Season {
+    /// Season number (1, 2, 3, ...)
+    pub season_number: u64,
+    /// Start timestamp
+    pub start_time: i64,
+    /// End timestamp
+    pub end_time: i64,
+    /// Total rewards allocated for this season's win bonuses
+    pub total_rewards: u64,
+    /// Win bonuses already paid out of total_rewards
+    pub bonus_distributed: u64,
+    /// Is season currently active
+    pub active: bool,
+    /// Prize pool for top predictors
+    pub prize_pool: u64,
+    pub bump: u8,
+    /// Share of the prize pool per rank in bps (index 0 = first place)
+    pub prize_shares_bps: [u16; LEADERBOARD_MAX_RANKS],
+    /// Number of prize-eligible ranks (set at season end)
+    pub ranked_count: u8,
+    /// Leaderboard locked, prizes claimable
+    pub finalized: bool,
+    /// Distinct users that submitted a leaderboard entry
+    pub entry_count: u64,
+    /// Leaderboard prizes already paid out of prize_pool
+    pub prize_distributed: u64,
+    /// Best entries so far, best first (winnings desc, then accuracy desc)
+    pub leaders: [Pubkey; LEADERBOARD_MAX_RANKS],
+    pub leader_winnings: [u64; LEADERBOARD_MAX_RANKS],
+    pub leader_accuracy: [u64; LEADERBOARD_MAX_RANKS],
+    pub leader_count: u8,
+}
+
+impl Season {
+    /// Season bonus at time `t`, ramped linearly over the first and last SEASON_PHASE_IN_DURATION
+    pub fn bonus_bps_at(&self, cfg: &ConfigParams, t: i64) -> u64 {
+        if t < self.start_time || t > self.end_time {
+            return 0;
+        }
+        let ramp = (t - self.start_time)
+            .min(self.end_time - t)
+            .min(cfg.season_phase_in_duration);
+        (cfg.season_bonus_bps as u128 * ramp as u128 / cfg.season_phase_in_duration as u128) as u64
+    }
+
+    /// Place `user`'s latest score among the leaders, replacing their previous one
+    /// Ties keep the earlier submission ahead; a score below a full table is dropped.
+    pub fn record_entry(&mut self, user: Pubkey, winnings: u64, accuracy: u64) {
+        let mut count = self.leader_count as usize;
+        if let Some(i) = self.leaders[..count].iter().position(|l| *l == user) {
+            self.leaders.copy_within(i + 1..count, i);
+            self.leader_winnings.copy_within(i + 1..count, i);
+            self.leader_accuracy.copy_within(i + 1..count, i);
+            count -= 1;
+        }
+
+        let pos = (0..count)
+            .find(|&i| (winnings, accuracy) > (self.leader_winnings[i], self.leader_accuracy[i]))
+            .unwrap_or(count);
+        if pos < LEADERBOARD_MAX_RANKS {
+            let end = (count + 1).min(LEADERBOARD_MAX_RANKS);
+            self.leaders.copy_within(pos..end - 1, pos + 1);
+            self.leader_winnings.copy_within(pos..end - 1, pos + 1);
+            self.leader_accuracy.copy_within(pos..end - 1, pos + 1);
+            self.leaders[pos] = user;
+            self.leader_winnings[pos] = winnings;
+            self.leader_accuracy[pos] = accuracy;
+            count = end;
+        }
+        self.leader_count = count as u8;
+    }
+
+    /// 1-based leaderboard rank of `user`, if they placed
+    pub fn rank_of(&self, user: &Pubkey) -> Option<u64> {
+        self.leaders[..self.ranked_count as usize]
+            .iter()
+            .position(|l| l == user)
+            .map(|i| i as u64 + 1)
+    }
+
+    /// Lock the leaderboard once the season is over
+    pub fn finalize(&mut self, now: i64) -> Result<()> {
+        require!(!self.finalized, IdlError::LeaderboardFinalized);
+        require!(now > self.end_time, IdlError::SeasonNotEnded);
+
+        self.active = false;
+        self.finalized = true;
+        self.ranked_count = self.leader_count;
+        Ok(())
+    }
+}
+
+/// Leaderboard entry for top predictors
+#[account]
+#[derive(InitSpace, Default)]
+pub struct LeaderboardEntry {
+    /// Season this entry is for
+    pub season: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Season accuracy (locked at season end)
+    pub accuracy: u64,
+    /// Season winnings
+    pub winnings: u64,
+    /// Rank (1 = first place), set when the prize is claimed
+    pub rank: u64,
+    /// Has claimed prize
+    pub prize_claimed: bool,
+    pub bump: u8,
+    /// Last snapshot timestamp
+    pub updated_at: i64,
+}
+
+/// Conviction bet - locked bet with bonus
+#[account]
+#[derive(InitSpace)]
+pub struct ConvictionBet {
+    pub owner: Pubkey,
+    pub bet: Pubkey,           // Reference to underlying Bet
+    pub market: Pubkey,
+    pub lock_duration: i64,    // How long bet is locked
+    pub lock_end: i64,         // When lock expires
+    pub bonus_bps: u64,        // Bonus percentage earned
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+/// Market creator stats - for prediction mining
+#[account]
+#[derive(InitSpace, Default)]
+pub struct CreatorStats {
+    pub creator: Pubkey,
+    /// Number of markets created
+    pub markets_created: u64,
+    /// Total volume on their markets
+    pub total_volume: u64,
+    /// Total fees earned from markets
+    pub total_fees_earned: u64,
+    /// Pending unclaimed fees
+    pub pending_fees: u64,
+    /// Last claim timestamp
+    pub last_claim: i64,
+    pub bump: u8,
+    /// Last market creation (MARKET_SPAM cooldown)
+    pub last_market_created: i64,
+}
 
-    pub token_program: Program<'info, Token>,
+impl CreatorStats {
+    /// Count a new market, unless the creator's last one was under MARKET_CREATION_COOLDOWN ago
+    pub fn record_market_created(&mut self, cfg: &ConfigParams, now: i64) -> Result<()> {
+        require!(
+            self.markets_created == 0 || now >= self.last_market_created + cfg.market_creation_cooldown,
+            IdlError::MarketCreationCooldown
+        );
+        self.markets_created = self.markets_created.saturating_add(1);
+        self.last_market_created = now;
+        Ok(())
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
-// ADVANCED MECHANICS ACCOUNTS
+// ADVANCED MECHANICS STATE - Battles, Guilds, Orders, Loot
 // ═══════════════════════════════════════════════════════════════════════════════
 
-#[derive(Accounts)]
-pub struct CreateBattle<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
-
-    pub market: Account<'info, PredictionMarket>,
-
-    #[account(
-        init,
-        payer = challenger,
-        space = 8 + PredictionBattle::INIT_SPACE,
-        seeds = [b"battle", market.key().as_ref(), challenger.key().as_ref()],
-        bump
-    )]
-    pub battle: Account<'info, PredictionBattle>,
-
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub challenger_token_account: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub challenger: Signer<'info>,
-
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+/// 1v1 Prediction Battle
+#[account]
+#[derive(InitSpace)]
+pub struct PredictionBattle {
+    /// Challenger who created the battle
+    pub challenger: Pubkey,
+    /// Opponent (zero if not yet accepted)
+    pub opponent: Pubkey,
+    /// Market this battle is for
+    pub market: Pubkey,
+    /// Stake amount each side puts in
+    pub stake_amount: u64,
+    /// Challenger's bet direction
+    pub challenger_bet_yes: bool,
+    /// Battle status: 0=pending, 1=active, 2=resolved, 3=cancelled
+    pub status: u8,
+    /// Winner (set after resolution)
+    pub winner: Pubkey,
+    /// Created timestamp
+    pub created_at: i64,
+    /// Accepted timestamp
+    pub accepted_at: i64,
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct AcceptBattle<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
-
-    #[account(mut)]
-    pub battle: Account<'info, PredictionBattle>,
-
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+/// Guild for pooled betting
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Guild {
+    /// Guild name (max 32 chars)
+    #[max_len(32)]
+    pub name: String,
+    /// Guild leader
+    pub leader: Pubkey,
+    /// Treasury vault for pooled funds
+    pub treasury: Pubkey,
+    /// Total pool shares held by members
+    pub total_pooled: u64,
+    /// Number of members
+    pub member_count: u64,
+    /// Total winnings distributed
+    pub total_winnings: u64,
+    /// Created timestamp
+    pub created_at: i64,
+    /// Is guild active
+    pub active: bool,
+    pub bump: u8,
+    /// Treasury funds currently in unsettled guild bets
+    pub amount_at_risk: u64,
+}
 
-    #[account(mut)]
-    pub opponent_token_account: Account<'info, TokenAccount>,
+impl Guild {
+    /// Pool shares bought by `contribution` at the treasury's value
+    /// Shares are only priced while no guild bets are open, so the treasury is the full guild value.
+    pub fn shares_for(&self, contribution: u64, treasury: u64) -> Result<u64> {
+        require!(self.amount_at_risk == 0, IdlError::GuildBetsOutstanding);
+        if self.total_pooled == 0 || treasury == 0 {
+            return Ok(contribution);
+        }
+        (contribution as u128)
+            .checked_mul(self.total_pooled as u128)
+            .and_then(|v| v.checked_div(treasury as u128))
+            .and_then(|v| u64::try_from(v).ok())
+            .ok_or(IdlError::MathOverflow.into())
+    }
 
-    #[account(mut)]
-    pub opponent: Signer<'info>,
+    /// Treasury value of `shares` pool shares
+    /// With bets open only the treasury's cash is shared out: the shares' claim on the open
+    /// bets stays with the remaining members, and the last shares wait for the bets to settle.
+    pub fn share_value(&self, shares: u64, treasury: u64) -> Result<u64> {
+        require!(
+            self.amount_at_risk == 0 || shares < self.total_pooled,
+            IdlError::GuildBetsOutstanding
+        );
+        (treasury as u128)
+            .checked_mul(shares as u128)
+            .and_then(|v| v.checked_div(self.total_pooled as u128))
+            .and_then(|v| u64::try_from(v).ok())
+            .ok_or(IdlError::MathOverflow.into())
+    }
+}
 
-    pub token_program: Program<'info, Token>,
+/// Guild membership
+#[account]
+#[derive(InitSpace)]
+pub struct GuildMember {
+    pub guild: Pubkey,
+    pub member: Pubkey,
+    /// Member's shares of the guild pool
+    pub contribution: u64,
+    /// Total withdrawn by this member
+    pub winnings_claimed: u64,
+    /// Joined timestamp
+    pub joined_at: i64,
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct ResolveBattle<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+/// Loot box purchase record
+#[account]
+#[derive(InitSpace, Default)]
+pub struct LootboxReward {
+    pub owner: Pubkey,
+    /// Reward type: 0=fee_discount, 1=stake_boost, 2=vip_upgrade, 3=idl_tokens
+    pub reward_type: u8,
+    /// Reward value (discount bps, boost bps, tier, or token amount)
+    pub reward_value: u64,
+    /// Expiry timestamp (0 = never)
+    pub expires_at: i64,
+    /// Is reward used
+    pub used: bool,
+    /// Lootbox tier: 0=common, 1=rare, 2=legendary
+    pub tier: u8,
+    pub bump: u8,
+    /// hash(secret) committed at purchase
+    pub commitment: [u8; 32],
+    /// Purchase slot; the roll uses the hash of the next slot
+    pub commit_slot: u64,
+    /// Reward rolled
+    pub opened: bool,
+}
 
-    #[account(mut)]
-    pub battle: Account<'info, PredictionBattle>,
+impl LootboxReward {
+    /// Opened, unexpired fee-discount reward
+    pub fn is_active_fee_discount(&self, now: i64) -> bool {
+        self.is_active(0, now)
+    }
 
-    pub market: Account<'info, PredictionMarket>,
+    /// Opened, unexpired stake-boost reward
+    pub fn is_active_stake_boost(&self, now: i64) -> bool {
+        self.is_active(1, now)
+    }
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+    /// Opened, unused one-shot reward (VIP upgrade or IDL tokens)
+    pub fn require_redeemable(&self) -> Result<()> {
+        require!(self.opened, IdlError::LootboxNotReady);
+        require!(!self.used, IdlError::AlreadyClaimed);
+        require!(matches!(self.reward_type, 2 | 3), IdlError::InvalidInput);
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub winner_token_account: Account<'info, TokenAccount>,
+    /// Effective stake added to a revealed bet of `amount` (0 unless an active stake boost)
+    pub fn stake_boost(&self, amount: u64, now: i64) -> u64 {
+        if !self.is_active_stake_boost(now) {
+            return 0;
+        }
+        (amount as u128 * self.reward_value as u128 / 10000) as u64
+    }
 
-    pub token_program: Program<'info, Token>,
+    fn is_active(&self, reward_type: u8, now: i64) -> bool {
+        self.opened
+            && self.reward_type == reward_type
+            && !self.used
+            && (self.expires_at == 0 || now < self.expires_at)
+    }
 }
 
-#[derive(Accounts)]
-pub struct CancelBattle<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
-
-    #[account(mut)]
-    pub battle: Account<'info, PredictionBattle>,
-
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub challenger_token_account: Account<'info, TokenAccount>,
+/// Limit order for conditional betting
+#[account]
+#[derive(InitSpace, Default)]
+pub struct LimitOrder {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    /// Bet amount
+    pub amount: u64,
+    /// Bet direction
+    pub bet_yes: bool,
+    /// Target odds in bps (e.g. 6000 = 60% implied probability)
+    pub target_odds_bps: u64,
+    /// Order expiry timestamp
+    pub expires_at: i64,
+    /// Is order filled
+    pub filled: bool,
+    /// Created timestamp
+    pub created_at: i64,
+    pub bump: u8,
+    /// Per-user order id (also the bet nonce once filled)
+    pub order_id: u64,
+}
 
-    pub challenger: Signer<'info>,
+impl LimitOrder {
+    /// Open, unexpired, and this side priced at or below the target probability
+    pub fn check_fill(&self, odds_bps: u64, now: i64) -> Result<()> {
+        require!(!self.filled, IdlError::OrderInactive);
+        require!(now < self.expires_at, IdlError::LimitOrderExpired);
+        require!(odds_bps <= self.target_odds_bps, IdlError::LimitPriceNotReached);
+        Ok(())
+    }
+}
 
-    pub token_program: Program<'info, Token>,
+/// Stop loss order for auto-exit
+#[account]
+#[derive(InitSpace, Default)]
+pub struct StopLoss {
+    pub owner: Pubkey,
+    pub bet: Pubkey,
+    pub market: Pubkey,
+    /// Threshold in bps of the entry odds (e.g. 5000 = exit once the side's odds halve)
+    pub threshold_bps: u64,
+    /// Is stop loss triggered
+    pub triggered: bool,
+    /// Is stop loss active
+    pub active: bool,
+    /// Created timestamp
+    pub created_at: i64,
+    pub bump: u8,
+    /// The bet side's odds when the stop loss was set
+    pub entry_odds_bps: u64,
 }
 
-#[derive(Accounts)]
-pub struct CreateGuild<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+impl StopLoss {
+    /// Odds at or below which the stop loss fires: entry odds less `threshold_bps` of them
+    pub fn trigger_odds_bps(&self) -> u64 {
+        (self.entry_odds_bps as u128 * 10000u64.saturating_sub(self.threshold_bps) as u128 / 10000) as u64
+    }
 
-    #[account(
-        init,
-        payer = leader,
-        space = 8 + Guild::INIT_SPACE,
-        seeds = [b"guild", leader.key().as_ref()],
-        bump
-    )]
-    pub guild: Account<'info, Guild>,
+    pub fn is_reached(&self, current_odds_bps: u64) -> bool {
+        current_odds_bps <= self.trigger_odds_bps()
+    }
+}
 
-    #[account(mut)]
-    pub guild_treasury: Account<'info, TokenAccount>,
+/// Market with dynamic odds
+#[account]
+#[derive(InitSpace, Default)]
+pub struct DynamicOdds {
+    pub market: Pubkey,
+    /// Current YES odds in bps (e.g. 5500 = 55%)
+    pub yes_odds_bps: u64,
+    /// Current NO odds in bps (always 10000 - yes_odds)
+    pub no_odds_bps: u64,
+    /// Last update timestamp
+    pub last_update: i64,
+    /// Total volume since last update
+    pub volume_since_update: u64,
+    pub bump: u8,
+}
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+impl DynamicOdds {
+    /// Set up a newly created odds account at the market's spot odds (no-op once initialized)
+    pub fn init_for(&mut self, cfg: &ConfigParams, market: &Account<PredictionMarket>, bump: u8, now: i64) {
+        if self.market != Pubkey::default() {
+            return;
+        }
+        self.market = market.key();
+        self.yes_odds_bps = spot_yes_odds_bps(cfg, market);
+        self.no_odds_bps = 10000 - self.yes_odds_bps;
+        self.last_update = now;
+        self.volume_since_update = 0;
+        self.bump = bump;
+    }
 
-    #[account(mut)]
-    pub leader_token_account: Account<'info, TokenAccount>,
+    /// Current odds (bps) for one side
+    pub fn odds_bps(&self, bet_yes: bool) -> u64 {
+        if bet_yes { self.yes_odds_bps } else { self.no_odds_bps }
+    }
 
-    #[account(mut)]
-    pub leader: Signer<'info>,
+    /// Record new pool volume; every ODDS_UPDATE_THRESHOLD of volume the odds move
+    /// toward spot, at most ODDS_MAX_SHIFT_BPS per update
+    pub fn record_volume(&mut self, cfg: &ConfigParams, market: &PredictionMarket, amount: u64, now: i64) {
+        self.volume_since_update = self.volume_since_update.saturating_add(amount);
+        if self.volume_since_update < cfg.odds_update_threshold {
+            return;
+        }
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+        let target = spot_yes_odds_bps(cfg, market);
+        self.yes_odds_bps = target.clamp(
+            self.yes_odds_bps.saturating_sub(cfg.odds_max_shift_bps),
+            std::cmp::min(self.yes_odds_bps + cfg.odds_max_shift_bps, 10000)
+        );
+        self.no_odds_bps = 10000 - self.yes_odds_bps;
+        self.last_update = now;
+        self.volume_since_update = 0;
+    }
 }
 
-#[derive(Accounts)]
-pub struct JoinGuild<'info> {
-    #[account(mut)]
-    pub guild: Account<'info, Guild>,
+/// Cashout position (partial exit)
+#[account]
+#[derive(InitSpace)]
+pub struct CashoutPosition {
+    pub owner: Pubkey,
+    pub bet: Pubkey,
+    pub market: Pubkey,
+    /// Original bet amount
+    pub original_amount: u64,
+    /// Amount cashed out
+    pub cashed_out_amount: u64,
+    /// Amount received after fees
+    pub received_amount: u64,
+    /// Cashout timestamp
+    pub cashed_out_at: i64,
+    pub bump: u8,
+}
 
-    #[account(
-        init,
-        payer = member,
-        space = 8 + GuildMember::INIT_SPACE,
-        seeds = [b"guild_member", guild.key().as_ref(), member.key().as_ref()],
-        bump
-    )]
-    pub guild_member: Account<'info, GuildMember>,
+// ═══════════════════════════════════════════════════════════════════════════════
+// GOVERNANCE STATE
+// ═══════════════════════════════════════════════════════════════════════════════
 
-    #[account(mut)]
-    pub guild_treasury: Account<'info, TokenAccount>,
+/// veIDL governance proposal
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    /// New parameters for UpdateConfig proposals
+    pub config_params: Option<ConfigParams>,
+    pub description_hash: [u8; 32],
+    pub created_at: i64,
+    /// Voting power is measured at this time (VOTE_SNAPSHOT_DELAY before creation)
+    pub snapshot_time: i64,
+    pub voting_ends_at: i64,
+    pub executable_at: i64,
+    /// Total votes required, fixed at creation
+    pub quorum: u64,
+    /// Share of votes in favour required, fixed at creation
+    pub pass_threshold_bps: u64,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub executed: bool,
+    pub bump: u8,
+}
 
-    #[account(mut)]
-    pub member_token_account: Account<'info, TokenAccount>,
+impl Proposal {
+    /// Quorum reached and strictly more than pass_threshold_bps of the votes in favour
+    pub fn passed(&self) -> bool {
+        let total = self.votes_for as u128 + self.votes_against as u128;
+        total > 0
+            && total >= self.quorum as u128
+            && self.votes_for as u128 * 10000 > total * self.pass_threshold_bps as u128
+    }
 
-    #[account(mut)]
-    pub member: Signer<'info>,
+    /// Voting closed, passed, timelock elapsed and not yet executed
+    pub fn require_executable(&self, now: i64) -> Result<()> {
+        require!(!self.executed, IdlError::ProposalAlreadyExecuted);
+        require!(now >= self.voting_ends_at, IdlError::ProposalVotingOpen);
+        require!(self.passed(), IdlError::ProposalNotPassed);
+        require!(now >= self.executable_at, IdlError::TimelockNotExpired);
+        Ok(())
+    }
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+/// One voter's ballot on a proposal
+#[account]
+#[derive(InitSpace)]
+pub struct ProposalVote {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct BuyLootbox<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+// ==================== TYPES ====================
 
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + LootboxReward::INIT_SPACE,
-        seeds = [b"lootbox", buyer.key().as_ref(), &Clock::get().unwrap().slot.to_le_bytes()],
-        bump
-    )]
-    pub lootbox_reward: Account<'info, LootboxReward>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub enum BadgeTier {
+    #[default]
+    None,
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+    Diamond,
+}
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+/// Actions a passed proposal may execute
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub enum GovernanceAction {
+    /// Apply the proposal's `config_params`
+    #[default]
+    UpdateConfig,
+    SetPaused(bool),
+    SetTvlCap(u64),
+    /// `recipient` is the IDL token account paid from the insurance fund
+    SpendInsurance { recipient: Pubkey, amount: u64 },
+}
 
-    #[account(mut)]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+impl GovernanceAction {
+    /// `config_params` must be present exactly when the action is UpdateConfig
+    pub fn validate(&self, config_params: Option<&ConfigParams>) -> Result<()> {
+        if *self != GovernanceAction::UpdateConfig {
+            require!(config_params.is_none(), IdlError::InvalidInput);
+        }
+        match self {
+            GovernanceAction::UpdateConfig => config_params.ok_or(IdlError::InvalidConfig)?.validate(),
+            GovernanceAction::SetPaused(_) => Ok(()),
+            GovernanceAction::SetTvlCap(cap) => {
+                require!(*cap <= MAX_TVL_CAP, IdlError::MaxTvlCapReached);
+                Ok(())
+            }
+            GovernanceAction::SpendInsurance { amount, .. } => {
+                require!(*amount > 0, IdlError::InvalidAmount);
+                Ok(())
+            }
+        }
+    }
+}
 
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default)]
+pub enum MetricType {
+    #[default]
+    Tvl,
+    Volume24h,
+    Users,
+    Transactions,
+    Price,
+    MarketCap,
+    Custom,
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+/// Economic parameters held by ProtocolConfig. Defaults mirror the constants above.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct ConfigParams {
+    // Fees
+    pub bet_fee_bps: u64,
+    pub hedge_market_fee_bps: u64,
+    pub staker_fee_share_bps: u64,
+    pub creator_fee_share_bps: u64,
+    pub treasury_fee_share_bps: u64,
+    pub burn_fee_share_bps: u64,
+    pub insurance_fee_bps: u64,
+    pub referral_fee_bps: u64,
+    pub creator_volume_fee_bps: u64,
+    pub protocol_fee_floor_bps: u64,
+    pub vip_fee_discount_bps: u64,
+
+    // Betting
+    pub min_bet_amount: u64,
+    pub max_bet_amount: u64,
+    pub min_resolution_delay: i64,
+    pub betting_close_window: i64,
+    pub claim_delay_after_resolution: i64,
+    pub bet_commit_window: i64,
+    pub bet_reveal_window: i64,
+    pub commitment_bond_amount: u64,
+    pub commitment_expiry: i64,
+    pub batch_reveal_delay: i64,
+
+    // Market creation
+    pub market_creation_cooldown: i64,
+    pub market_creation_stake: u64,
+
+    // Oracles and disputes
+    pub oracle_bond_amount: u64,
+    pub oracle_dispute_window: i64,
+    pub oracle_slash_percent: u64,
+    pub min_oracle_consensus: u8,
+    pub oracle_consensus_threshold: u8,
+    pub oracle_reveal_deadline: i64,
+    pub dispute_bond_amount: u64,
+    pub dispute_slash_if_invalid: u64,
+    pub dispute_vote_period: i64,
+    pub vote_snapshot_delay: i64,
+
+    // Staking and veIDL
+    pub min_stake_amount: u64,
+    pub min_stake_duration: i64,
+    pub large_stake_threshold: u64,
+    pub stake_commit_window: i64,
+    pub reward_claim_cooldown: i64,
+    pub min_lock_duration: i64,
+    pub max_lock_duration: i64,
+    pub tvl_cap_increment: u64,
+    pub tvl_raise_queue_window: i64,
+
+    // Badges
+    pub badge_hold_time: i64,
+    pub badge_tier_bronze: u64,
+    pub badge_tier_silver: u64,
+    pub badge_tier_gold: u64,
+    pub badge_tier_platinum: u64,
+    pub badge_tier_diamond: u64,
+    pub badge_veidl_bronze: u64,
+    pub badge_veidl_silver: u64,
+    pub badge_veidl_gold: u64,
+    pub badge_veidl_platinum: u64,
+    pub badge_veidl_diamond: u64,
+
+    // Bonuses
+    pub stake_bonus_per_million: u64,
+    pub max_stake_bonus_bps: u64,
+    pub accuracy_bonus_threshold: u64,
+    pub accuracy_bonus_bps: u64,
+    pub streak_bonus_per_win: u64,
+    pub max_streak_bonus_bps: u64,
+    pub vip_tier_1_stake: u64,
+    pub vip_tier_2_stake: u64,
+    pub vip_tier_3_stake: u64,
+    pub vip_tier_4_stake: u64,
+    pub season_duration: i64,
+    pub season_bonus_bps: u64,
+    pub season_phase_in_duration: i64,
+    pub early_bird_window: i64,
+    pub early_bird_bonus_bps: u64,
+    pub conviction_lock_min: i64,
+    pub conviction_lock_max: i64,
+    pub conviction_bonus_per_day: u64,
+
+    // Battles, guilds and lootboxes
+    pub battle_min_stake: u64,
+    pub battle_timeout: i64,
+    pub battle_platform_fee_bps: u64,
+    pub guild_min_members: u64,
+    pub guild_max_members: u64,
+    pub guild_creation_fee: u64,
+    pub guild_leader_share_bps: u64,
+    pub lootbox_common_price: u64,
+    pub lootbox_rare_price: u64,
+    pub lootbox_legendary_price: u64,
+    pub lootbox_burn_percent: u64,
+
+    // Odds, cashouts and orders
+    pub odds_update_threshold: u64,
+    pub odds_max_shift_bps: u64,
+    pub odds_base_yes: u64,
+    pub cashout_fee_bps: u64,
+    pub cashout_min_time: i64,
+    pub limit_order_expiry: i64,
+    pub limit_order_fee_bps: u64,
+    pub stop_loss_check_interval: i64,
+    pub stop_loss_min_threshold: u64,
+    pub stop_loss_keeper_reward_bps: u64,
+
+    // Governance
+    pub proposal_min_voting_power: u64,
+    pub proposal_voting_period: i64,
+    pub proposal_quorum_bps: u64,
+    pub proposal_pass_threshold_bps: u64,
+    pub proposal_execution_delay: i64,
 }
 
-#[derive(Accounts)]
-pub struct CreateLimitOrder<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+impl Default for ConfigParams {
+    fn default() -> Self {
+        Self {
+            // Fees
+            bet_fee_bps: BET_FEE_BPS,
+            hedge_market_fee_bps: HEDGE_MARKET_FEE_BPS,
+            staker_fee_share_bps: STAKER_FEE_SHARE_BPS,
+            creator_fee_share_bps: CREATOR_FEE_SHARE_BPS,
+            treasury_fee_share_bps: TREASURY_FEE_SHARE_BPS,
+            burn_fee_share_bps: BURN_FEE_SHARE_BPS,
+            insurance_fee_bps: INSURANCE_FEE_BPS,
+            referral_fee_bps: REFERRAL_FEE_BPS,
+            creator_volume_fee_bps: CREATOR_VOLUME_FEE_BPS,
+            protocol_fee_floor_bps: PROTOCOL_FEE_FLOOR_BPS,
+            vip_fee_discount_bps: VIP_FEE_DISCOUNT_BPS,
+
+            // Betting
+            min_bet_amount: MIN_BET_AMOUNT,
+            max_bet_amount: MAX_BET_AMOUNT,
+            min_resolution_delay: MIN_RESOLUTION_DELAY,
+            betting_close_window: BETTING_CLOSE_WINDOW,
+            claim_delay_after_resolution: CLAIM_DELAY_AFTER_RESOLUTION,
+            bet_commit_window: BET_COMMIT_WINDOW,
+            bet_reveal_window: BET_REVEAL_WINDOW,
+            commitment_bond_amount: COMMITMENT_BOND_AMOUNT,
+            commitment_expiry: COMMITMENT_EXPIRY,
+            batch_reveal_delay: BATCH_REVEAL_DELAY,
+
+            // Market creation
+            market_creation_cooldown: MARKET_CREATION_COOLDOWN,
+            market_creation_stake: MARKET_CREATION_STAKE,
+
+            // Oracles and disputes
+            oracle_bond_amount: ORACLE_BOND_AMOUNT,
+            oracle_dispute_window: ORACLE_DISPUTE_WINDOW,
+            oracle_slash_percent: ORACLE_SLASH_PERCENT,
+            min_oracle_consensus: MIN_ORACLE_CONSENSUS,
+            oracle_consensus_threshold: ORACLE_CONSENSUS_THRESHOLD,
+            oracle_reveal_deadline: ORACLE_REVEAL_DEADLINE,
+            dispute_bond_amount: DISPUTE_BOND_AMOUNT,
+            dispute_slash_if_invalid: DISPUTE_SLASH_IF_INVALID,
+            dispute_vote_period: DISPUTE_VOTE_PERIOD,
+            vote_snapshot_delay: VOTE_SNAPSHOT_DELAY,
+
+            // Staking and veIDL
+            min_stake_amount: MIN_STAKE_AMOUNT,
+            min_stake_duration: MIN_STAKE_DURATION,
+            large_stake_threshold: LARGE_STAKE_THRESHOLD,
+            stake_commit_window: STAKE_COMMIT_WINDOW,
+            reward_claim_cooldown: REWARD_CLAIM_COOLDOWN,
+            min_lock_duration: MIN_LOCK_DURATION,
+            max_lock_duration: MAX_LOCK_DURATION,
+            tvl_cap_increment: TVL_CAP_INCREMENT,
+            tvl_raise_queue_window: TVL_RAISE_QUEUE_WINDOW,
+
+            // Badges
+            badge_hold_time: BADGE_HOLD_TIME,
+            badge_tier_bronze: BADGE_TIER_BRONZE,
+            badge_tier_silver: BADGE_TIER_SILVER,
+            badge_tier_gold: BADGE_TIER_GOLD,
+            badge_tier_platinum: BADGE_TIER_PLATINUM,
+            badge_tier_diamond: BADGE_TIER_DIAMOND,
+            badge_veidl_bronze: BADGE_VEIDL_BRONZE,
+            badge_veidl_silver: BADGE_VEIDL_SILVER,
+            badge_veidl_gold: BADGE_VEIDL_GOLD,
+            badge_veidl_platinum: BADGE_VEIDL_PLATINUM,
+            badge_veidl_diamond: BADGE_VEIDL_DIAMOND,
+
+            // Bonuses
+            stake_bonus_per_million: STAKE_BONUS_PER_MILLION,
+            max_stake_bonus_bps: MAX_STAKE_BONUS_BPS,
+            accuracy_bonus_threshold: ACCURACY_BONUS_THRESHOLD,
+            accuracy_bonus_bps: ACCURACY_BONUS_BPS,
+            streak_bonus_per_win: STREAK_BONUS_PER_WIN,
+            max_streak_bonus_bps: MAX_STREAK_BONUS_BPS,
+            vip_tier_1_stake: VIP_TIER_1_STAKE,
+            vip_tier_2_stake: VIP_TIER_2_STAKE,
+            vip_tier_3_stake: VIP_TIER_3_STAKE,
+            vip_tier_4_stake: VIP_TIER_4_STAKE,
+            season_duration: SEASON_DURATION,
+            season_bonus_bps: SEASON_BONUS_BPS,
+            season_phase_in_duration: SEASON_PHASE_IN_DURATION,
+            early_bird_window: EARLY_BIRD_WINDOW,
+            early_bird_bonus_bps: EARLY_BIRD_BONUS_BPS,
+            conviction_lock_min: CONVICTION_LOCK_MIN,
+            conviction_lock_max: CONVICTION_LOCK_MAX,
+            conviction_bonus_per_day: CONVICTION_BONUS_PER_DAY,
+
+            // Battles, guilds and lootboxes
+            battle_min_stake: BATTLE_MIN_STAKE,
+            battle_timeout: BATTLE_TIMEOUT,
+            battle_platform_fee_bps: BATTLE_PLATFORM_FEE_BPS,
+            guild_min_members: GUILD_MIN_MEMBERS,
+            guild_max_members: GUILD_MAX_MEMBERS,
+            guild_creation_fee: GUILD_CREATION_FEE,
+            guild_leader_share_bps: GUILD_LEADER_SHARE_BPS,
+            lootbox_common_price: LOOTBOX_COMMON_PRICE,
+            lootbox_rare_price: LOOTBOX_RARE_PRICE,
+            lootbox_legendary_price: LOOTBOX_LEGENDARY_PRICE,
+            lootbox_burn_percent: LOOTBOX_BURN_PERCENT,
+
+            // Odds, cashouts and orders
+            odds_update_threshold: ODDS_UPDATE_THRESHOLD,
+            odds_max_shift_bps: ODDS_MAX_SHIFT_BPS,
+            odds_base_yes: ODDS_BASE_YES,
+            cashout_fee_bps: CASHOUT_FEE_BPS,
+            cashout_min_time: CASHOUT_MIN_TIME,
+            limit_order_expiry: LIMIT_ORDER_EXPIRY,
+            limit_order_fee_bps: LIMIT_ORDER_FEE_BPS,
+            stop_loss_check_interval: STOP_LOSS_CHECK_INTERVAL,
+            stop_loss_min_threshold: STOP_LOSS_MIN_THRESHOLD,
+            stop_loss_keeper_reward_bps: STOP_LOSS_KEEPER_REWARD_BPS,
+
+            // Governance
+            proposal_min_voting_power: PROPOSAL_MIN_VOTING_POWER,
+            proposal_voting_period: PROPOSAL_VOTING_PERIOD,
+            proposal_quorum_bps: PROPOSAL_QUORUM_BPS,
+            proposal_pass_threshold_bps: PROPOSAL_PASS_THRESHOLD_BPS,
+            proposal_execution_delay: PROPOSAL_EXECUTION_DELAY,
+        }
+    }
+}
 
-    pub market: Account<'info, PredictionMarket>,
+impl ConfigParams {
+    /// Reject parameter sets that would break fee accounting or the bet/oracle timelines
+    pub fn validate(&self) -> Result<()> {
+        // Proposed values are arbitrary u64s; sums that overflow are rejected, not wrapped
+        let fee_shares = self.staker_fee_share_bps
+            .checked_add(self.creator_fee_share_bps)
+            .and_then(|v| v.checked_add(self.treasury_fee_share_bps))
+            .and_then(|v| v.checked_add(self.burn_fee_share_bps))
+            .ok_or(IdlError::InvalidConfig)?;
+        require!(fee_shares == 10000, IdlError::InvalidConfig);
+
+        let market_fee_bps = self.bet_fee_bps
+            .checked_add(self.hedge_market_fee_bps)
+            .ok_or(IdlError::InvalidConfig)?;
+        // Discount at the top VIP tier
+        let max_vip_discount_bps = self.vip_fee_discount_bps
+            .checked_mul(4)
+            .ok_or(IdlError::InvalidConfig)?;
+        for bps in [
+            market_fee_bps,
+            self.insurance_fee_bps,
+            self.referral_fee_bps,
+            self.creator_volume_fee_bps,
+            self.protocol_fee_floor_bps,
+            max_vip_discount_bps,
+            self.max_stake_bonus_bps,
+            self.accuracy_bonus_bps,
+            self.max_streak_bonus_bps,
+            self.season_bonus_bps,
+            self.early_bird_bonus_bps,
+            self.battle_platform_fee_bps,
+            self.guild_leader_share_bps,
+            self.odds_max_shift_bps,
+            self.cashout_fee_bps,
+            self.limit_order_fee_bps,
+            self.stop_loss_min_threshold,
+            self.stop_loss_keeper_reward_bps,
+            self.proposal_quorum_bps,
+            self.proposal_pass_threshold_bps,
+        ] {
+            require!(bps <= 10000, IdlError::InvalidConfig);
+        }
+        for percent in [
+            self.oracle_slash_percent,
+            self.dispute_slash_if_invalid,
+            self.accuracy_bonus_threshold,
+            self.lootbox_burn_percent,
+        ] {
+            require!(percent <= 100, IdlError::InvalidConfig);
+        }
+        require!(self.odds_base_yes > 0 && self.odds_base_yes < 10000, IdlError::InvalidConfig);
+        require!(
+            self.oracle_consensus_threshold > 0 && self.oracle_consensus_threshold <= 100,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.min_oracle_consensus > 0 && self.min_oracle_consensus <= MAX_ORACLES_PER_MARKET,
+            IdlError::InvalidConfig
+        );
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + LimitOrder::INIT_SPACE,
-        seeds = [b"limit_order", market.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub limit_order: Account<'info, LimitOrder>,
+        require!(
+            self.min_bet_amount > 0 && self.min_bet_amount <= self.max_bet_amount,
+            IdlError::InvalidConfig
+        );
+        require!(self.min_stake_amount > 0, IdlError::InvalidConfig);
+        require!(
+            self.min_lock_duration > 0 && self.min_lock_duration <= self.max_lock_duration,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.conviction_lock_min > 0 && self.conviction_lock_min <= self.conviction_lock_max,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.guild_min_members > 0 && self.guild_min_members <= self.guild_max_members,
+            IdlError::InvalidConfig
+        );
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+        // Commit -> reveal -> expiry must stay ordered or bets can never be revealed
+        require!(
+            self.bet_commit_window >= 0
+                && self.bet_commit_window < self.bet_reveal_window
+                && self.bet_reveal_window <= self.commitment_expiry,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.stake_commit_window >= 0 && self.stake_commit_window < self.commitment_expiry,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.betting_close_window >= 0 && self.min_resolution_delay > self.betting_close_window,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.season_duration > 0 && self.season_phase_in_duration > 0,
+            IdlError::InvalidConfig
+        );
+        // A sub-majority threshold would let the losing side of a vote pass proposals
+        require!(
+            self.proposal_voting_period > 0
+                && self.proposal_execution_delay >= 0
+                && self.proposal_pass_threshold_bps >= 5000,
+            IdlError::InvalidConfig
+        );
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+        require!(
+            self.badge_tier_bronze < self.badge_tier_silver
+                && self.badge_tier_silver < self.badge_tier_gold
+                && self.badge_tier_gold < self.badge_tier_platinum
+                && self.badge_tier_platinum < self.badge_tier_diamond,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.vip_tier_1_stake < self.vip_tier_2_stake
+                && self.vip_tier_2_stake < self.vip_tier_3_stake
+                && self.vip_tier_3_stake < self.vip_tier_4_stake,
+            IdlError::InvalidConfig
+        );
+        require!(
+            self.lootbox_common_price > 0
+                && self.lootbox_common_price < self.lootbox_rare_price
+                && self.lootbox_rare_price < self.lootbox_legendary_price,
+            IdlError::InvalidConfig
+        );
+        Ok(())
+    }
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+// ==================== EVENTS ====================
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct ProtocolInitialized {
+    pub version: u8,
+    pub authority: Pubkey,
+    pub idl_mint: Pubkey,
+    pub vault: Pubkey,
+    pub burn_vault: Pubkey,
+    pub tvl_cap: u64,
 }
 
-#[derive(Accounts)]
-pub struct CancelLimitOrder<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
-
-    #[account(mut)]
-    pub limit_order: Account<'info, LimitOrder>,
+/// Tokens added to a staker's balance (direct, revealed or TVL-raise allocation)
+#[event]
+pub struct Staked {
+    pub version: u8,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub staked_amount: u64,
+    pub total_staked: u64,
+}
 
-    #[account(mut, seeds = [b"vault"], bump = state.vault_bump)]
-    pub vault: Account<'info, TokenAccount>,
+#[event]
+pub struct StakeCommitted {
+    pub version: u8,
+    pub user: Pubkey,
+    pub reveal_after: i64,
+}
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct Unstaked {
+    pub version: u8,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub staked_amount: u64,
+    pub total_staked: u64,
+}
 
-    pub user: Signer<'info>,
+#[event]
+pub struct VeLocked {
+    pub version: u8,
+    pub user: Pubkey,
+    pub locked_stake: u64,
+    pub initial_ve_amount: u64,
+    pub lock_end: i64,
+    pub total_ve_supply: u64,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct VeUnlocked {
+    pub version: u8,
+    pub user: Pubkey,
+    pub locked_stake: u64,
+    pub total_ve_supply: u64,
 }
 
-#[derive(Accounts)]
-pub struct SetStopLoss<'info> {
-    pub bet: Account<'info, Bet>,
+#[event]
+pub struct VeLockExtended {
+    pub version: u8,
+    pub user: Pubkey,
+    pub initial_ve_amount: u64,
+    pub lock_end: i64,
+    pub total_ve_supply: u64,
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + StopLoss::INIT_SPACE,
-        seeds = [b"stop_loss", bet.key().as_ref()],
-        bump
-    )]
-    pub stop_loss: Account<'info, StopLoss>,
+#[event]
+pub struct MarketCreated {
+    pub version: u8,
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub market_type: u8,
+    pub protocol_id: String,
+    pub resolution_timestamp: i64,
+    pub creation_stake: u64,
+    pub parent_market: Option<Pubkey>,
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+/// `creation_stake_slashed` is non-zero only when cancelled as spam
+#[event]
+pub struct MarketCancelled {
+    pub version: u8,
+    pub market: Pubkey,
+    pub creation_stake_slashed: u64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct MarketResolved {
+    pub version: u8,
+    pub market: Pubkey,
+    pub outcome: Option<bool>,
+    pub winning_outcome: Option<u8>,
+    pub actual_value: Option<u64>,
+    pub resolved_at: i64,
 }
 
-#[derive(Accounts)]
-pub struct PartialCashout<'info> {
-    #[account(seeds = [b"state"], bump = state.bump)]
-    pub state: Account<'info, ProtocolState>,
+#[event]
+pub struct CreationStakeReclaimed {
+    pub version: u8,
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
 
-    pub bet: Account<'info, Bet>,
+#[event]
+pub struct RevealBatchUnveiled {
+    pub version: u8,
+    pub market: Pubkey,
+    pub total_yes_actual: u64,
+    pub total_no_actual: u64,
+}
 
-    pub market: Account<'info, PredictionMarket>,
+#[event]
+pub struct RefundClaimed {
+    pub version: u8,
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(mut)]
-    pub market_pool: Account<'info, TokenAccount>,
+#[event]
+pub struct BetLost {
+    pub version: u8,
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + CashoutPosition::INIT_SPACE,
-        seeds = [b"cashout", bet.key().as_ref()],
-        bump
-    )]
-    pub cashout_position: Account<'info, CashoutPosition>,
+/// Winning claim with the full fee breakdown. `fee` is the sum of the five fee shares;
+/// `referral_fee` is credited to the referrer on top, out of the staker share.
+#[event]
+pub struct WinningsClaimed {
+    pub version: u8,
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub bet_amount: u64,
+    pub gross_winnings: u64,
+    pub fee_bps: u64,
+    pub fee: u64,
+    pub staker_fee: u64,
+    pub creator_fee: u64,
+    pub treasury_fee: u64,
+    pub burn_fee: u64,
+    pub insurance_fee: u64,
+    pub referral_fee: u64,
+    pub conviction_bonus: u64,
+    pub record_bonus: u64,
+    pub season_bonus: u64,
+    pub net_winnings: u64,
+    pub restaked: u64,
+}
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct StakingRewardsClaimed {
+    pub version: u8,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub restaked: u64,
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+#[event]
+pub struct StakingRewardsCompounded {
+    pub version: u8,
+    pub user: Pubkey,
+    pub keeper: Pubkey,
+    pub restaked: u64,
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct BadgeIssued {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub tier: BadgeTier,
+    pub ve_amount: u64,
+    pub volume_usd: u64,
 }
 
-#[derive(Accounts)]
-pub struct InitDynamicOdds<'info> {
-    pub market: Account<'info, PredictionMarket>,
+#[event]
+pub struct BadgeRevoked {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub ve_amount: u64,
+}
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + DynamicOdds::INIT_SPACE,
-        seeds = [b"dynamic_odds", market.key().as_ref()],
-        bump
-    )]
-    pub dynamic_odds: Account<'info, DynamicOdds>,
+#[event]
+pub struct ProtocolPaused {
+    pub version: u8,
+    pub paused: bool,
+}
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[event]
+pub struct AccountMigrated {
+    pub version: u8,
+    pub account: Pubkey,
+    pub old_len: u64,
+    pub new_len: u64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct AuthorityTransferInitiated {
+    pub version: u8,
+    pub current_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub executable_at: i64,
 }
 
-// ==================== STATE ====================
+#[event]
+pub struct AuthorityTransferred {
+    pub version: u8,
+    pub new_authority: Pubkey,
+}
 
-#[account]
-#[derive(InitSpace)]
-pub struct ProtocolState {
+#[event]
+pub struct AuthorityTransferCancelled {
+    pub version: u8,
     pub authority: Pubkey,
-    pub treasury: Pubkey,
-    pub idl_mint: Pubkey,
-    pub vault: Pubkey,
-    pub burn_vault: Pubkey,  // Added for multi-step init
-    pub total_staked: u64,
-    pub total_ve_supply: u64,
-    pub reward_pool: u64,
-    pub total_fees_collected: u64,
-    pub total_burned: u64,
-    pub bump: u8,
-    pub vault_bump: u8,
-    pub burn_vault_bump: u8,  // Added for multi-step init
-    pub paused: bool,
-    // SECURITY FIX: Reward checkpoint for proper distribution
-    pub reward_per_token_stored: u128,  // Scaled by 1e18 for precision
-    pub last_reward_update: i64,
-    // RICK FIX: Authority timelock
-    pub pending_authority: Option<Pubkey>,
-    pub authority_transfer_time: Option<i64>,
-    // TIER 3: TVL cap (gradual rollout)
-    pub tvl_cap: u64,
-    // TIER 3: Insurance fund
-    pub insurance_fund: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct StakerAccount {
-    pub owner: Pubkey,
-    pub staked_amount: u64,
-    pub last_stake_timestamp: i64,
-    pub rewards_claimed: u64,               // Track total rewards claimed
-    pub reward_per_token_paid: u128,        // SECURITY FIX: Checkpoint per staker
-    pub pending_rewards: u64,               // Unclaimed rewards
-    pub last_reward_claim: i64,             // RICK FIX: Cooldown timestamp
-    pub bump: u8,
+#[event]
+pub struct ConfigUpdateProposed {
+    pub version: u8,
+    pub params: ConfigParams,
+    pub executable_at: i64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct VePosition {
-    pub owner: Pubkey,
-    pub locked_stake: u64,
-    pub initial_ve_amount: u64,  // RICK FIX: Renamed - this is veIDL at lock time
-    pub lock_start: i64,
-    pub lock_end: i64,
-    pub lock_duration: i64,      // RICK FIX: Store original duration for decay calc
-    pub bump: u8,
+#[event]
+pub struct ConfigUpdateApplied {
+    pub version: u8,
+    pub params: ConfigParams,
 }
 
-impl VePosition {
-    /// RICK FIX: Calculate current veIDL with linear decay
-    /// Whitepaper: "Current veIDL = Initial veIDL * (Time Remaining / Lock Duration)"
-    pub fn current_ve_amount(&self, current_time: i64) -> u64 {
-        if current_time >= self.lock_end {
-            return 0;
-        }
-        if current_time <= self.lock_start {
-            return self.initial_ve_amount;
-        }
+#[event]
+pub struct ConfigUpdateCancelled {
+    pub version: u8,
+}
 
-        let time_remaining = self.lock_end.saturating_sub(current_time);
+#[event]
+pub struct TvlRaiseOpened {
+    pub version: u8,
+    pub raise_id: u64,
+    pub new_cap: u64,
+    pub headroom: u64,
+    pub queue_ends_at: i64,
+}
 
-        // current = initial * (remaining / duration)
-        (self.initial_ve_amount as u128)
-            .saturating_mul(time_remaining as u128)
-            .checked_div(self.lock_duration as u128)
-            .map(|v| v as u64)
-            .unwrap_or(0)
-    }
+#[event]
+pub struct TvlRaiseJoined {
+    pub version: u8,
+    pub raise_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct PredictionMarket {
-    pub creator: Pubkey,
-    #[max_len(32)]
-    pub protocol_id: String,
-    pub metric_type: MetricType,
-    pub target_value: u64,
-    pub resolution_timestamp: i64,
-    #[max_len(200)]
-    pub description: String,
-    // SECURITY FIX: Track ACTUAL tokens deposited (not effective amounts)
-    pub total_yes_actual: u64,      // Real tokens deposited on YES
-    pub total_no_actual: u64,       // Real tokens deposited on NO
-    // Effective amounts for payout calculation only
-    pub total_yes_amount: u64,      // Weighted YES (with staker bonus)
-    pub total_no_amount: u64,       // Weighted NO (with staker bonus)
-    pub resolved: bool,
-    pub resolved_at: Option<i64>,
-    pub outcome: Option<bool>,
-    pub actual_value: Option<u64>,
-    pub oracle: Pubkey,
-    pub created_at: i64,
-    pub bump: u8,
-    // SECURITY FIX: Market status for cancellation
-    pub status: u8,                 // 0=active, 1=resolved, 2=cancelled
-    // ATTACK FIX: Multi-oracle consensus support
-    pub oracle_count: u8,           // Number of registered oracles
-    pub oracle_votes_yes: u8,       // Oracles that voted YES
-    pub oracle_votes_no: u8,        // Oracles that voted NO
+#[event]
+pub struct TvlRaiseEntrySettled {
+    pub version: u8,
+    pub raise_id: u64,
+    pub user: Pubkey,
+    pub staked: u64,
+    pub refunded: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct Bet {
-    pub owner: Pubkey,
+#[event]
+pub struct InsuranceWithdrawn {
+    pub version: u8,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub insurance_fund: u64,
+}
+
+/// Commit-reveal bet commitment. `bettor` is the guild for guild bets.
+#[event]
+pub struct BetCommitted {
+    pub version: u8,
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub bond_amount: u64,
+    pub reveal_deadline: i64,
+}
+
+#[event]
+pub struct CommitmentBondReleased {
+    pub version: u8,
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub bond_amount: u64,
+    pub forfeited: bool,
+}
+
+/// Revealed bet. `amount` is net of the creator volume fee; `bettor` is the guild for guild bets.
+#[event]
+pub struct BetRevealed {
+    pub version: u8,
     pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub outcome_index: u8,
     pub amount: u64,
     pub effective_amount: u64,
-    pub bet_yes: bool,
-    pub timestamp: i64,
-    pub claimed: bool,
-    pub nonce: u64,
-    pub bump: u8,
+    pub creator_fee: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct VolumeBadge {
-    pub owner: Pubkey,
-    pub tier: BadgeTier,
-    pub volume_usd: u64,
-    pub ve_amount: u64,
-    pub issued_at: i64,
-    pub bump: u8,
+#[event]
+pub struct OracleBondDeposited {
+    pub version: u8,
+    pub oracle: Pubkey,
+    pub amount: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct UserVolume {
-    pub user: Pubkey,
-    pub total_volume_usd: u64,
-    pub last_updated: i64,
-    pub bump: u8,
+#[event]
+pub struct OracleRegistered {
+    pub version: u8,
+    pub market: Pubkey,
+    pub oracle: Pubkey,
+    pub oracle_count: u8,
 }
 
-// 10/10 FIX: Commit-reveal bet to prevent front-running
-#[account]
-#[derive(InitSpace)]
-pub struct BetCommitment {
-    pub owner: Pubkey,
+#[event]
+pub struct ResolutionCommitted {
+    pub version: u8,
     pub market: Pubkey,
-    pub commitment: [u8; 32],  // hash(amount, bet_yes, nonce, salt)
-    pub commit_time: i64,
-    pub revealed: bool,
-    pub bump: u8,
+    pub oracle: Pubkey,
 }
 
-// 10/10 FIX: Oracle bond for accountability
-#[account]
-#[derive(InitSpace)]
-pub struct OracleBond {
+/// Oracle reveal. `value_b` is only set for comparison markets.
+#[event]
+pub struct ResolutionRevealed {
+    pub version: u8,
+    pub market: Pubkey,
     pub oracle: Pubkey,
-    pub bond_amount: u64,
-    pub bonded_at: i64,
-    pub slashed: bool,
-    pub bump: u8,
-    // SELF-REVIEW FIX: Track active resolutions to prevent multi-market exploit
-    // Oracle can only have ONE pending resolution at a time
-    pub active_resolution: Option<Pubkey>,  // Market key if resolution pending
+    pub value: u64,
+    pub value_b: u64,
 }
 
-// 10/10 FIX: Resolution commitment for oracle commit-reveal
-#[account]
-#[derive(InitSpace)]
-pub struct ResolutionCommitment {
+#[event]
+pub struct OracleSlashed {
+    pub version: u8,
     pub market: Pubkey,
     pub oracle: Pubkey,
-    pub commitment: [u8; 32],  // hash(actual_value, nonce)
-    pub commit_time: i64,
-    pub revealed: bool,
-    pub disputed: bool,
-    pub bump: u8,
+    pub amount: u64,
 }
 
-// ═══════════════════════════════════════════════════════════════════════════════
-// PUMP MECHANICS STATE - New tokenomics structures
-// ═══════════════════════════════════════════════════════════════════════════════
+#[event]
+pub struct DisputeOpened {
+    pub version: u8,
+    pub market: Pubkey,
+    pub dispute: Pubkey,
+    pub disputer: Pubkey,
+    pub bond_amount: u64,
+    pub snapshot_time: i64,
+    pub voting_ends_at: i64,
+}
 
-/// Referral relationship - who referred who
-#[account]
-#[derive(InitSpace)]
-pub struct ReferralAccount {
-    /// The user who was referred
-    pub user: Pubkey,
-    /// The referrer who gets fees
-    pub referrer: Pubkey,
-    /// Total fees earned by referrer from this user (historical)
-    pub total_fees_earned: u64,
-    /// SECURITY FIX: Pending fees available to claim
-    pub pending_fees: u64,
-    /// Timestamp of referral registration
-    pub registered_at: i64,
-    pub bump: u8,
+#[event]
+pub struct DisputeVoteCast {
+    pub version: u8,
+    pub dispute: Pubkey,
+    pub voter: Pubkey,
+    pub choice: u8,
+    pub weight: u64,
 }
 
-/// User prediction statistics for accuracy tracking
-#[account]
-#[derive(InitSpace)]
-pub struct PredictorStats {
-    pub owner: Pubkey,
-    /// Total predictions made
-    pub total_predictions: u64,
-    /// Correct predictions
-    pub correct_predictions: u64,
-    /// Current win streak
-    pub current_streak: u64,
-    /// Best ever streak
-    pub best_streak: u64,
-    /// Total winnings
-    pub total_winnings: u64,
-    /// Total losses
-    pub total_losses: u64,
-    /// Last prediction timestamp
-    pub last_prediction: i64,
-    /// Auto-compound enabled
-    pub auto_compound: bool,
-    /// VIP tier based on stake
-    pub vip_tier: u8,
-    pub bump: u8,
+#[event]
+pub struct DisputeSettled {
+    pub version: u8,
+    pub market: Pubkey,
+    pub dispute: Pubkey,
+    pub result: u8,
+    pub votes_uphold: u64,
+    pub votes_overturn: u64,
+    pub votes_cancel: u64,
+    pub disputer_slashed: u64,
+    pub oracles_slashed: u64,
+}
+
+#[event]
+pub struct OracleBondWithdrawn {
+    pub version: u8,
+    pub oracle: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
 }
 
-impl PredictorStats {
-    /// Calculate accuracy percentage (0-100)
-    pub fn accuracy(&self) -> u64 {
-        if self.total_predictions == 0 {
-            return 0;
-        }
-        (self.correct_predictions * 100) / self.total_predictions
-    }
+#[event]
+pub struct OracleBondReleased {
+    pub version: u8,
+    pub oracle: Pubkey,
+    pub market: Pubkey,
+}
 
-    /// Calculate streak bonus in basis points
-    pub fn streak_bonus_bps(&self) -> u64 {
-        std::cmp::min(
-            self.current_streak * STREAK_BONUS_PER_WIN,
-            MAX_STREAK_BONUS_BPS
-        )
-    }
+#[event]
+pub struct ReferralRegistered {
+    pub version: u8,
+    pub user: Pubkey,
+    pub referrer: Pubkey,
+}
 
-    /// Calculate VIP tier from stake amount
-    pub fn calculate_vip_tier(stake: u64) -> u8 {
-        if stake >= VIP_TIER_4_STAKE {
-            4
-        } else if stake >= VIP_TIER_3_STAKE {
-            3
-        } else if stake >= VIP_TIER_2_STAKE {
-            2
-        } else if stake >= VIP_TIER_1_STAKE {
-            1
-        } else {
-            0
-        }
-    }
+#[event]
+pub struct AutoCompoundSet {
+    pub version: u8,
+    pub user: Pubkey,
+    pub enabled: bool,
+}
 
-    /// Calculate fee discount based on VIP tier
-    pub fn vip_fee_discount_bps(&self) -> u64 {
-        (self.vip_tier as u64) * VIP_FEE_DISCOUNT_BPS
-    }
+#[event]
+pub struct PredictorBonusesFunded {
+    pub version: u8,
+    pub amount: u64,
+    pub predictor_bonus_pool: u64,
 }
 
-/// Season state for time-limited reward boosts
-#[account]
-#[derive(InitSpace)]
-pub struct Season {
-    /// Season number (1, 2, 3, ...)
+#[event]
+pub struct VipTierUpdated {
+    pub version: u8,
+    pub user: Pubkey,
+    pub vip_tier: u8,
+}
+
+#[event]
+pub struct SeasonCreated {
+    pub version: u8,
+    pub season: Pubkey,
     pub season_number: u64,
-    /// Start timestamp
-    pub start_time: i64,
-    /// End timestamp
+    pub prize_pool: u64,
     pub end_time: i64,
-    /// Total rewards allocated for this season
+}
+
+#[event]
+pub struct SeasonFunded {
+    pub version: u8,
+    pub season: Pubkey,
+    pub amount: u64,
     pub total_rewards: u64,
-    /// Rewards already distributed
-    pub distributed_rewards: u64,
-    /// Is season currently active
-    pub active: bool,
-    /// Prize pool for top predictors
-    pub prize_pool: u64,
-    pub bump: u8,
 }
 
-/// Leaderboard entry for top predictors
-#[account]
-#[derive(InitSpace)]
-pub struct LeaderboardEntry {
-    /// Season this entry is for
+#[event]
+pub struct SeasonEnded {
+    pub version: u8,
+    pub season: Pubkey,
+    pub entry_count: u64,
+    pub ranked_count: u8,
+}
+
+#[event]
+pub struct LeaderboardPrizeClaimed {
+    pub version: u8,
     pub season: Pubkey,
-    /// User address
     pub user: Pubkey,
-    /// Season accuracy (locked at season end)
-    pub accuracy: u64,
-    /// Season winnings
-    pub winnings: u64,
-    /// Rank (1 = first place)
     pub rank: u64,
-    /// Has claimed prize
-    pub prize_claimed: bool,
-    pub bump: u8,
+    pub prize: u64,
 }
 
-/// Conviction bet - locked bet with bonus
-#[account]
-#[derive(InitSpace)]
-pub struct ConvictionBet {
-    pub owner: Pubkey,
-    pub bet: Pubkey,           // Reference to underlying Bet
-    pub market: Pubkey,
-    pub lock_duration: i64,    // How long bet is locked
-    pub lock_end: i64,         // When lock expires
-    pub bonus_bps: u64,        // Bonus percentage earned
-    pub claimed: bool,
-    pub bump: u8,
+#[event]
+pub struct CreatorFeesClaimed {
+    pub version: u8,
+    pub creator: Pubkey,
+    pub amount: u64,
 }
 
-/// Market creator stats - for prediction mining
-#[account]
-#[derive(InitSpace)]
-pub struct CreatorStats {
-    pub creator: Pubkey,
-    /// Number of markets created
-    pub markets_created: u64,
-    /// Total volume on their markets
-    pub total_volume: u64,
-    /// Total fees earned from markets
-    pub total_fees_earned: u64,
-    /// Pending unclaimed fees
-    pub pending_fees: u64,
-    /// Last claim timestamp
-    pub last_claim: i64,
-    pub bump: u8,
+#[event]
+pub struct ConvictionBetPlaced {
+    pub version: u8,
+    pub market: Pubkey,
+    pub bet: Pubkey,
+    pub user: Pubkey,
+    pub lock_end: i64,
+    pub bonus_bps: u64,
 }
 
-// ═══════════════════════════════════════════════════════════════════════════════
-// ADVANCED MECHANICS STATE - Battles, Guilds, Orders, Loot
-// ═══════════════════════════════════════════════════════════════════════════════
+#[event]
+pub struct ReferralFeesClaimed {
+    pub version: u8,
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
 
-/// 1v1 Prediction Battle
-#[account]
-#[derive(InitSpace)]
-pub struct PredictionBattle {
-    /// Challenger who created the battle
-    pub challenger: Pubkey,
-    /// Opponent (zero if not yet accepted)
-    pub opponent: Pubkey,
-    /// Market this battle is for
+#[event]
+pub struct BattleCreated {
+    pub version: u8,
+    pub battle: Pubkey,
     pub market: Pubkey,
-    /// Stake amount each side puts in
+    pub challenger: Pubkey,
     pub stake_amount: u64,
-    /// Challenger's bet direction
     pub challenger_bet_yes: bool,
-    /// Battle status: 0=pending, 1=active, 2=resolved, 3=cancelled
-    pub status: u8,
-    /// Winner (set after resolution)
+}
+
+#[event]
+pub struct BattleAccepted {
+    pub version: u8,
+    pub battle: Pubkey,
+    pub opponent: Pubkey,
+}
+
+#[event]
+pub struct BattleResolved {
+    pub version: u8,
+    pub battle: Pubkey,
+    pub market: Pubkey,
     pub winner: Pubkey,
-    /// Created timestamp
-    pub created_at: i64,
-    /// Accepted timestamp
-    pub accepted_at: i64,
-    pub bump: u8,
+    pub payout: u64,
+    pub platform_fee: u64,
 }
 
-/// Guild for pooled betting
-#[account]
-#[derive(InitSpace)]
-pub struct Guild {
-    /// Guild name (max 32 chars)
-    #[max_len(32)]
-    pub name: String,
-    /// Guild leader
+#[event]
+pub struct BattleCancelled {
+    pub version: u8,
+    pub battle: Pubkey,
+    pub refunded: u64,
+}
+
+#[event]
+pub struct GuildCreated {
+    pub version: u8,
+    pub guild: Pubkey,
     pub leader: Pubkey,
-    /// Treasury vault for pooled funds
-    pub treasury: Pubkey,
-    /// Total pooled IDL
-    pub total_pooled: u64,
-    /// Number of members
-    pub member_count: u64,
-    /// Total winnings distributed
-    pub total_winnings: u64,
-    /// Created timestamp
-    pub created_at: i64,
-    /// Is guild active
-    pub active: bool,
-    pub bump: u8,
+    pub name: String,
 }
 
-/// Guild membership
-#[account]
-#[derive(InitSpace)]
-pub struct GuildMember {
+#[event]
+pub struct GuildJoined {
+    pub version: u8,
     pub guild: Pubkey,
     pub member: Pubkey,
-    /// Member's share of guild pool
     pub contribution: u64,
-    /// Winnings claimed by this member
-    pub winnings_claimed: u64,
-    /// Joined timestamp
-    pub joined_at: i64,
-    pub bump: u8,
+    pub shares: u64,
+    pub total_shares: u64,
 }
 
-/// Loot box purchase record
-#[account]
-#[derive(InitSpace)]
-pub struct LootboxReward {
+#[event]
+pub struct GuildBetSettled {
+    pub version: u8,
+    pub guild: Pubkey,
+    pub market: Pubkey,
+    pub returned: u64,
+    pub leader_cut: u64,
+}
+
+/// Member withdrawal from a guild treasury. `left` is set when the member closed their membership.
+#[event]
+pub struct GuildWithdrawn {
+    pub version: u8,
+    pub guild: Pubkey,
+    pub member: Pubkey,
+    pub shares: u64,
+    pub payout: u64,
+    pub left: bool,
+}
+
+#[event]
+pub struct GuildDissolved {
+    pub version: u8,
+    pub guild: Pubkey,
+}
+
+#[event]
+pub struct LootboxPurchased {
+    pub version: u8,
+    pub lootbox: Pubkey,
+    pub owner: Pubkey,
+    pub tier: u8,
+    pub price: u64,
+    pub burned: u64,
+    pub commit_slot: u64,
+}
+
+#[event]
+pub struct LootboxOpened {
+    pub version: u8,
+    pub lootbox: Pubkey,
     pub owner: Pubkey,
-    /// Reward type: 0=fee_discount, 1=stake_boost, 2=vip_upgrade, 3=idl_tokens
     pub reward_type: u8,
-    /// Reward value (discount bps, boost bps, tier, or token amount)
     pub reward_value: u64,
-    /// Expiry timestamp (0 = never)
     pub expires_at: i64,
-    /// Is reward used
-    pub used: bool,
-    /// Lootbox tier: 0=common, 1=rare, 2=legendary
-    pub tier: u8,
-    pub bump: u8,
 }
 
-/// Limit order for conditional betting
-#[account]
-#[derive(InitSpace)]
-pub struct LimitOrder {
+#[event]
+pub struct LootboxRedeemed {
+    pub version: u8,
+    pub lootbox: Pubkey,
     pub owner: Pubkey,
+    pub reward_type: u8,
+    pub reward_value: u64,
+}
+
+#[event]
+pub struct LimitOrderCreated {
+    pub version: u8,
+    pub order: Pubkey,
     pub market: Pubkey,
-    /// Bet amount
+    pub owner: Pubkey,
     pub amount: u64,
-    /// Bet direction
     pub bet_yes: bool,
-    /// Target odds in bps (e.g. 6000 = 60% implied probability)
     pub target_odds_bps: u64,
-    /// Order expiry timestamp
     pub expires_at: i64,
-    /// Is order filled
-    pub filled: bool,
-    /// Created timestamp
-    pub created_at: i64,
-    pub bump: u8,
 }
 
-/// Stop loss order for auto-exit
-#[account]
-#[derive(InitSpace)]
-pub struct StopLoss {
+/// Unfilled limit order refunded to its owner, by cancellation or (`expired`) by the expiry crank.
+#[event]
+pub struct LimitOrderClosed {
+    pub version: u8,
+    pub order: Pubkey,
     pub owner: Pubkey,
-    pub bet: Pubkey,
-    pub market: Pubkey,
-    /// Threshold in bps (e.g. 5000 = exit if losing side hits 50%)
-    pub threshold_bps: u64,
-    /// Is stop loss triggered
-    pub triggered: bool,
-    /// Is stop loss active
-    pub active: bool,
-    /// Created timestamp
-    pub created_at: i64,
-    pub bump: u8,
+    pub refunded: u64,
+    pub expired: bool,
 }
 
-/// Market with dynamic odds
-#[account]
-#[derive(InitSpace)]
-pub struct DynamicOdds {
+#[event]
+pub struct LimitOrderFilled {
+    pub version: u8,
+    pub order: Pubkey,
     pub market: Pubkey,
-    /// Current YES odds in bps (e.g. 5500 = 55%)
-    pub yes_odds_bps: u64,
-    /// Current NO odds in bps (always 10000 - yes_odds)
-    pub no_odds_bps: u64,
-    /// Last update timestamp
-    pub last_update: i64,
-    /// Total volume since last update
-    pub volume_since_update: u64,
-    pub bump: u8,
+    pub owner: Pubkey,
+    pub bet_amount: u64,
+    pub effective_amount: u64,
+    pub odds_bps: u64,
+    pub keeper: Pubkey,
+    pub keeper_fee: u64,
+    pub creator_fee: u64,
 }
 
-/// Cashout position (partial exit)
-#[account]
-#[derive(InitSpace)]
-pub struct CashoutPosition {
-    pub owner: Pubkey,
+#[event]
+pub struct StopLossSet {
+    pub version: u8,
     pub bet: Pubkey,
-    pub market: Pubkey,
-    /// Original bet amount
-    pub original_amount: u64,
-    /// Amount cashed out
+    pub owner: Pubkey,
+    pub threshold_bps: u64,
+}
+
+/// Early exit at current odds. `keeper` is set when a stop loss triggered the cashout.
+#[event]
+pub struct BetCashedOut {
+    pub version: u8,
+    pub market: Pubkey,
+    pub bet: Pubkey,
+    pub owner: Pubkey,
     pub cashed_out_amount: u64,
-    /// Amount received after fees
-    pub received_amount: u64,
-    /// Cashout timestamp
-    pub cashed_out_at: i64,
-    pub bump: u8,
+    pub payout: u64,
+    pub odds_bps: u64,
+    pub keeper: Option<Pubkey>,
+    pub keeper_reward: u64,
 }
 
-// ==================== TYPES ====================
+#[event]
+pub struct ProposalCreated {
+    pub version: u8,
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub config_params: Option<ConfigParams>,
+    pub description_hash: [u8; 32],
+    pub snapshot_time: i64,
+    pub voting_ends_at: i64,
+    pub executable_at: i64,
+    pub quorum: u64,
+}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
-pub enum BadgeTier {
-    #[default]
-    None,
-    Bronze,
-    Silver,
-    Gold,
-    Platinum,
-    Diamond,
+#[event]
+pub struct ProposalVoteCast {
+    pub version: u8,
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
-pub enum MetricType {
-    Tvl,
-    Volume24h,
-    Users,
-    Transactions,
-    Price,
-    MarketCap,
-    Custom,
+#[event]
+pub struct ProposalExecuted {
+    pub version: u8,
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub action: GovernanceAction,
 }
 
 // ==================== ERRORS ====================
@@ -4080,6 +11097,12 @@ pub enum IdlError {
     #[msg("Authority timelock not expired (48 hours required)")]
     TimelockNotExpired,
 
+    #[msg("No config update pending")]
+    NoConfigUpdatePending,
+
+    #[msg("Invalid protocol config")]
+    InvalidConfig,
+
     #[msg("Target value must be greater than 0")]
     InvalidTargetValue,
 
@@ -4165,6 +11188,15 @@ pub enum IdlError {
     #[msg("Maximum oracles per market reached")]
     MaxOraclesReached,
 
+    #[msg("Oracle already committed a resolution for this market")]
+    ResolutionAlreadyCommitted,
+
+    #[msg("Registered oracles have not all revealed yet")]
+    OracleRevealsPending,
+
+    #[msg("Oracle accounts must cover every registered oracle exactly once")]
+    InvalidOracleAccounts,
+
     // ═══════════════════════════════════════════════════════════════════════════════
     // NOVEL ATTACK FIX ERRORS
     // ═══════════════════════════════════════════════════════════════════════════════
@@ -4176,10 +11208,16 @@ pub enum IdlError {
     #[msg("Commitment has expired - reclaim your bond")]
     CommitmentExpired,
 
+    #[msg("Commitment has not expired yet")]
+    CommitmentNotExpired,
+
     // COMMITMENT_SNIPE fix
     #[msg("Batch reveal delay not passed - wait for all reveals to be hidden")]
     BatchRevealDelayNotPassed,
 
+    #[msg("No pending reveals to unveil")]
+    NoPendingReveals,
+
     // DISPUTE_GRIEF fix
     #[msg("Dispute bond required (5 tokens)")]
     DisputeBondRequired,
@@ -4198,10 +11236,25 @@ pub enum IdlError {
     #[msg("Large stakes (>10 tokens) require commit-reveal")]
     LargeStakeRequiresCommitReveal,
 
+    #[msg("Stake commitment already pending - reveal it or wait for it to expire")]
+    StakeCommitmentPending,
+
     // TVL_CAP_RACE fix
     #[msg("TVL raise in progress - join queue for pro-rata allocation")]
     TvlRaiseQueueActive,
 
+    #[msg("TVL raise queue is closed")]
+    TvlRaiseClosed,
+
+    #[msg("Auto-compound is not enabled for this staker")]
+    AutoCompoundDisabled,
+
+    #[msg("Lootbox already opened")]
+    LootboxAlreadyOpened,
+
+    #[msg("Lootbox not ready - open it in a later slot")]
+    LootboxNotReady,
+
     // VE_DECAY_ARBITRAGE fix
     #[msg("Vote power snapshot not ready - wait 24h before vote")]
     VoteSnapshotNotReady,
@@ -4224,4 +11277,1091 @@ pub enum IdlError {
 
     #[msg("Vault not initialized - call init_vault first")]
     VaultNotInitialized,
+
+    // Season leaderboard errors
+    #[msg("Season is not active")]
+    SeasonNotActive,
+
+    #[msg("Season has not ended yet")]
+    SeasonNotEnded,
+
+    #[msg("Prize shares must not exceed 10000 bps")]
+    InvalidPrizeShares,
+
+    #[msg("Leaderboard already finalized")]
+    LeaderboardFinalized,
+
+    #[msg("Leaderboard not finalized yet")]
+    LeaderboardNotFinalized,
+
+    #[msg("Not eligible for a season prize")]
+    NotPrizeWinner,
+
+    // Guild errors
+    #[msg("Guild is not active")]
+    GuildInactive,
+
+    #[msg("Guild needs at least 3 members to bet")]
+    GuildTooSmall,
+
+    #[msg("Guild has unsettled bets")]
+    GuildBetsOutstanding,
+
+    #[msg("Invalid guild treasury account")]
+    InvalidGuildTreasury,
+
+    // Order errors
+    #[msg("Order already filled, cancelled or triggered")]
+    OrderInactive,
+
+    #[msg("Limit order has expired")]
+    LimitOrderExpired,
+
+    #[msg("Limit order has not expired yet")]
+    LimitOrderNotExpired,
+
+    #[msg("Market odds have not reached the limit price")]
+    LimitPriceNotReached,
+
+    #[msg("Market odds have not reached the stop loss threshold")]
+    StopLossNotReached,
+
+    #[msg("Stop loss was set too recently to trigger")]
+    StopLossTooRecent,
+
+    // Hedge market errors
+    #[msg("Invalid market account")]
+    InvalidMarket,
+
+    #[msg("Invalid hedge parent market")]
+    InvalidHedgeParent,
+
+    #[msg("Hedge markets resolve from their parent market")]
+    HedgeResolvesFromParent,
+
+    // Market type errors
+    #[msg("Instruction not supported for this market type")]
+    WrongMarketType,
+
+    #[msg("Categorical markets need between 2 and 8 outcomes")]
+    InvalidOutcomeCount,
+
+    #[msg("Invalid outcome index")]
+    InvalidOutcome,
+
+    #[msg("Scalar range upper bound must exceed lower bound")]
+    InvalidRange,
+
+    // Governance errors
+    #[msg("Not enough veIDL voting power at the snapshot")]
+    InsufficientVotingPower,
+
+    #[msg("Proposal voting has closed")]
+    ProposalVotingClosed,
+
+    #[msg("Proposal voting is still open")]
+    ProposalVotingOpen,
+
+    #[msg("Proposal did not reach quorum or threshold")]
+    ProposalNotPassed,
+
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+
+    // Migration errors
+    #[msg("Account is already at its current size")]
+    AccountAlreadyMigrated,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_code<T: std::fmt::Debug>(result: Result<T>) -> u32 {
+        match result.unwrap_err() {
+            Error::AnchorError(e) => e.error_code_number,
+            Error::ProgramError(e) => panic!("unexpected program error: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn season_stats_count_only_settlements_inside_the_season() {
+        let state = ProtocolState { season_start: 1_000, season_end: 2_000, ..Default::default() };
+        assert_eq!(state.season_start_at(999), None);
+        assert_eq!(state.season_start_at(1_500), Some(1_000));
+        assert_eq!(state.season_start_at(2_001), None);
+
+        // Lifetime history before the season doesn't count toward it
+        let mut stats = PredictorStats::default();
+        stats.record_settlement(true, 5_000, 900, state.season_start_at(900));
+        stats.record_settlement(true, 300, 1_200, state.season_start_at(1_200));
+        stats.record_settlement(false, 100, 1_300, state.season_start_at(1_300));
+        assert_eq!(stats.total_predictions, 3);
+        assert_eq!(stats.season_start, 1_000);
+        assert_eq!((stats.season_predictions, stats.season_correct, stats.season_winnings), (2, 1, 300));
+
+        // The next season starts from zero
+        stats.record_settlement(true, 700, 3_100, Some(3_000));
+        assert_eq!(stats.season_start, 3_000);
+        assert_eq!((stats.season_predictions, stats.season_correct, stats.season_winnings), (1, 1, 700));
+    }
+
+    #[test]
+    fn season_leaderboard_ranks_entries_in_any_submission_order() {
+        let mut season = Season { end_time: 1_000, active: true, ..Default::default() };
+        let users: Vec<Pubkey> = (0..LEADERBOARD_MAX_RANKS + 2).map(|_| Pubkey::new_unique()).collect();
+
+        // Worst first: every submission still lands in its place
+        for (i, user) in users.iter().enumerate() {
+            season.record_entry(*user, 100 * i as u64, 50);
+        }
+        assert_eq!(season.leader_count as usize, LEADERBOARD_MAX_RANKS);
+        assert_eq!(season.leaders[0], users[users.len() - 1]);
+        assert!(!season.leaders.contains(&users[0]));
+
+        // Ties go to the better accuracy; a resubmission moves the user instead of duplicating them
+        season.record_entry(users[5], 1_100, 60);
+        season.record_entry(users[0], 2_000, 10);
+        assert_eq!(&season.leaders[..3], &[users[0], users[5], users[11]]);
+        assert_eq!(season.leaders.iter().filter(|l| **l == users[5]).count(), 1);
+
+        // Nothing to rank once it's over: the season always locks
+        assert_eq!(error_code(season.finalize(1_000)), u32::from(IdlError::SeasonNotEnded));
+        season.finalize(1_001).unwrap();
+        assert_eq!(season.rank_of(&users[0]), Some(1));
+        assert_eq!(season.rank_of(&users[5]), Some(2));
+        assert_eq!(season.rank_of(&users[1]), None);
+        assert_eq!(error_code(season.finalize(1_002)), u32::from(IdlError::LeaderboardFinalized));
+    }
+
+    #[test]
+    fn legacy_accounts_grow_into_the_current_layout() {
+        let entry = LeaderboardEntry {
+            user: Pubkey::new_unique(),
+            accuracy: 70,
+            winnings: 5_000,
+            rank: 3,
+            updated_at: 1_234,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        entry.try_serialize(&mut data).unwrap();
+
+        // Before updated_at was appended the account ended at `bump`
+        let legacy_len = data.len() - std::mem::size_of::<i64>();
+        data.truncate(legacy_len);
+        let new_len = migrated_space(&LeaderboardEntry::DISCRIMINATOR).unwrap();
+        assert!(legacy_len < new_len);
+
+        // migrate_account reallocs with a zeroed tail
+        data.resize(new_len, 0);
+        let migrated = LeaderboardEntry::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!((migrated.user, migrated.winnings, migrated.rank), (entry.user, 5_000, 3));
+        assert_eq!(migrated.updated_at, 0);
+        assert_eq!(migrated_space(&[0; 8]), None);
+    }
+
+    fn guild(total_pooled: u64, amount_at_risk: u64) -> Guild {
+        Guild { total_pooled, amount_at_risk, ..Default::default() }
+    }
+
+    #[test]
+    fn guild_shares_round_trip_at_treasury_value() {
+        let mut guild = guild(0, 0);
+
+        // First member sets the share price at 1:1
+        assert_eq!(guild.shares_for(1_000, 0).unwrap(), 1_000);
+        guild.total_pooled = 1_000;
+
+        // Treasury doubled from winnings: a new member buys at the higher price
+        let shares = guild.shares_for(1_000, 2_000).unwrap();
+        assert_eq!(shares, 500);
+        guild.total_pooled += shares;
+
+        // Each side withdraws exactly what it is owed
+        assert_eq!(guild.share_value(500, 3_000).unwrap(), 1_000);
+        assert_eq!(guild.share_value(1_000, 3_000).unwrap(), 2_000);
+    }
+
+    #[test]
+    fn guild_shares_not_priced_with_bets_outstanding() {
+        let mut guild = guild(1_000, 400);
+
+        // Joining now would let the new member share in an outcome already staked
+        assert_eq!(
+            error_code(guild.shares_for(1_000, 600)),
+            u32::from(IdlError::GuildBetsOutstanding)
+        );
+
+        guild.amount_at_risk = 0;
+        assert_eq!(guild.shares_for(1_000, 1_000).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn guild_members_withdraw_cash_not_at_risk() {
+        // 1000 shares, 400 of the 1000 contributed out in open bets
+        let mut guild = guild(1_000, 400);
+        let mut treasury = 600;
+
+        // A member with 250 shares takes their part of the cash and nothing of the open bets
+        let payout = guild.share_value(250, treasury).unwrap();
+        assert_eq!(payout, 150);
+        treasury -= payout;
+        guild.total_pooled -= 250;
+        assert_eq!(
+            error_code(guild.share_value(750, treasury)),
+            u32::from(IdlError::GuildBetsOutstanding)
+        );
+
+        // The bets return 800, all of it to the members still in
+        treasury += 800;
+        guild.amount_at_risk = 0;
+        assert_eq!(guild.share_value(750, treasury).unwrap(), 1_250);
+    }
+
+    #[test]
+    fn limit_order_fills_only_at_target_before_expiry() {
+        let mut order = LimitOrder { target_odds_bps: 4000, expires_at: 1_000, ..Default::default() };
+
+        assert_eq!(error_code(order.check_fill(4001, 500)), u32::from(IdlError::LimitPriceNotReached));
+        assert!(order.check_fill(4000, 500).is_ok());
+        assert!(order.check_fill(3500, 999).is_ok());
+        assert_eq!(error_code(order.check_fill(3500, 1_000)), u32::from(IdlError::LimitOrderExpired));
+
+        order.filled = true;
+        assert_eq!(error_code(order.check_fill(3500, 500)), u32::from(IdlError::OrderInactive));
+    }
+
+    #[test]
+    fn limit_order_fill_waits_for_the_reveal_batch() {
+        let cfg = ConfigParams::default();
+        let mut market = PredictionMarket::default();
+
+        // A fill joins the pending batch like a revealed bet, hidden from the public pools
+        market.add_pending_reveal(&cfg, 1, 1_000, 1_100, 100);
+        assert_eq!(market.total_yes_actual, 0);
+        assert_eq!(market.total_yes_amount, 0);
+
+        assert!(!market.unveil_reveal_batch(&cfg, 100 + cfg.batch_reveal_delay - 1));
+        assert!(market.unveil_reveal_batch(&cfg, 100 + cfg.batch_reveal_delay));
+        assert_eq!(market.total_yes_actual, 1_000);
+        assert_eq!(market.total_yes_amount, 1_100);
+        assert_eq!(market.total_no_actual, 0);
+    }
+
+    #[test]
+    fn stop_loss_triggers_relative_to_entry_odds() {
+        let mut stop_loss = StopLoss { entry_odds_bps: 6000, threshold_bps: 5000, ..Default::default() };
+
+        // Fires once the side has lost half of its entry odds
+        assert_eq!(stop_loss.trigger_odds_bps(), 3000);
+        assert!(!stop_loss.is_reached(3001));
+        assert!(stop_loss.is_reached(3000));
+
+        // An underdog bet isn't stopped out at once just for being below 50%
+        stop_loss.entry_odds_bps = 2000;
+        assert!(!stop_loss.is_reached(2000));
+        assert!(!stop_loss.is_reached(1001));
+        assert!(stop_loss.is_reached(1000));
+
+        stop_loss.threshold_bps = 9000;
+        assert_eq!(stop_loss.trigger_odds_bps(), 200);
+    }
+
+    /// Open binary market with `yes` and `no` staked on each side
+    fn binary_market(yes: u64, no: u64) -> PredictionMarket {
+        PredictionMarket {
+            resolution_timestamp: i64::MAX / 2,
+            total_yes_actual: yes,
+            total_yes_amount: yes,
+            total_no_actual: no,
+            total_no_amount: no,
+            ..Default::default()
+        }
+    }
+
+    fn bet(amount: u64, bet_yes: bool) -> Bet {
+        Bet { amount, effective_amount: amount, bet_yes, ..Default::default() }
+    }
+
+    /// Binary market with YES bets of 500 and 500 against a NO bet of 1000
+    fn cashout_fixture() -> (ConfigParams, PredictionMarket, [Bet; 3]) {
+        let market = binary_market(1_000, 1_000);
+        (ConfigParams::default(), market, [bet(500, true), bet(500, true), bet(1_000, false)])
+    }
+
+    #[test]
+    fn cashout_leaves_the_pot_fully_claimable() {
+        let (cfg, mut market, [mut cashed, held_yes, held_no]) = cashout_fixture();
+        let pool_tokens = 2_000u64;
+
+        let (fee, payout) = cash_out_bet(&cfg, &mut market, &mut cashed, 500, 6000, 100, cfg.cashout_min_time)
+            .unwrap();
+        assert_eq!(fee, 5);
+        assert_eq!(payout, 297);
+        assert!(cashed.claimed);
+
+        // The whole stake leaves its side; the haircut stays in the pot
+        assert_eq!(market.total_yes_actual, 500);
+        assert_eq!(market.total_yes_amount, 500);
+        assert_eq!(market.cashout_retained, 203);
+        let remaining = pool_tokens - payout;
+        assert_eq!(
+            market.total_yes_actual + market.total_no_actual + market.cashout_retained,
+            remaining
+        );
+
+        // Whichever side wins, its bets claim exactly what is left in the pool
+        market.outcome = Some(true);
+        assert_eq!(market_payout(&held_yes, &market).unwrap(), remaining);
+        market.outcome = Some(false);
+        assert_eq!(market_payout(&held_no, &market).unwrap(), remaining);
+    }
+
+    #[test]
+    fn partial_cashout_keeps_the_rest_of_the_bet_in_play() {
+        let (cfg, mut market, [mut cashed, held_yes, _]) = cashout_fixture();
+
+        let (_, first) = cash_out_bet(&cfg, &mut market, &mut cashed, 200, 5000, 0, cfg.cashout_min_time)
+            .unwrap();
+        assert_eq!(first, 100);
+        assert_eq!(cashed.amount, 300);
+        assert_eq!(cashed.effective_amount, 300);
+        assert!(!cashed.claimed);
+        assert_eq!(market.total_yes_actual, 800);
+        assert_eq!(market.cashout_retained, 100);
+
+        // The remaining YES bets split the pool that's left when YES wins (less rounding dust)
+        market.outcome = Some(true);
+        let remaining = 2_000 - first;
+        let claims = market_payout(&cashed, &market).unwrap() + market_payout(&held_yes, &market).unwrap();
+        assert!(claims <= remaining && remaining - claims < 2, "{claims} of {remaining}");
+    }
+
+    #[test]
+    fn dynamic_odds_drift_toward_spot_in_capped_steps() {
+        let cfg = ConfigParams::default();
+        let step = cfg.odds_update_threshold;
+        let mut odds = DynamicOdds { yes_odds_bps: 5000, no_odds_bps: 5000, ..Default::default() };
+
+        // Spot is 75% YES, but nothing moves until a full threshold of volume has come in
+        let market = binary_market(3 * step, step);
+        odds.record_volume(&cfg, &market, step - 1, 10);
+        assert_eq!((odds.yes_odds_bps, odds.volume_since_update), (5000, step - 1));
+
+        // Each update moves at most ODDS_MAX_SHIFT_BPS and restarts the volume count
+        odds.record_volume(&cfg, &market, 1, 20);
+        assert_eq!((odds.odds_bps(true), odds.odds_bps(false)), (5500, 4500));
+        assert_eq!((odds.last_update, odds.volume_since_update), (20, 0));
+        odds.record_volume(&cfg, &market, step, 30);
+        assert_eq!(odds.yes_odds_bps, 6000);
+
+        // Within one shift of spot the odds land on it, and never leave 0..=10000
+        odds.yes_odds_bps = 7300;
+        odds.record_volume(&cfg, &market, step, 40);
+        assert_eq!(odds.yes_odds_bps, 7500);
+        odds.yes_odds_bps = 9800;
+        odds.record_volume(&cfg, &binary_market(step, 0), step, 50);
+        assert_eq!((odds.yes_odds_bps, odds.no_odds_bps), (10000, 0));
+    }
+
+    fn categorical_market(pools: &[u64]) -> PredictionMarket {
+        let mut market = PredictionMarket {
+            market_type: MARKET_TYPE_CATEGORICAL,
+            outcome_count: pools.len() as u8,
+            ..Default::default()
+        };
+        market.outcome_pools[..pools.len()].copy_from_slice(pools);
+        market.outcome_pools_effective[..pools.len()].copy_from_slice(pools);
+        market
+    }
+
+    #[test]
+    fn categorical_winners_split_every_losing_outcome() {
+        let mut market = categorical_market(&[100, 300, 600]);
+        let on = |outcome_index: u8, amount: u64| Bet { outcome_index, ..bet(amount, false) };
+        assert!(!market.is_two_sided());
+
+        // The oracle value is the winning index, which must be one of the market's outcomes
+        assert_eq!(error_code(market.apply_resolution(3, 0)), u32::from(IdlError::InvalidOutcome));
+        market.apply_resolution(1, 0).unwrap();
+        assert_eq!(market.winning_outcome, Some(1));
+
+        // Outcome 1's bets share the 700 staked on outcomes 0 and 2 (less rounding dust)
+        let first = market_payout(&on(1, 100), &market).unwrap();
+        let second = market_payout(&on(1, 200), &market).unwrap();
+        assert_eq!((first, second), (333, 666));
+        assert_eq!(market_payout(&on(2, 600), &market).unwrap(), 0);
+    }
+
+    #[test]
+    fn scalar_payout_is_linear_in_the_clamped_value() {
+        let mut market = PredictionMarket {
+            market_type: MARKET_TYPE_SCALAR,
+            range_low: 100,
+            range_high: 300,
+            ..binary_market(1_000, 1_000)
+        };
+        let (long, short) = (bet(400, true), bet(1_000, false));
+
+        // 150 is a quarter of the way up: longs share a quarter of the pot, shorts the rest
+        market.apply_resolution(150, 0).unwrap();
+        assert_eq!(market_payout(&long, &market).unwrap(), 200);
+        assert_eq!(market_payout(&short, &market).unwrap(), 1_500);
+
+        // Values outside the range pay as the nearest bound
+        market.actual_value = Some(50);
+        assert_eq!(market_payout(&long, &market).unwrap(), 0);
+        assert_eq!(market_payout(&short, &market).unwrap(), 2_000);
+        market.actual_value = Some(1_000);
+        assert_eq!(market_payout(&long, &market).unwrap(), 800);
+
+        // With nobody on the other side, stakes are refunded
+        market.total_no_amount = 0;
+        assert_eq!(market_payout(&long, &market).unwrap(), 400);
+    }
+
+    #[test]
+    fn comparison_resolves_to_the_larger_protocol() {
+        let mut market = PredictionMarket {
+            market_type: MARKET_TYPE_COMPARISON,
+            ..binary_market(1_000, 1_000)
+        };
+
+        // Oracles agree on the winner, not on the exact values; 2 marks a tie
+        assert_eq!(market.consensus_key(700, 500), 1);
+        assert_eq!(market.consensus_key(710, 500), 1);
+        assert_eq!(market.consensus_key(500, 700), 0);
+        assert_eq!(market.consensus_key(600, 600), 2);
+
+        // YES is protocol A strictly beating B, and pays out like a binary market
+        market.apply_resolution(700, 500).unwrap();
+        assert_eq!((market.outcome, market.comparison_value), (Some(true), Some(500)));
+        assert_eq!(market_payout(&bet(1_000, true), &market).unwrap(), 2_000);
+        assert_eq!(market_payout(&bet(1_000, false), &market).unwrap(), 0);
+
+        market.apply_resolution(500, 700).unwrap();
+        assert_eq!(market.outcome, Some(false));
+    }
+
+    #[test]
+    fn predictor_bonuses_leave_the_pot_fully_claimable() {
+        let (cfg, mut market, [first, second, _]) = cashout_fixture();
+        market.outcome = Some(true);
+        let stats = PredictorStats {
+            total_predictions: 10,
+            correct_predictions: 9,
+            current_streak: 3,
+            ..Default::default()
+        };
+        let bonus_bps = stats.record_bonus_bps(&cfg);
+        let full_bonus = 500 * bonus_bps / 10000;
+        assert!(full_bonus > 0);
+        let mut state = ProtocolState { predictor_bonus_pool: full_bonus + full_bonus / 2, ..Default::default() };
+
+        // Both winners claim their full share of the pot; bonuses only draw on their own pool
+        let mut pot = 2_000;
+        let mut bonuses = Vec::new();
+        for bet in [&first, &second] {
+            let payout = market_payout(bet, &market).unwrap();
+            pot -= payout;
+            bonuses.push(state.take_predictor_bonus(payout - bet.amount, bonus_bps));
+        }
+        assert_eq!(pot, 0);
+        assert_eq!(bonuses, vec![full_bonus, full_bonus / 2]);
+        assert_eq!(state.predictor_bonus_pool, 0);
+    }
+
+    #[test]
+    fn dispute_window_and_settlement_never_overlap() {
+        let cfg = ConfigParams::default();
+        let resolved_at = 1_000;
+        let close = resolved_at + cfg.oracle_dispute_window;
+
+        assert!(!dispute_window_closed(&cfg, resolved_at, resolved_at));
+        assert!(!dispute_window_closed(&cfg, resolved_at, close - 1));
+        // Disputes are rejected from the instant hedge markets and claims may settle
+        assert!(dispute_window_closed(&cfg, resolved_at, close));
+    }
+
+    #[test]
+    fn oracle_consensus_needs_count_and_threshold() {
+        let cfg = ConfigParams::default();
+
+        // 2 of 3 reveals is short of 67%; 3 of 4 isn't
+        assert_eq!(tally_oracle_votes(&cfg, &[Some(1), Some(1), Some(0)]), (1, 2, false));
+        assert_eq!(tally_oracle_votes(&cfg, &[Some(1), Some(1), Some(1), Some(0)]), (1, 3, true));
+        // Unanimous, but a single reveal is below the minimum oracle count
+        assert!(!tally_oracle_votes(&cfg, &[Some(1), None, None]).2);
+        assert!(tally_oracle_votes(&cfg, &[Some(7), Some(7), None]).2);
+    }
+
+    #[test]
+    fn oracles_that_commit_and_never_reveal_are_slashed() {
+        // Dissent only costs the bond when there is a consensus to dissent from
+        assert!(oracle_slashable(Some(0), false, Some(1)));
+        assert!(!oracle_slashable(Some(1), false, Some(1)));
+        assert!(!oracle_slashable(Some(0), false, None));
+
+        // Withholding a committed vote is slashed either way; never committing is not
+        assert!(oracle_slashable(None, true, Some(1)));
+        assert!(oracle_slashable(None, true, None));
+        assert!(!oracle_slashable(None, false, Some(1)));
+    }
+
+    #[test]
+    fn oracle_bond_stays_locked_through_dispute() {
+        let cfg = ConfigParams::default();
+        let window = cfg.oracle_dispute_window;
+        let mut market = PredictionMarket::default();
+
+        assert_eq!(
+            error_code(require_oracle_bond_unlocked(&cfg, &market, 0)),
+            u32::from(IdlError::MarketNotResolved)
+        );
+
+        // Resolved: locked until the dispute window has closed
+        market.status = MARKET_STATUS_RESOLVED;
+        market.resolved = true;
+        market.resolved_at = Some(1_000);
+        assert_eq!(
+            error_code(require_oracle_bond_unlocked(&cfg, &market, 1_000 + window - 1)),
+            u32::from(IdlError::DisputeWindowOpen)
+        );
+
+        // Disputed inside the window: still locked after it closes, until the vote settles
+        market.status = MARKET_STATUS_DISPUTED;
+        assert_eq!(
+            error_code(require_oracle_bond_unlocked(&cfg, &market, 1_000 + window + 1)),
+            u32::from(IdlError::ResolutionDisputed)
+        );
+
+        // Upheld: free once the window is over
+        market.status = MARKET_STATUS_RESOLVED;
+        assert!(require_oracle_bond_unlocked(&cfg, &market, 1_000 + window + 1).is_ok());
+
+        // Cancelled by the vote: nothing left to dispute
+        market.status = MARKET_STATUS_CANCELLED;
+        market.resolved = false;
+        market.resolved_at = None;
+        assert!(require_oracle_bond_unlocked(&cfg, &market, 1_000).is_ok());
+    }
+
+    #[test]
+    fn dispute_votes_use_power_at_the_dispute_snapshot() {
+        let cfg = ConfigParams::default();
+        let opened_at = 200_000;
+        let snapshot_time = opened_at - cfg.vote_snapshot_delay;
+        let mut dispute = Dispute { opened_at, snapshot_time, ..Default::default() };
+
+        let upholder = ve_position(1_000, 100, 1_000_000);
+        let mut overturner = ve_position(1_000, 100, 1_000_000);
+        // Extending after the snapshot would outweigh the upholder at current terms
+        overturner.extend_terms(5_000, 5_000_100, 5_000_000, snapshot_time + 1);
+        assert!(overturner.current_ve_amount(snapshot_time) > upholder.current_ve_amount(snapshot_time));
+
+        let weight = |vp: &VePosition| snapshot_voting_power(Some(vp), None, snapshot_time);
+        assert_eq!(weight(&overturner), weight(&upholder));
+        dispute.record_vote(DISPUTE_CHOICE_UPHOLD, weight(&upholder));
+        dispute.record_vote(DISPUTE_CHOICE_OVERTURN, weight(&overturner));
+
+        // Equal power at the snapshot: the resolution stands
+        assert_eq!(dispute.outcome(), DISPUTE_CHOICE_UPHOLD);
+        dispute.record_vote(DISPUTE_CHOICE_CANCEL, 1);
+        assert_eq!(dispute.outcome(), DISPUTE_CHOICE_UPHOLD);
+        dispute.record_vote(DISPUTE_CHOICE_OVERTURN, 1);
+        assert_eq!(dispute.outcome(), DISPUTE_CHOICE_OVERTURN);
+    }
+
+    #[test]
+    fn legacy_commitments_migrate_with_their_owner_as_bond_payer() {
+        let owner = Pubkey::new_unique();
+        let legacy = BetCommitment { owner, commit_time: 100, ..Default::default() };
+        let mut data = Vec::new();
+        legacy.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - 8 - 32);
+        data.resize(migrated_space(&BetCommitment::DISCRIMINATOR).unwrap(), 0);
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &ID, false, 0);
+        migrate_legacy_fields(&info, &BetCommitment::DISCRIMINATOR).unwrap();
+
+        let migrated = BetCommitment::try_deserialize(&mut &info.try_borrow_data().unwrap()[..]).unwrap();
+        assert_eq!((migrated.owner, migrated.commit_time), (owner, 100));
+        assert_eq!((migrated.bond_amount, migrated.bond_payer), (0, owner));
+    }
+
+    #[test]
+    fn withheld_reveals_forfeit_the_commitment_bond() {
+        let cfg = ConfigParams::default();
+        let mut commitment = BetCommitment { commit_time: 100, ..Default::default() };
+        let mut market = binary_market(0, 0);
+        let expiry = 100 + cfg.commitment_expiry;
+
+        // The bond can't be touched until the commitment expires, or once it's revealed
+        assert_eq!(
+            error_code(commitment.check_expired(&cfg, &market, expiry)),
+            u32::from(IdlError::CommitmentNotExpired)
+        );
+        assert!(commitment.check_expired(&cfg, &market, expiry + 1).unwrap());
+
+        // Refunded when the market stopped taking reveals before the window ended
+        market.resolution_timestamp = 100 + cfg.bet_reveal_window;
+        assert!(!commitment.check_expired(&cfg, &market, expiry + 1).unwrap());
+        market.resolution_timestamp = i64::MAX / 2;
+        market.status = MARKET_STATUS_CANCELLED;
+        assert!(!commitment.check_expired(&cfg, &market, expiry + 1).unwrap());
+
+        commitment.revealed = true;
+        assert_eq!(
+            error_code(commitment.check_expired(&cfg, &market, expiry + 1)),
+            u32::from(IdlError::AlreadyRevealed)
+        );
+    }
+
+    #[test]
+    fn revealed_bets_stay_hidden_until_their_batch_unveils() {
+        let cfg = ConfigParams::default();
+        let delay = cfg.batch_reveal_delay;
+        let mut market = binary_market(1_000, 1_000);
+
+        // Reveals inside one window pile up out of sight of the public pools
+        market.add_pending_reveal(&cfg, 1, 300, 330, 100);
+        market.add_pending_reveal(&cfg, 0, 200, 200, 100 + delay - 1);
+        assert!(!market.unveil_reveal_batch(&cfg, 100 + delay - 1));
+        assert_eq!((market.total_yes_actual, market.total_no_actual), (1_000, 1_000));
+
+        // A reveal after the window first unveils the earlier batch, then starts a new one
+        market.add_pending_reveal(&cfg, 1, 50, 50, 100 + delay);
+        assert_eq!((market.total_yes_actual, market.total_yes_amount), (1_300, 1_330));
+        assert_eq!(market.total_no_actual, 1_200);
+        assert_eq!(market.pending_batch_start, 100 + delay);
+        assert_eq!(market.pending_actual[1], 50);
+
+        // Once betting is over the last batch is folded in without waiting
+        market.flush_pending_reveals();
+        assert_eq!(market.total_yes_actual, 1_350);
+        assert_eq!((market.pending_batch_start, market.pending_actual[1]), (0, 0));
+    }
+
+    #[test]
+    fn creators_wait_out_the_cooldown_and_earn_on_volume() {
+        let cfg = ConfigParams::default();
+        let mut stats = CreatorStats::default();
+        let start = 1_000;
+
+        // The first market is never held back; the next waits out the cooldown
+        stats.record_market_created(&cfg, start).unwrap();
+        assert_eq!(
+            error_code(stats.record_market_created(&cfg, start + cfg.market_creation_cooldown - 1)),
+            u32::from(IdlError::MarketCreationCooldown)
+        );
+        stats.record_market_created(&cfg, start + cfg.market_creation_cooldown).unwrap();
+        assert_eq!(stats.markets_created, 2);
+
+        // Each revealed bet credits volume and the fee claim_creator_fees pays out
+        let amount = 1_000_000;
+        let fee = accrue_creator_volume_fee(&cfg, &mut stats, amount);
+        accrue_creator_volume_fee(&cfg, &mut stats, amount);
+        assert_eq!(fee, amount * cfg.creator_volume_fee_bps / 10000);
+        assert!(fee > 0);
+        assert_eq!((stats.total_volume, stats.pending_fees), (2 * amount, 2 * fee));
+    }
+
+    #[test]
+    fn large_stakes_reveal_only_the_committed_amount_in_the_window() {
+        let cfg = ConfigParams::default();
+        let amount = cfg.large_stake_threshold * 2;
+        let salt = [7u8; 32];
+        let commitment = StakeCommitment {
+            commitment: stake_commitment_hash(amount, &salt),
+            commit_time: 100,
+            ..Default::default()
+        };
+        let opens = 100 + cfg.stake_commit_window;
+        let expires = 100 + cfg.commitment_expiry;
+
+        commitment.check_reveal(&cfg, amount, &salt, opens).unwrap();
+        commitment.check_reveal(&cfg, amount, &salt, expires).unwrap();
+        let reveal = |amount, salt: &[u8; 32], now| error_code(commitment.check_reveal(&cfg, amount, salt, now));
+        assert_eq!(reveal(amount, &salt, opens - 1), u32::from(IdlError::RevealTooEarly));
+        assert_eq!(reveal(amount, &salt, expires + 1), u32::from(IdlError::CommitmentExpired));
+
+        // Neither the amount nor the salt can change after committing
+        assert_eq!(reveal(amount + 1, &salt, opens), u32::from(IdlError::InvalidCommitment));
+        assert_eq!(reveal(amount, &[8u8; 32], opens), u32::from(IdlError::InvalidCommitment));
+    }
+
+    /// Queue deposits into the open raise the way join_tvl_raise does
+    fn queue_tvl_raise(state: &mut ProtocolState, amounts: &[u64]) {
+        for &amount in amounts {
+            state.tvl_raise_requested += amount;
+            state.tvl_raise_pending += amount;
+        }
+    }
+
+    #[test]
+    fn tvl_raise_oversubscribed_is_pro_rata() {
+        let mut state = ProtocolState { total_staked: 1_000, ..Default::default() };
+        state.open_tvl_raise(2_000, 3_600, 100);
+        assert_eq!(state.tvl_raise_headroom, 1_000);
+
+        let amounts = [600, 600, 800];
+        queue_tvl_raise(&mut state, &amounts);
+        let allocations: Vec<u64> = amounts.iter().map(|&a| state.tvl_raise_allocation(a)).collect();
+        assert_eq!(allocations, vec![300, 300, 400]);
+
+        // Direct deposits stay closed until the last entry is settled
+        for (amount, allocation) in amounts.iter().zip(&allocations) {
+            assert!(state.tvl_raise_active(3_700));
+            state.total_staked += allocation;
+            state.tvl_raise_pending -= amount;
+        }
+        assert!(!state.tvl_raise_active(3_700));
+        assert_eq!(state.total_staked, state.tvl_cap);
+    }
+
+    #[test]
+    fn tvl_raise_never_allocates_past_headroom() {
+        let mut state = ProtocolState::default();
+        state.open_tvl_raise(2, 3_600, 0);
+        queue_tvl_raise(&mut state, &[1, 1, 1]);
+        let total: u64 = (0..3).map(|_| state.tvl_raise_allocation(1)).sum();
+        assert!(total <= state.tvl_raise_headroom);
+
+        // Undersubscribed: every entry is staked in full
+        let mut state = ProtocolState::default();
+        state.open_tvl_raise(1_000, 3_600, 0);
+        queue_tvl_raise(&mut state, &[400, 500]);
+        assert_eq!(state.tvl_raise_allocation(400), 400);
+        assert_eq!(state.tvl_raise_allocation(500), 500);
+    }
+
+    #[test]
+    fn season_bonus_ramps_in_and_out() {
+        let cfg = ConfigParams::default();
+        let ramp = cfg.season_phase_in_duration;
+        let season = Season { start_time: 1_000, end_time: 1_000 + 10 * ramp, ..Default::default() };
+        let full = cfg.season_bonus_bps;
+
+        // Nothing outside the season, a linear ramp at either end, the full bonus between
+        assert_eq!(season.bonus_bps_at(&cfg, 999), 0);
+        assert_eq!(season.bonus_bps_at(&cfg, 1_000), 0);
+        assert_eq!(season.bonus_bps_at(&cfg, 1_000 + ramp / 2), full / 2);
+        assert_eq!(season.bonus_bps_at(&cfg, 1_000 + ramp), full);
+        assert_eq!(season.bonus_bps_at(&cfg, 1_000 + 5 * ramp), full);
+        assert_eq!(season.bonus_bps_at(&cfg, season.end_time - ramp / 4), full / 4);
+        assert_eq!(season.bonus_bps_at(&cfg, season.end_time + 1), 0);
+    }
+
+    #[test]
+    fn early_bird_bonus_follows_the_commit_time() {
+        let cfg = ConfigParams::default();
+        let market = PredictionMarket { created_at: 1_000, ..binary_market(0, 0) };
+        let amount = 1_000_000;
+        let bonus = amount * cfg.early_bird_bonus_bps / 10000;
+
+        // Committed inside the window: the bonus holds however late the reveal comes
+        assert_eq!(early_bird_bonus(&cfg, &market, 1_000, amount), bonus);
+        assert_eq!(early_bird_bonus(&cfg, &market, 1_000 + cfg.early_bird_window, amount), bonus);
+        assert_eq!(early_bird_bonus(&cfg, &market, 1_001 + cfg.early_bird_window, amount), 0);
+    }
+
+    #[test]
+    fn vip_discounts_apply_to_their_owner_down_to_the_fee_floor() {
+        let cfg = ConfigParams::default();
+        let payer = Pubkey::new_unique();
+        let stats = PredictorStats { owner: payer, vip_tier: 2, ..Default::default() };
+        let tier_discount = 2 * cfg.vip_fee_discount_bps;
+        let base = cfg.protocol_fee_floor_bps + tier_discount + 50;
+
+        assert_eq!(discounted_fee_bps(&cfg, base, &payer, Some(&stats), None, 0), base - tier_discount);
+        // Someone else's stats don't discount the payer's fee
+        assert_eq!(discounted_fee_bps(&cfg, base, &Pubkey::new_unique(), Some(&stats), None, 0), base);
+
+        // Stacked with a lootbox discount, the fee stops at the floor
+        let lootbox = LootboxReward {
+            owner: payer,
+            reward_type: 0,
+            reward_value: 100,
+            expires_at: 10,
+            opened: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            discounted_fee_bps(&cfg, base, &payer, Some(&stats), Some(&lootbox), 0),
+            cfg.protocol_fee_floor_bps
+        );
+        // A base rate already under the floor is left alone
+        let low = cfg.protocol_fee_floor_bps / 2;
+        assert_eq!(discounted_fee_bps(&cfg, low, &payer, Some(&stats), None, 0), low);
+    }
+
+    #[test]
+    fn compounding_restakes_under_the_cap_without_a_bonus() {
+        let mut state = ProtocolState {
+            total_staked: 1_000,
+            tvl_cap: 1_300,
+            reward_per_token_stored: 2 * 1_000_000_000_000_000_000u128,
+            ..Default::default()
+        };
+        let mut staker = StakerAccount { staked_amount: 100, ..Default::default() };
+
+        // Rewards earned on the old stake are checkpointed before it grows
+        assert_eq!(compound_into_stake(&mut state, &mut staker, 200, 0).unwrap(), 200);
+        assert_eq!((staker.staked_amount, state.total_staked), (300, 1_200));
+        assert_eq!(staker.pending_rewards, 200);
+        assert_eq!(calculate_earned(&staker, &state), 0);
+
+        // Only the headroom is restaked; nothing while a raise queue is open
+        assert_eq!(compound_into_stake(&mut state, &mut staker, 500, 0).unwrap(), 100);
+        assert_eq!(state.total_staked, state.tvl_cap);
+        state.open_tvl_raise(2_000, 3_600, 0);
+        assert_eq!(compound_into_stake(&mut state, &mut staker, 500, 10).unwrap(), 0);
+        assert_eq!(compound_into_stake(&mut state, &mut staker, 500, 3_600).unwrap(), 500);
+    }
+
+    /// SlotHashes sysvar data for `(slot, hash)` entries, newest first
+    fn slot_hashes_data(entries: &[(u64, u8)]) -> Vec<u8> {
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for &(slot, fill) in entries {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[fill; 32]);
+        }
+        data
+    }
+
+    #[test]
+    fn lootbox_roll_uses_the_slot_after_purchase() {
+        let key = Pubkey::new_unique();
+        let owner = anchor_lang::solana_program::sysvar::ID;
+        let mut lamports = 0;
+        let mut data = slot_hashes_data(&[(104, 4), (103, 3), (101, 1), (100, 0)]);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        // Exact slot, or the oldest later slot when the target was skipped
+        assert_eq!(recent_slot_hash(&info, 103).unwrap(), Some([3; 32]));
+        assert_eq!(recent_slot_hash(&info, 102).unwrap(), Some([3; 32]));
+        assert_eq!(recent_slot_hash(&info, 100).unwrap(), Some([0; 32]));
+        // Older than every entry: aged out
+        assert_eq!(recent_slot_hash(&info, 99).unwrap(), None);
+    }
+
+    #[test]
+    fn lootbox_roll_depends_on_secret_and_slot_hash() {
+        let lootbox = Pubkey::new_unique();
+        let rolls: Vec<u8> = (0..16u8).map(|i| lootbox_roll(&[i; 32], Some([7; 32]), &lootbox)).collect();
+        assert!(rolls.iter().all(|&r| r < 100));
+        assert!(rolls.iter().any(|&r| r != rolls[0]));
+        assert_eq!(lootbox_roll(&[0; 32], Some([7; 32]), &lootbox), rolls[0]);
+        assert_ne!(
+            (0..16u8).map(|i| lootbox_roll(&[i; 32], Some([8; 32]), &lootbox)).collect::<Vec<_>>(),
+            rolls
+        );
+
+        // Waiting out the slot hash only gets the worst roll, the smallest reward of each tier
+        let cfg = ConfigParams::default();
+        assert_eq!(lootbox_roll(&[0; 32], None, &lootbox), 0);
+        assert_eq!(lootbox_prize(&cfg, 0, 0), (0, 100, 604800));
+        assert_eq!(lootbox_prize(&cfg, 1, 0), (0, 300, 2592000));
+        assert_eq!(lootbox_prize(&cfg, 2, 0), (0, 1000, 7776000));
+        assert_eq!(lootbox_prize(&cfg, 0, 99), (3, cfg.lootbox_common_price, 0));
+        assert_eq!(lootbox_prize(&cfg, 2, 89), (2, 1, 0));
+    }
+
+    #[test]
+    fn lootbox_rewards_apply_until_used_or_expired() {
+        let unopened = LootboxReward::default();
+        assert_eq!(error_code(unopened.require_redeemable()), u32::from(IdlError::LootboxNotReady));
+
+        // Opened stake boost: boosts reveals until it expires, can't be redeemed
+        let mut reward = LootboxReward {
+            reward_type: 1,
+            reward_value: 500,
+            expires_at: 1_000,
+            opened: true,
+            ..Default::default()
+        };
+        assert_eq!(reward.stake_boost(10_000, 999), 500);
+        assert_eq!(reward.stake_boost(10_000, 1_000), 0);
+        assert!(!reward.is_active_fee_discount(999));
+        assert_eq!(error_code(reward.require_redeemable()), u32::from(IdlError::InvalidInput));
+
+        // Token reward redeems once
+        reward.reward_type = 3;
+        reward.expires_at = 0;
+        assert!(reward.require_redeemable().is_ok());
+        reward.used = true;
+        assert_eq!(error_code(reward.require_redeemable()), u32::from(IdlError::AlreadyClaimed));
+    }
+
+    #[test]
+    fn config_validation_rejects_overflowing_sums() {
+        let cfg = ConfigParams::default();
+        assert!(cfg.validate().is_ok());
+
+        // Shares that wrap around to 10000 must not pass
+        let mut bad = cfg;
+        bad.staker_fee_share_bps = u64::MAX;
+        bad.creator_fee_share_bps = 10001 - bad.treasury_fee_share_bps - bad.burn_fee_share_bps;
+        assert_eq!(error_code(bad.validate()), u32::from(IdlError::InvalidConfig));
+
+        let mut bad = cfg;
+        bad.bet_fee_bps = u64::MAX;
+        assert_eq!(error_code(bad.validate()), u32::from(IdlError::InvalidConfig));
+
+        let mut bad = cfg;
+        bad.vip_fee_discount_bps = u64::MAX / 2;
+        assert_eq!(error_code(bad.validate()), u32::from(IdlError::InvalidConfig));
+    }
+
+    fn proposal(quorum: u64, pass_threshold_bps: u64) -> Proposal {
+        Proposal { quorum, pass_threshold_bps, ..Default::default() }
+    }
+
+    #[test]
+    fn proposal_needs_quorum_and_strict_majority() {
+        let mut proposal = proposal(1_000, 5000);
+        assert!(!proposal.passed());
+
+        // Below quorum
+        proposal.votes_for = 900;
+        assert!(!proposal.passed());
+
+        // Quorum reached, but a tie doesn't pass
+        proposal.votes_for = 500;
+        proposal.votes_against = 500;
+        assert!(!proposal.passed());
+
+        proposal.votes_for = 501;
+        assert!(proposal.passed());
+    }
+
+    #[test]
+    fn proposal_executes_after_voting_and_timelock() {
+        let mut proposal = proposal(100, 5000);
+        proposal.voting_ends_at = 1_000;
+        proposal.executable_at = 1_000 + ConfigParams::default().proposal_execution_delay;
+        proposal.votes_for = 100;
+
+        assert_eq!(error_code(proposal.require_executable(999)), u32::from(IdlError::ProposalVotingOpen));
+        assert_eq!(error_code(proposal.require_executable(1_000)), u32::from(IdlError::TimelockNotExpired));
+        assert!(proposal.require_executable(proposal.executable_at).is_ok());
+
+        proposal.votes_against = 100;
+        assert_eq!(
+            error_code(proposal.require_executable(proposal.executable_at)),
+            u32::from(IdlError::ProposalNotPassed)
+        );
+
+        proposal.votes_against = 0;
+        proposal.executed = true;
+        assert_eq!(
+            error_code(proposal.require_executable(proposal.executable_at)),
+            u32::from(IdlError::ProposalAlreadyExecuted)
+        );
+    }
+
+    /// Lock of `ve_amount` taken at `lock_start` for `lock_duration`
+    fn ve_position(ve_amount: u64, lock_start: i64, lock_duration: i64) -> VePosition {
+        VePosition {
+            initial_ve_amount: ve_amount,
+            lock_start,
+            lock_end: lock_start + lock_duration,
+            lock_duration,
+            last_modified: lock_start,
+            ..Default::default()
+        }
+    }
+
+    fn volume_badge(ve_amount: u64, issued_at: i64) -> VolumeBadge {
+        VolumeBadge { ve_amount, issued_at, last_modified: issued_at, ..Default::default() }
+    }
+
+    #[test]
+    fn snapshot_voting_ignores_power_created_after_it() {
+        let ve = ve_position(1_000, 100, 1_000);
+        let mut badge = volume_badge(50, 100);
+
+        // Power is the decayed lock at the snapshot, not at vote time
+        assert_eq!(snapshot_voting_power(Some(&ve), Some(&badge), 600), 550);
+        assert_eq!(snapshot_voting_power(Some(&ve), None, 100), 1_000);
+
+        // A lock or badge acquired after the snapshot adds nothing
+        assert_eq!(snapshot_voting_power(Some(&ve), Some(&badge), 99), 0);
+        badge.issued_at = 700;
+        assert_eq!(snapshot_voting_power(Some(&ve), Some(&badge), 600), 500);
+    }
+
+    #[test]
+    fn snapshot_voting_ignores_lock_extensions_and_upgrades_after_it() {
+        let mut ve = ve_position(1_000, 100, 1_000);
+        let mut badge = volume_badge(50, 100);
+
+        // Doubling the lock at 700 doesn't raise power at a snapshot taken at 600
+        ve.extend_terms(2_000, 2_100, 2_000, 700);
+        assert_eq!(ve.snapshot_ve_amount(600), 500);
+        assert_eq!(ve.snapshot_ve_amount(700), 1_400);
+
+        // An upgrade neither adds to nor wipes out the badge's earlier power
+        badge.upgrade_grant(200, 700);
+        assert_eq!(snapshot_voting_power(Some(&ve), Some(&badge), 600), 550);
+        assert_eq!(snapshot_voting_power(Some(&ve), Some(&badge), 700), 1_600);
+
+        // Extended again: the terms at the first snapshot are gone, so it no longer counts
+        ve.extend_terms(2_500, 2_600, 2_500, 800);
+        assert_eq!(ve.snapshot_ve_amount(600), 0);
+        assert_eq!(ve.snapshot_ve_amount(750), 1_350);
+
+        // A migrated lock has no recorded change: its current terms hold from lock_start,
+        // and extending it keeps them for earlier snapshots
+        let mut legacy = VePosition { last_modified: 0, ..ve_position(1_000, 100, 1_000) };
+        assert_eq!(legacy.snapshot_ve_amount(600), 500);
+        legacy.extend_terms(2_000, 2_100, 2_000, 700);
+        assert_eq!(legacy.snapshot_ve_amount(600), 500);
+        assert_eq!(legacy.snapshot_ve_amount(50), 0);
+    }
+
+    #[test]
+    fn claim_events_lead_with_the_schema_version_and_full_fee_breakdown() {
+        use anchor_lang::Event;
+
+        let cfg = ConfigParams::default();
+        let fees = FeeSplit::new(&cfg, 10_000);
+        let split = fees.insurance + fees.staker + fees.creator + fees.treasury + fees.burn;
+        assert!(split <= fees.fee && fees.fee - split < 5, "{split} of {}", fees.fee);
+
+        let event = WinningsClaimed {
+            version: EVENT_VERSION,
+            market: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            bet_amount: 50_000,
+            gross_winnings: 100_000,
+            fee_bps: 1000,
+            fee: fees.fee,
+            staker_fee: fees.staker,
+            creator_fee: fees.creator,
+            treasury_fee: fees.treasury,
+            burn_fee: fees.burn,
+            insurance_fee: fees.insurance,
+            referral_fee: 0,
+            conviction_bonus: 0,
+            record_bonus: 0,
+            season_bonus: 0,
+            net_winnings: 90_000,
+            restaked: 0,
+        };
+
+        // Indexers read the discriminator, then the version, before decoding the rest
+        let data = event.data();
+        assert_eq!(data[..8], WinningsClaimed::DISCRIMINATOR);
+        assert_eq!(data[8], EVENT_VERSION);
+        let decoded = WinningsClaimed::try_from_slice(&data[8..]).unwrap();
+        assert_eq!((decoded.market, decoded.fee), (event.market, fees.fee));
+        assert_eq!(
+            (decoded.staker_fee, decoded.burn_fee, decoded.insurance_fee),
+            (fees.staker, fees.burn, fees.insurance)
+        );
+    }
 }
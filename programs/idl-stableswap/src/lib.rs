@@ -74,6 +74,9 @@ pub const MAX_SLIPPAGE_BPS: u64 = 500;
 /// Prevents front-running of amp ramps
 pub const AMP_COMMIT_DELAY: i64 = 3600;
 
+/// Event schema version, carried in every event (bump on breaking field changes)
+pub const EVENT_VERSION: u8 = 1;
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // MIGRATION POOL CONSTANTS
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -106,7 +109,7 @@ pub mod idl_stableswap {
         amplification: u64,
     ) -> Result<()> {
         require!(
-            amplification >= MIN_AMPLIFICATION && amplification <= MAX_AMPLIFICATION,
+            (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&amplification),
             StableSwapError::InvalidAmplification
         );
 
@@ -189,6 +192,15 @@ pub mod idl_stableswap {
         pool.lp_mint_bump = ctx.bumps.lp_mint;
         pool.paused = false; // Now ready for use
 
+        emit!(PoolInitialized {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            authority: pool.authority,
+            bags_mint: pool.bags_mint,
+            pump_mint: pool.pump_mint,
+            lp_mint: pool.lp_mint,
+            amplification: pool.amplification,
+        });
         msg!("IDL StableSwap initialized");
         msg!("  BAGS Mint: {}", pool.bags_mint);
         msg!("  PUMP Mint: {}", pool.pump_mint);
@@ -304,16 +316,8 @@ pub mod idl_stableswap {
                 .and_then(|v| v.checked_div(d0))
                 .ok_or(StableSwapError::MathOverflow)? as u64;
 
-            let bags_diff = if new_bags_balance > ideal_bags {
-                new_bags_balance - ideal_bags
-            } else {
-                ideal_bags - new_bags_balance
-            };
-            let pump_diff = if new_pump_balance > ideal_pump {
-                new_pump_balance - ideal_pump
-            } else {
-                ideal_pump - new_pump_balance
-            };
+            let bags_diff = new_bags_balance.abs_diff(ideal_bags);
+            let pump_diff = new_pump_balance.abs_diff(ideal_pump);
 
             // Fee on imbalance (using swap fee rate)
             let imbalance_fee_bags = (bags_diff as u128 * SWAP_FEE_BPS as u128 / 10000) as u64;
@@ -358,12 +362,23 @@ pub mod idl_stableswap {
                 .ok_or(StableSwapError::MathOverflow)?;
         }
         ctx.accounts.pool.admin_fees_bags = ctx.accounts.pool.admin_fees_bags
-            .checked_add(imbalance_fee_bags * ADMIN_FEE_PERCENT as u64 / 100)
+            .checked_add(imbalance_fee_bags * ADMIN_FEE_PERCENT / 100)
             .ok_or(StableSwapError::MathOverflow)?;
         ctx.accounts.pool.admin_fees_pump = ctx.accounts.pool.admin_fees_pump
-            .checked_add(imbalance_fee_pump * ADMIN_FEE_PERCENT as u64 / 100)
+            .checked_add(imbalance_fee_pump * ADMIN_FEE_PERCENT / 100)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(LiquidityAdded {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            bags_amount,
+            pump_amount,
+            lp_amount,
+            imbalance_fee_bags,
+            imbalance_fee_pump,
+            lp_supply: ctx.accounts.pool.lp_supply,
+        });
         msg!("Added liquidity: {} BAGS + {} PUMP = {} LP", bags_amount, pump_amount, lp_amount);
 
         Ok(())
@@ -409,11 +424,8 @@ pub mod idl_stableswap {
             // Calculate imbalance: |bags - pump| / total
             // Fee = swap_fee_bps * imbalance_ratio (max fee when fully imbalanced)
             let bags_ratio = (ctx.accounts.pool.bags_balance as u128 * 10000) / total_balance as u128;
-            let imbalance_bps = if bags_ratio > 5000 {
-                bags_ratio - 5000  // How much above 50%
-            } else {
-                5000 - bags_ratio  // How much below 50%
-            };
+            // How far bags is from 50%, in either direction
+            let imbalance_bps = bags_ratio.abs_diff(5000);
 
             // Fee scales with imbalance: at 50/50 = 0 fee, at 100/0 = full swap fee
             // fee_bps = swap_fee_bps * imbalance_bps / 5000
@@ -508,6 +520,17 @@ pub mod idl_stableswap {
             .checked_add(admin_fee_pump)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(LiquidityRemoved {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            lp_amount,
+            bags_amount,
+            pump_amount,
+            imbalance_fee_bags,
+            imbalance_fee_pump,
+            lp_supply: ctx.accounts.pool.lp_supply,
+        });
         msg!("Removed liquidity: {} LP = {} BAGS + {} PUMP (imbalance fee: {} BAGS, {} PUMP)",
              lp_amount, bags_amount, pump_amount, imbalance_fee_bags, imbalance_fee_pump);
 
@@ -639,6 +662,17 @@ pub mod idl_stableswap {
             .checked_add(lp_amount)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(LiquidityAdded {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            bags_amount: if is_bags { amount } else { 0 },
+            pump_amount: if is_bags { 0 } else { amount },
+            lp_amount,
+            imbalance_fee_bags: 0,
+            imbalance_fee_pump: 0,
+            lp_supply: ctx.accounts.pool.lp_supply,
+        });
         msg!("Added single-sided liquidity: {} {} = {} LP",
             amount,
             if is_bags { "BAGS" } else { "PUMP" },
@@ -740,6 +774,19 @@ pub mod idl_stableswap {
             .checked_add(amount_in)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(Swapped {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            bags_to_pump: true,
+            migration: true,
+            amount_in,
+            amount_out,
+            fee,
+            admin_fee,
+            bags_balance: ctx.accounts.pool.bags_balance,
+            pump_balance: ctx.accounts.pool.pump_balance,
+        });
         msg!("Migrated {} BAGS -> {} PUMP (fee: {} = 0.1337%)", amount_in, amount_out, fee);
 
         Ok(())
@@ -833,6 +880,19 @@ pub mod idl_stableswap {
             .checked_add(amount_in)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(Swapped {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            bags_to_pump: false,
+            migration: true,
+            amount_in,
+            amount_out,
+            fee,
+            admin_fee,
+            bags_balance: ctx.accounts.pool.bags_balance,
+            pump_balance: ctx.accounts.pool.pump_balance,
+        });
         msg!("Migrated {} PUMP -> {} BAGS (fee: {} = 0.1337%)", amount_in, amount_out, fee);
 
         Ok(())
@@ -890,6 +950,16 @@ pub mod idl_stableswap {
         period.total_staked = 0;
         period.bump = ctx.bumps.farming_period;
 
+        emit!(FarmingPeriodCreated {
+            version: EVENT_VERSION,
+            pool: period.pool,
+            farming_period: period.key(),
+            reward_mint: period.reward_mint,
+            start_time,
+            end_time,
+            total_rewards,
+            reward_per_second,
+        });
         msg!("Created farming period: {} rewards over {} seconds", total_rewards, duration);
 
         Ok(())
@@ -960,6 +1030,14 @@ pub mod idl_stableswap {
             .checked_add(amount)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(LpStaked {
+            version: EVENT_VERSION,
+            farming_period: ctx.accounts.farming_period.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            lp_staked: ctx.accounts.user_position.lp_staked,
+            total_staked: ctx.accounts.farming_period.total_staked,
+        });
         msg!("Staked {} LP tokens for farming", amount);
 
         Ok(())
@@ -1025,6 +1103,14 @@ pub mod idl_stableswap {
             .checked_sub(amount)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(LpUnstaked {
+            version: EVENT_VERSION,
+            farming_period: ctx.accounts.farming_period.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            lp_staked: ctx.accounts.user_position.lp_staked,
+            total_staked: ctx.accounts.farming_period.total_staked,
+        });
         msg!("Unstaked {} LP tokens from farming", amount);
 
         Ok(())
@@ -1083,6 +1169,12 @@ pub mod idl_stableswap {
             .checked_add(total_pending)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(FarmingRewardsClaimed {
+            version: EVENT_VERSION,
+            farming_period: ctx.accounts.farming_period.key(),
+            user: ctx.accounts.user.key(),
+            amount: total_pending,
+        });
         msg!("Claimed {} farming rewards", total_pending);
 
         Ok(())
@@ -1187,6 +1279,19 @@ pub mod idl_stableswap {
             .checked_add(amount_in)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(Swapped {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            bags_to_pump: true,
+            migration: false,
+            amount_in,
+            amount_out: amount_out_after_fee,
+            fee: fee as u64,
+            admin_fee: admin_fee as u64,
+            bags_balance: ctx.accounts.pool.bags_balance,
+            pump_balance: ctx.accounts.pool.pump_balance,
+        });
         msg!("Swapped {} BAGS -> {} PUMP (fee: {})", amount_in, amount_out_after_fee, fee);
 
         Ok(())
@@ -1290,6 +1395,19 @@ pub mod idl_stableswap {
             .checked_add(amount_in)
             .ok_or(StableSwapError::MathOverflow)?;
 
+        emit!(Swapped {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            bags_to_pump: false,
+            migration: false,
+            amount_in,
+            amount_out: amount_out_after_fee,
+            fee: fee as u64,
+            admin_fee: admin_fee as u64,
+            bags_balance: ctx.accounts.pool.bags_balance,
+            pump_balance: ctx.accounts.pool.pump_balance,
+        });
         msg!("Swapped {} PUMP -> {} BAGS (fee: {})", amount_in, amount_out_after_fee, fee);
 
         Ok(())
@@ -1312,6 +1430,12 @@ pub mod idl_stableswap {
         pool.pending_amp_commit = Some(commit_hash);
         pool.amp_commit_time = Some(clock.unix_timestamp);
 
+        emit!(AmpRampCommitted {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            commit_hash,
+            reveal_after: clock.unix_timestamp + AMP_COMMIT_DELAY,
+        });
         msg!("Amplification ramp committed. Reveal after {} seconds", AMP_COMMIT_DELAY);
         Ok(())
     }
@@ -1325,7 +1449,7 @@ pub mod idl_stableswap {
         salt: [u8; 32],
     ) -> Result<()> {
         require!(
-            target_amplification >= MIN_AMPLIFICATION && target_amplification <= MAX_AMPLIFICATION,
+            (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&target_amplification),
             StableSwapError::InvalidAmplification
         );
         require!(ramp_duration >= MIN_RAMP_DURATION, StableSwapError::RampTooFast);
@@ -1376,6 +1500,14 @@ pub mod idl_stableswap {
         pool.ramp_start_time = clock.unix_timestamp;
         pool.ramp_stop_time = clock.unix_timestamp + ramp_duration;
 
+        emit!(AmpRampStarted {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            initial_amplification: current_amp,
+            target_amplification,
+            ramp_start_time: pool.ramp_start_time,
+            ramp_stop_time: pool.ramp_stop_time,
+        });
         msg!(
             "Amplification ramp started: {} -> {} over {} seconds",
             current_amp,
@@ -1397,6 +1529,11 @@ pub mod idl_stableswap {
         pool.ramp_start_time = 0;
         pool.ramp_stop_time = 0;
 
+        emit!(AmpRampStopped {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            amplification: current_amp,
+        });
         msg!("Amplification ramp stopped at {}", current_amp);
         Ok(())
     }
@@ -1412,6 +1549,12 @@ pub mod idl_stableswap {
         let old_fee = pool.swap_fee_bps;
         pool.swap_fee_bps = new_fee_bps;
 
+        emit!(SwapFeeUpdated {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            old_fee_bps: old_fee,
+            new_fee_bps,
+        });
         msg!("Swap fee updated: {} -> {} bps", old_fee, new_fee_bps);
         Ok(())
     }
@@ -1422,6 +1565,11 @@ pub mod idl_stableswap {
         paused: bool,
     ) -> Result<()> {
         ctx.accounts.pool.paused = paused;
+        emit!(PoolPaused {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            paused,
+        });
         msg!("Pool paused: {}", paused);
         Ok(())
     }
@@ -1489,6 +1637,12 @@ pub mod idl_stableswap {
         ctx.accounts.pool.admin_fees_bags = ctx.accounts.pool.admin_fees_bags.saturating_sub(bags_to_withdraw);
         ctx.accounts.pool.admin_fees_pump = ctx.accounts.pool.admin_fees_pump.saturating_sub(pump_to_withdraw);
 
+        emit!(AdminFeesWithdrawn {
+            version: EVENT_VERSION,
+            pool: ctx.accounts.pool.key(),
+            bags_amount: bags_to_withdraw,
+            pump_amount: pump_to_withdraw,
+        });
         msg!("Admin fees withdrawn: {} BAGS, {} PUMP", bags_to_withdraw, pump_to_withdraw);
         Ok(())
     }
@@ -1506,6 +1660,13 @@ pub mod idl_stableswap {
         pool.pending_authority = Some(new_authority);
         pool.authority_transfer_time = Some(clock.unix_timestamp);
 
+        emit!(AuthorityTransferInitiated {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            current_authority: pool.authority,
+            new_authority,
+            executable_at: clock.unix_timestamp + AUTHORITY_TIMELOCK,
+        });
         msg!("Authority transfer initiated to {}. Timelock: 48 hours", new_authority);
         Ok(())
     }
@@ -1529,6 +1690,12 @@ pub mod idl_stableswap {
         pool.pending_authority = None;
         pool.authority_transfer_time = None;
 
+        emit!(AuthorityTransferred {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+            old_authority,
+            new_authority: pending,
+        });
         msg!("Authority transferred: {} -> {}", old_authority, pending);
         Ok(())
     }
@@ -1541,6 +1708,10 @@ pub mod idl_stableswap {
         pool.pending_authority = None;
         pool.authority_transfer_time = None;
 
+        emit!(AuthorityTransferCancelled {
+            version: EVENT_VERSION,
+            pool: pool.key(),
+        });
         msg!("Authority transfer cancelled");
         Ok(())
     }
@@ -2406,6 +2577,175 @@ pub struct UserFarmingPosition {
     pub bump: u8,
 }
 
+// ═══════════════════════════════════════════════════════════════════════════════
+// EVENTS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[event]
+pub struct PoolInitialized {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub bags_mint: Pubkey,
+    pub pump_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub amplification: u64,
+}
+
+/// Liquidity deposit. Single-sided deposits leave the other amount at zero.
+#[event]
+pub struct LiquidityAdded {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub bags_amount: u64,
+    pub pump_amount: u64,
+    pub lp_amount: u64,
+    pub imbalance_fee_bags: u64,
+    pub imbalance_fee_pump: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub lp_amount: u64,
+    pub bags_amount: u64,
+    pub pump_amount: u64,
+    pub imbalance_fee_bags: u64,
+    pub imbalance_fee_pump: u64,
+    pub lp_supply: u64,
+}
+
+/// Curve swap or 1:1 migration (`migration`). `amount_out` is what the user received.
+#[event]
+pub struct Swapped {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub bags_to_pump: bool,
+    pub migration: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub admin_fee: u64,
+    pub bags_balance: u64,
+    pub pump_balance: u64,
+}
+
+#[event]
+pub struct FarmingPeriodCreated {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub farming_period: Pubkey,
+    pub reward_mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_rewards: u64,
+    pub reward_per_second: u64,
+}
+
+#[event]
+pub struct LpStaked {
+    pub version: u8,
+    pub farming_period: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub lp_staked: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct LpUnstaked {
+    pub version: u8,
+    pub farming_period: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub lp_staked: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct FarmingRewardsClaimed {
+    pub version: u8,
+    pub farming_period: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AmpRampCommitted {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub commit_hash: [u8; 32],
+    pub reveal_after: i64,
+}
+
+#[event]
+pub struct AmpRampStarted {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub initial_amplification: u64,
+    pub target_amplification: u64,
+    pub ramp_start_time: i64,
+    pub ramp_stop_time: i64,
+}
+
+#[event]
+pub struct AmpRampStopped {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub amplification: u64,
+}
+
+#[event]
+pub struct SwapFeeUpdated {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub old_fee_bps: u64,
+    pub new_fee_bps: u64,
+}
+
+#[event]
+pub struct PoolPaused {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct AdminFeesWithdrawn {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub bags_amount: u64,
+    pub pump_amount: u64,
+}
+
+#[event]
+pub struct AuthorityTransferInitiated {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub current_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferCancelled {
+    pub version: u8,
+    pub pool: Pubkey,
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // ERRORS
 // ═══════════════════════════════════════════════════════════════════════════════